@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+use tauri::AppHandle;
+
+use super::links::{refresh_index, vault_index};
+use super::vault::{ensure_lock_owned, resolve_entry, resolve_path};
+use crate::error::{CommandError, CommandResult};
+use crate::index::VaultIndex;
+
+/// A file in an attachment folder that no note references
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedAttachment {
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Modification time in milliseconds since the Unix epoch
+    pub modified_at: Option<u64>,
+}
+
+/// Attachments moved to the trash, and the ones that could not be
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashReport {
+    pub trashed: Vec<String>,
+    pub failed: Vec<CommandError>,
+}
+
+/// List files in attachment folders that no note references
+#[tauri::command]
+pub async fn find_orphaned_attachments(
+    app: AppHandle,
+    path: String,
+) -> CommandResult<Vec<OrphanedAttachment>> {
+    log::info!("Looking for orphaned attachments in: {}", path);
+
+    let resolved = resolve_path(&app, &path)?;
+    let index = vault_index(&app, &resolved)?;
+
+    let attachments: Vec<OrphanedAttachment> = index
+        .orphaned_attachments()
+        .into_iter()
+        .filter_map(|file| {
+            let metadata = fs::metadata(&file).ok()?;
+            Some(OrphanedAttachment {
+                path: index.shown(&file),
+                size: metadata.len(),
+                modified_at: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as u64),
+            })
+        })
+        .collect();
+
+    log::info!(
+        "Found {} orphaned attachment(s) in '{}'",
+        attachments.len(),
+        path
+    );
+    Ok(attachments)
+}
+
+/// Move orphaned attachments to the system trash
+/// Each path is checked again, so a file that a note started referencing in the
+/// meantime is reported as a conflict instead of being trashed
+#[tauri::command]
+pub async fn trash_attachments(app: AppHandle, paths: Vec<String>) -> CommandResult<TrashReport> {
+    log::info!("Moving {} attachment(s) to trash", paths.len());
+
+    let mut report = TrashReport::default();
+    let mut targets: Vec<(String, PathBuf)> = Vec::new();
+    for path in paths {
+        match resolve_entry(&app, &path)
+            .and_then(|resolved| ensure_lock_owned(&app, &resolved).map(|_| resolved))
+        {
+            Ok(resolved) => targets.push((path, resolved)),
+            Err(e) => report.failed.push(e),
+        }
+    }
+    let Some((_, first)) = targets.first() else {
+        return Ok(report);
+    };
+
+    let index = vault_index(&app, first)?;
+    let targets = still_orphaned(&index, targets, &mut report);
+    drop(index);
+
+    let mut trashed: Vec<PathBuf> = Vec::new();
+    for (path, resolved) in targets {
+        match trash::delete(&resolved) {
+            Ok(_) => {
+                log::info!("Moved attachment to trash: {}", path);
+                report.trashed.push(path);
+                trashed.push(resolved);
+            }
+            Err(e) => report.failed.push(
+                CommandError::internal(format!("Failed to move '{}' to trash: {}", path, e))
+                    .logged(),
+            ),
+        }
+    }
+
+    refresh_index(&app, &trashed);
+    Ok(report)
+}
+
+/// Keep the targets that are still unreferenced attachments, reporting the
+/// others as conflicts
+fn still_orphaned(
+    index: &VaultIndex,
+    targets: Vec<(String, PathBuf)>,
+    report: &mut TrashReport,
+) -> Vec<(String, PathBuf)> {
+    let orphans: HashSet<PathBuf> = index.orphaned_attachments().into_iter().collect();
+    targets
+        .into_iter()
+        .filter(|(path, resolved)| {
+            let orphaned = orphans.contains(resolved);
+            if !orphaned {
+                report.failed.push(
+                    CommandError::conflict(
+                        path,
+                        format!("Not an unreferenced attachment: {}", path),
+                    )
+                    .logged(),
+                );
+            }
+            orphaned
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn referenced_since_the_scan_is_not_trashed() {
+        let dir = std::env::temp_dir().join(format!("yana-trash-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("attachments")).unwrap();
+        fs::write(dir.join("attachments/a.png"), b"").unwrap();
+        fs::write(dir.join("attachments/b.png"), b"").unwrap();
+        fs::write(dir.join("Note.md"), "").unwrap();
+        let root = fs::canonicalize(&dir).unwrap();
+
+        let mut index = VaultIndex::default();
+        index.rebuild(&root);
+        let target = |name: &str| (name.to_string(), root.join(name));
+        assert_eq!(index.orphaned_attachments().len(), 2);
+
+        // The note embeds one of the listed attachments before the user confirms
+        index.update_note(&root.join("Note.md"), "![[b.png]]\n");
+        let mut report = TrashReport::default();
+        let kept = still_orphaned(
+            &index,
+            vec![
+                target("attachments/a.png"),
+                target("attachments/b.png"),
+                target("Note.md"),
+            ],
+            &mut report,
+        );
+
+        assert_eq!(kept, [target("attachments/a.png")]);
+        let failed: Vec<(ErrorCode, Option<&str>)> = report
+            .failed
+            .iter()
+            .map(|e| (e.code, e.path.as_deref()))
+            .collect();
+        assert_eq!(
+            failed,
+            [
+                (ErrorCode::Conflict, Some("attachments/b.png")),
+                (ErrorCode::Conflict, Some("Note.md")),
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::{AppHandle, Manager};
+
+use super::links::{index_note, vault_index};
+use super::vault::{ensure_lock_owned, resolve_path};
+use crate::error::{CommandError, CommandResult};
+use crate::index::blocks::{self, BlockId, BlockInsertError};
+use crate::index::embeds::{ResolvedEmbed, DEFAULT_EMBED_DEPTH, MAX_EMBED_DEPTH};
+use crate::write_queue::{Edit, WriteQueue};
+
+/// Resolve an embed written in a note to the markdown it stands for
+/// `target` is what sits between `![[` and `]]`, e.g. `Other note#Section` or
+/// `Other note#^block-id`; missing notes, headings and blocks are reported in the
+/// result rather than as errors
+#[tauri::command]
+pub async fn resolve_embed(
+    app: AppHandle,
+    path: String,
+    target: String,
+    max_depth: Option<usize>,
+) -> CommandResult<ResolvedEmbed> {
+    let resolved = resolve_path(&app, &path)?;
+
+    // An alias does not change what is embedded
+    let reference = target.split('|').next().unwrap_or_default();
+    let (name, fragment) = match reference.split_once('#') {
+        Some((name, fragment)) => (name, Some(fragment)),
+        None => (reference, None),
+    };
+    let max_depth = max_depth
+        .unwrap_or(DEFAULT_EMBED_DEPTH)
+        .min(MAX_EMBED_DEPTH);
+
+    let embed = vault_index(&app, &resolved)?.resolve_embed(&resolved, name, fragment, max_depth);
+
+    if !embed.problems.is_empty() {
+        log::warn!(
+            "Embed '{}' in '{}' has {} problem(s)",
+            target,
+            path,
+            embed.problems.len()
+        );
+    }
+    Ok(embed)
+}
+
+/// Result of an insert_block_id call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockIdOutcome {
+    /// Block id without the `^`, for `[[note#^id]]` references
+    pub id: String,
+    /// 1-based line number of the marker
+    pub line: usize,
+    /// Whether the id was added, rather than already present
+    pub created: bool,
+}
+
+/// Block ids (`^id`) in a note with the text of their blocks
+#[tauri::command]
+pub async fn get_block_ids(app: AppHandle, path: String) -> CommandResult<Vec<BlockId>> {
+    let resolved = resolve_path(&app, &path)?;
+    let index = vault_index(&app, &resolved)?;
+
+    let blocks = index
+        .note(&resolved)
+        .map(|entry| entry.blocks.clone())
+        .ok_or_else(|| CommandError::not_found(&path, "Note").logged())?;
+
+    log::info!("Found {} block id(s) in '{}'", blocks.len(), path);
+    Ok(blocks)
+}
+
+/// Give the block on a line of a note a unique `^id`, or return the one it has
+/// Writes the note when an id is added; the editor should reload it
+#[tauri::command]
+pub async fn insert_block_id(
+    app: AppHandle,
+    path: String,
+    line: usize,
+) -> CommandResult<BlockIdOutcome> {
+    log::info!("Adding block id on line {} of: {}", line, path);
+
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    let insert = app.state::<WriteQueue>().exclusive(&[&resolved], || {
+        let content = fs::read_to_string(&resolved)
+            .map_err(|e| CommandError::io("Failed to read", &resolved, e).logged())?;
+        let insert = blocks::insert_block_id(&content, line).map_err(|e| match e {
+            BlockInsertError::NotABlock => CommandError::invalid_input(format!(
+                "Line {} of '{}' is not part of a paragraph, list item, heading, table or quote",
+                line, path
+            ))
+            .logged(),
+        })?;
+        let Some(updated) = &insert.content else {
+            return Ok(Edit::unchanged(insert));
+        };
+        fs::write(&resolved, updated)
+            .map_err(|e| CommandError::io("Failed to write", &resolved, e).logged())?;
+        Ok(Edit::written(insert))
+    })?;
+
+    let created = insert.content.is_some();
+    if let Some(updated) = insert.content {
+        index_note(&app, &resolved, &updated);
+        log::info!("Added block id '^{}' to '{}'", insert.id, path);
+    }
+
+    Ok(BlockIdOutcome {
+        id: insert.id,
+        line: insert.line,
+        created,
+    })
+}
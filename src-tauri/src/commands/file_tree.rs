@@ -4,15 +4,29 @@ use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::fs;
 use std::hash::{Hash, Hasher};
+use std::io::{ErrorKind, Write};
 use std::path::Path;
 use std::sync::Mutex;
 // use std::time::Duration; // Removed
 use tauri::{AppHandle, Emitter, Manager};
 
+use super::links::{
+    apply_heading_renames, index_saved_note, indexed_headings, refresh_index, vault_index,
+    write_rewrites, LinkUpdateReport,
+};
+use super::templates::new_note_content;
+use super::vault::{ensure_lock_owned, forget_lock_owner, resolve_entry, resolve_path};
+use crate::error::{CommandError, CommandResult};
+use crate::index::attachments::ATTACHMENTS_DIR;
+use crate::index::heading_links::HeadingRename;
+use crate::journal::Journal;
+use crate::vault_lock::VAULT_META_DIR;
+use crate::write_queue::{Edit, WriteOutcome, WriteQueue};
+
 /// Supported file extensions for the file tree
 const SUPPORTED_EXTENSIONS: &[&str] = &["md", "MD", "png", "jpg", "jpeg", "gif", "svg", "webp"];
 
-/// Debounce window in milliseconds for file watcher events (Unused now)
+// Debounce window in milliseconds for file watcher events (Unused now)
 // const DEBOUNCE_MS: u64 = 500;
 
 /// Event name for file system changes emitted to frontend
@@ -46,7 +60,7 @@ pub struct FileNode {
 
 impl FileNode {
     /// Create a new FileNode from a path
-    fn new(path: &Path, node_type: &str, children: Option<Vec<FileNode>>) -> Self {
+    pub(crate) fn new(path: &Path, node_type: &str, children: Option<Vec<FileNode>>) -> Self {
         let path_str = path.to_string_lossy().to_string();
         let name = path
             .file_name()
@@ -68,11 +82,33 @@ impl FileNode {
     }
 }
 
+/// Result of rename_path: the renamed node and the notes whose links were updated
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameOutcome {
+    pub node: FileNode,
+    pub links: LinkUpdateReport,
+}
+
+/// Result of write_file: the queued write, plus headings renamed by the new content
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveOutcome {
+    #[serde(flatten)]
+    pub write: WriteOutcome,
+    /// Renamed headings of the note that links in other notes still use
+    pub heading_renames: Vec<HeadingRename>,
+    /// Links updated to follow the renamed headings, when requested
+    pub links: Option<LinkUpdateReport>,
+}
+
 /// Response from read_file including timing information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadFileResponse {
     pub content: String,
     pub duration_ms: f64,
+    /// Sequence the next write_file of this path must exceed
+    pub seq: u64,
 }
 
 /// Check if a file has a supported extension
@@ -84,12 +120,12 @@ fn is_supported_file(path: &Path) -> bool {
 }
 
 /// Recursively scan a directory and build the file tree
-fn scan_directory_recursive(dir_path: &Path) -> Result<Vec<FileNode>, String> {
-    let entries = fs::read_dir(dir_path).map_err(|e| {
-        let error_msg = format!("Failed to read directory '{}': {}", dir_path.display(), e);
-        log::error!("{}", error_msg);
-        error_msg
-    })?;
+///
+/// `dir_path` is the resolved directory that is read; nodes are named after
+/// `node_path`, the same directory as the frontend knows it.
+fn scan_directory_recursive(dir_path: &Path, node_path: &Path) -> CommandResult<Vec<FileNode>> {
+    let entries = fs::read_dir(dir_path)
+        .map_err(|e| CommandError::io("Failed to read directory", dir_path, e).logged())?;
 
     let mut folders: Vec<FileNode> = Vec::new();
     let mut files: Vec<FileNode> = Vec::new();
@@ -119,29 +155,37 @@ fn scan_directory_recursive(dir_path: &Path) -> Result<Vec<FileNode>, String> {
             continue;
         }
 
-        if path.is_dir() {
-            // Recursively scan subdirectory - log but don't fail on permission errors
-            match scan_directory_recursive(&path) {
-                Ok(children) => {
-                    let node = FileNode::new(&path, "folder", Some(children));
-                    folders.push(node);
-                }
-                Err(e) => {
-                    log::warn!("Failed to scan subdirectory '{}': {}", path.display(), e);
-                    // Still add the folder node, but with empty children
-                    let node = FileNode::new(&path, "folder", Some(Vec::new()));
-                    folders.push(node);
+        let node_child = node_path.join(&file_name);
+        // Symlinked folders are not followed, they could lead out of the vault
+        // or loop back into it
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => {
+                // Recursively scan subdirectory - log but don't fail on permission errors
+                match scan_directory_recursive(&path, &node_child) {
+                    Ok(children) => {
+                        let node = FileNode::new(&node_child, "folder", Some(children));
+                        folders.push(node);
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to scan subdirectory '{}': {}", path.display(), e);
+                        // Still add the folder node, but with empty children
+                        let node = FileNode::new(&node_child, "folder", Some(Vec::new()));
+                        folders.push(node);
+                    }
                 }
             }
-        } else if path.is_file() && is_supported_file(&path) {
-            let node = FileNode::new(&path, "file", None);
-            files.push(node);
+            Ok(kind) if kind.is_symlink() && path.is_dir() => {}
+            Ok(_) if path.is_file() && is_supported_file(&path) => {
+                let node = FileNode::new(&node_child, "file", None);
+                files.push(node);
+            }
+            _ => {}
         }
     }
 
     // Sort folders and files alphabetically (case-insensitive)
-    folders.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    files.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    folders.sort_by_key(|node| node.name.to_lowercase());
+    files.sort_by_key(|node| node.name.to_lowercase());
 
     // Combine: folders first, then files
     folders.extend(files);
@@ -150,30 +194,29 @@ fn scan_directory_recursive(dir_path: &Path) -> Result<Vec<FileNode>, String> {
 
 /// Scan a directory and return hierarchical file tree
 #[tauri::command]
-pub async fn scan_directory(path: String) -> Result<Vec<FileNode>, String> {
+pub async fn scan_directory(app: AppHandle, path: String) -> CommandResult<Vec<FileNode>> {
     log::info!("Scanning directory: {}", path);
 
-    let dir_path = Path::new(&path);
+    let resolved = resolve_path(&app, &path)?;
 
-    if !dir_path.exists() {
-        let error_msg = format!("Directory does not exist: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+    if !resolved.exists() {
+        return Err(CommandError::not_found(&path, "Directory").logged());
     }
 
-    if !dir_path.is_dir() {
-        let error_msg = format!("Path is not a directory: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+    if !resolved.is_dir() {
+        return Err(CommandError::not_a_directory(&path).logged());
     }
 
-    match scan_directory_recursive(dir_path) {
+    match scan_directory_recursive(&resolved, Path::new(&path)) {
         Ok(nodes) => {
             log::info!(
                 "Successfully scanned directory '{}' with {} top-level entries",
                 path,
                 nodes.len()
             );
+            // Index the vault the first time it is scanned; afterwards the
+            // watcher and the file commands keep the index current
+            drop(vault_index(&app, &resolved)?);
             Ok(nodes)
         }
         Err(e) => {
@@ -185,24 +228,27 @@ pub async fn scan_directory(path: String) -> Result<Vec<FileNode>, String> {
 
 /// Read file contents as string with timing information
 #[tauri::command]
-pub async fn read_file(path: String) -> Result<ReadFileResponse, String> {
+pub async fn read_file(app: AppHandle, path: String) -> CommandResult<ReadFileResponse> {
     log::info!("Reading file: {}", path);
 
-    let file_path = Path::new(&path);
+    let resolved = resolve_path(&app, &path)?;
+    let file_path = resolved.as_path();
 
     if !file_path.exists() {
-        let error_msg = format!("File does not exist: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+        return Err(CommandError::not_found(&path, "File").logged());
     }
 
     if !file_path.is_file() {
-        let error_msg = format!("Path is not a file: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+        return Err(CommandError::not_a_file(&path).logged());
     }
 
     let start = std::time::Instant::now();
+    // Taken before reading, so a backend edit landing in between makes the
+    // editor's next save stale rather than letting it overwrite the edit
+    let seq = app
+        .state::<WriteQueue>()
+        .latest_seq(file_path)
+        .map_err(CommandError::logged)?;
 
     match fs::read_to_string(file_path) {
         Ok(content) => {
@@ -219,22 +265,31 @@ pub async fn read_file(path: String) -> Result<ReadFileResponse, String> {
             Ok(ReadFileResponse {
                 content,
                 duration_ms,
+                seq,
             })
         }
-        Err(e) => {
-            let error_msg = format!("Failed to read file '{}': {}", path, e);
-            log::error!("{}", error_msg);
-            Err(error_msg)
-        }
+        Err(e) => Err(CommandError::io("Failed to read file", &path, e).logged()),
     }
 }
 
 /// Write content to a file
+/// Writes to the same path are serialized through the write queue; `seq` orders
+/// them so a slow, older autosave can never overwrite a newer one. Headings renamed
+/// by the new content are reported, and with `update_heading_links` the links in
+/// other notes are updated to follow them
 #[tauri::command]
-pub async fn write_file(path: String, content: String) -> Result<(), String> {
+pub async fn write_file(
+    app: AppHandle,
+    path: String,
+    content: String,
+    seq: Option<u64>,
+    update_heading_links: Option<bool>,
+) -> CommandResult<SaveOutcome> {
     log::info!("Writing to file: {}", path);
 
-    let file_path = Path::new(&path);
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    let file_path = resolved.as_path();
 
     // Basic validation to ensure we're writing to a valid path structure
     // We don't strictly check for file existence because we might want to create it,
@@ -243,72 +298,144 @@ pub async fn write_file(path: String, content: String) -> Result<(), String> {
     // Let's at least check parent directory exists to avoid random writes.
     if let Some(parent) = file_path.parent() {
         if !parent.exists() {
-            let error_msg = format!("Parent directory does not exist: {}", parent.display());
-            log::error!("{}", error_msg);
-            return Err(error_msg);
+            return Err(CommandError::not_found(parent, "Parent directory").logged());
         }
     }
 
-    match fs::write(file_path, content) {
-        Ok(_) => {
-            log::info!("Successfully wrote to file '{}'", path);
-            Ok(())
-        }
-        Err(e) => {
-            let error_msg = format!("Failed to write file '{}': {}", path, e);
-            log::error!("{}", error_msg);
-            Err(error_msg)
+    let previous_headings = indexed_headings(&app, file_path);
+    let queue = app.state::<WriteQueue>();
+    let journal = app.state::<Journal>();
+    match queue.write(file_path, content.clone(), seq) {
+        Ok(outcome) => {
+            let mut heading_renames = Vec::new();
+            let mut links = None;
+            // The checkpoint is no longer needed once its content is on disk
+            if !outcome.superseded {
+                journal.discard_if_persisted(file_path, &content);
+                heading_renames =
+                    index_saved_note(&app, file_path, previous_headings.as_deref(), &content);
+                if update_heading_links.unwrap_or(false) && !heading_renames.is_empty() {
+                    // The note itself is saved; failing to follow the rename is only logged
+                    links = apply_heading_renames(&app, file_path).ok();
+                }
+            }
+            if outcome.superseded {
+                log::info!(
+                    "Write #{} to '{}' superseded, #{} is on disk",
+                    outcome.seq,
+                    path,
+                    outcome.persisted_seq
+                );
+            } else {
+                log::info!("Successfully wrote to file '{}' (#{})", path, outcome.seq);
+            }
+            Ok(SaveOutcome {
+                write: outcome,
+                heading_renames,
+                links,
+            })
         }
+        Err(e) => Err(e.logged()),
     }
 }
 
-/// Create a new markdown note in the specified directory
-#[tauri::command]
-pub async fn create_new_note(path: String) -> Result<FileNode, String> {
-    log::info!("Creating new note in: {}", path);
-
-    let dir_path = Path::new(&path);
-    if !dir_path.exists() || !dir_path.is_dir() {
-        let error_msg = format!("Invalid parent directory: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
-    }
-
+/// First free note name like "Untitled.md", "Untitled 1.md", etc. in a directory
+/// A dangling symlink takes up its name too
+fn untitled_name(dir_path: &Path) -> String {
     let mut file_name = "Untitled.md".to_string();
-    let mut file_path = dir_path.join(&file_name);
     let mut counter = 1;
-
-    // Find a unique name like "Untitled.md", "Untitled 1.md", etc.
-    while file_path.exists() {
+    while fs::symlink_metadata(dir_path.join(&file_name)).is_ok() {
         file_name = format!("Untitled {}.md", counter);
-        file_path = dir_path.join(&file_name);
         counter += 1;
     }
+    file_name
+}
 
-    match fs::write(&file_path, "") {
-        Ok(_) => {
-            log::info!("Successfully created new note: {}", file_path.display());
-            // Return the FileNode for the newly created file
-            Ok(FileNode::new(&file_path, "file", None))
-        }
-        Err(e) => {
-            let error_msg = format!("Failed to create new note: {}", e);
-            log::error!("{}", error_msg);
-            Err(error_msg)
+/// Create a note under the first free "Untitled" name in a directory, returning
+/// its file name
+///
+/// The file is created with `create_new`, so a file that takes the name after it
+/// was picked, e.g. from a sync client or a second click, is never replaced; the
+/// next free name is tried instead. `content` gives the note's content for the
+/// path it is created at.
+pub(crate) fn create_untitled(
+    queue: &WriteQueue,
+    dir_path: &Path,
+    content: impl Fn(&Path) -> String,
+) -> CommandResult<String> {
+    loop {
+        let file_name = untitled_name(dir_path);
+        let file_path = dir_path.join(&file_name);
+        let content = content(&file_path);
+
+        let created = queue.exclusive(&[&file_path], || {
+            let file = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&file_path);
+            match file.and_then(|mut file| file.write_all(content.as_bytes())) {
+                Ok(_) => Ok(Edit::written(true)),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(Edit::unchanged(false)),
+                Err(e) => {
+                    Err(CommandError::io("Failed to create new note", &file_path, e).logged())
+                }
+            }
+        })?;
+        if created {
+            return Ok(file_name);
         }
+        log::info!(
+            "'{}' was created meanwhile, trying the next name",
+            file_path.display()
+        );
+    }
+}
+
+/// Create a new markdown note in the specified directory
+/// The note starts from the template of the folder, or of the closest parent
+/// folder that has one, with its variables expanded
+#[tauri::command]
+pub async fn create_new_note(app: AppHandle, path: String) -> CommandResult<FileNode> {
+    log::info!("Creating new note in: {}", path);
+
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    let dir_path = resolved.as_path();
+    if !dir_path.exists() {
+        return Err(CommandError::not_found(&path, "Parent directory").logged());
     }
+    if !dir_path.is_dir() {
+        return Err(CommandError::not_a_directory(&path).logged());
+    }
+
+    let file_name = create_untitled(&app.state::<WriteQueue>(), dir_path, |file_path| {
+        new_note_content(&app, dir_path, file_path)
+    })?;
+    let file_path = dir_path.join(&file_name);
+    log::info!("Successfully created new note: {}", file_path.display());
+    refresh_index(&app, [&file_path]);
+
+    // Return the FileNode for the newly created file
+    Ok(FileNode::new(
+        &Path::new(&path).join(&file_name),
+        "file",
+        None,
+    ))
 }
 
 /// Create a new folder in the specified directory
 #[tauri::command]
-pub async fn create_new_folder(path: String) -> Result<FileNode, String> {
+pub async fn create_new_folder(app: AppHandle, path: String) -> CommandResult<FileNode> {
     log::info!("Creating new folder in: {}", path);
 
-    let dir_path = Path::new(&path);
-    if !dir_path.exists() || !dir_path.is_dir() {
-        let error_msg = format!("Invalid parent directory: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    let dir_path = resolved.as_path();
+    if !dir_path.exists() {
+        return Err(CommandError::not_found(&path, "Parent directory").logged());
+    }
+    if !dir_path.is_dir() {
+        return Err(CommandError::not_a_directory(&path).logged());
     }
 
     let mut folder_name = "New Folder".to_string();
@@ -325,30 +452,31 @@ pub async fn create_new_folder(path: String) -> Result<FileNode, String> {
     match fs::create_dir(&folder_path) {
         Ok(_) => {
             log::info!("Successfully created new folder: {}", folder_path.display());
+            refresh_index(&app, [&folder_path]);
             // Return the FileNode for the newly created folder
-            Ok(FileNode::new(&folder_path, "folder", Some(Vec::new())))
-        }
-        Err(e) => {
-            let error_msg = format!("Failed to create new folder: {}", e);
-            log::error!("{}", error_msg);
-            Err(error_msg)
+            Ok(FileNode::new(
+                &Path::new(&path).join(&folder_name),
+                "folder",
+                Some(Vec::new()),
+            ))
         }
+        Err(e) => Err(CommandError::io("Failed to create new folder", &folder_path, e).logged()),
     }
 }
 
 /// Delete a file or directory at the specified path
 #[tauri::command]
-pub async fn delete_path(path: String) -> Result<(), String> {
+pub async fn delete_path(app: AppHandle, path: String) -> CommandResult<()> {
     log::info!("Deleting path: {}", path);
 
-    let target_path = Path::new(&path);
-    if !target_path.exists() {
-        let error_msg = format!("Path does not exist: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+    let resolved = resolve_entry(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    let target_path = resolved.as_path();
+    if target_path.symlink_metadata().is_err() {
+        return Err(CommandError::not_found(&path, "Path").logged());
     }
 
-    let result = if target_path.is_dir() {
+    let result = if target_path.is_dir() && !target_path.is_symlink() {
         fs::remove_dir_all(target_path)
     } else {
         fs::remove_file(target_path)
@@ -357,35 +485,35 @@ pub async fn delete_path(path: String) -> Result<(), String> {
     match result {
         Ok(_) => {
             log::info!("Successfully deleted: {}", path);
+            refresh_index(&app, [&resolved]);
             Ok(())
         }
-        Err(e) => {
-            let error_msg = format!("Failed to delete '{}': {}", path, e);
-            log::error!("{}", error_msg);
-            Err(error_msg)
-        }
+        Err(e) => Err(CommandError::io("Failed to delete", &path, e).logged()),
     }
 }
 
 /// Duplicate a file at the specified path
 #[tauri::command]
-pub async fn duplicate_file(path: String) -> Result<FileNode, String> {
+pub async fn duplicate_file(app: AppHandle, path: String) -> CommandResult<FileNode> {
     log::info!("Duplicating file: {}", path);
 
-    let source_path = Path::new(&path);
-    if !source_path.exists() || !source_path.is_file() {
-        let error_msg = format!("File does not exist or is not a file: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    let source_path = resolved.as_path();
+    if !source_path.exists() {
+        return Err(CommandError::not_found(&path, "File").logged());
+    }
+    if !source_path.is_file() {
+        return Err(CommandError::not_a_file(&path).logged());
     }
 
-    let parent = source_path
-        .parent()
-        .ok_or_else(|| "Could not determine parent directory".to_string())?;
+    let parent = source_path.parent().ok_or_else(|| {
+        CommandError::invalid_path(&path, "Could not determine parent directory").logged()
+    })?;
     let file_stem = source_path
         .file_stem()
         .and_then(|s| s.to_str())
-        .ok_or_else(|| "Invalid filename stem".to_string())?;
+        .ok_or_else(|| CommandError::invalid_path(&path, "Invalid filename stem").logged())?;
     let extension = source_path
         .extension()
         .and_then(|e| e.to_str())
@@ -414,79 +542,98 @@ pub async fn duplicate_file(path: String) -> Result<FileNode, String> {
     match fs::copy(source_path, &target_path) {
         Ok(_) => {
             log::info!("Successfully duplicated file to: {}", target_path.display());
-            Ok(FileNode::new(&target_path, "file", None))
-        }
-        Err(e) => {
-            let error_msg = format!("Failed to duplicate file: {}", e);
-            log::error!("{}", error_msg);
-            Err(error_msg)
+            refresh_index(&app, [&target_path]);
+            let node_path = Path::new(&path).with_file_name(&new_file_name);
+            Ok(FileNode::new(&node_path, "file", None))
         }
+        Err(e) => Err(CommandError::io("Failed to duplicate file", &path, e).logged()),
     }
 }
 
-/// Rename a file or directory at the specified path
+/// Rename or move a file or directory at the specified path
+/// With `update_links`, links pointing at the moved files are rewritten across the
+/// vault, along with the relative links inside moved notes
 #[tauri::command]
-pub async fn rename_path(path: String, new_path: String) -> Result<FileNode, String> {
+pub async fn rename_path(
+    app: AppHandle,
+    path: String,
+    new_path: String,
+    update_links: Option<bool>,
+) -> CommandResult<RenameOutcome> {
     log::info!("Renaming: {} to {}", path, new_path);
 
-    let source_path = Path::new(&path);
-    let target_path = Path::new(&new_path);
+    let source_resolved = resolve_entry(&app, &path)?;
+    let target_resolved = resolve_entry(&app, &new_path)?;
+    ensure_lock_owned(&app, &source_resolved)?;
+    ensure_lock_owned(&app, &target_resolved)?;
+    let source_path = source_resolved.as_path();
+    let target_path = target_resolved.as_path();
 
-    if !source_path.exists() {
-        let error_msg = format!("Source path does not exist: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+    if source_path.symlink_metadata().is_err() {
+        return Err(CommandError::not_found(&path, "Source path").logged());
     }
 
-    if target_path.exists() {
-        let error_msg = format!("Target path already exists: {}", new_path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+    if target_path.symlink_metadata().is_ok() {
+        return Err(CommandError::already_exists(&new_path).logged());
     }
 
-    match fs::rename(source_path, target_path) {
-        Ok(_) => {
-            log::info!("Successfully renamed '{}' to '{}'", path, new_path);
-            let node_type = if target_path.is_dir() {
-                "folder"
-            } else {
-                "file"
-            };
-            Ok(FileNode::new(target_path, node_type, None))
-        }
+    // Links have to be captured while they still resolve to the old location
+    let plans = if update_links.unwrap_or(false) {
+        vault_index(&app, source_path)?.plan_move(source_path)
+    } else {
+        Vec::new()
+    };
+
+    if let Err(e) = fs::rename(source_path, target_path) {
+        return Err(CommandError::io("Failed to rename", &path, e).logged());
+    }
+    log::info!("Successfully renamed '{}' to '{}'", path, new_path);
+    refresh_index(&app, [&source_resolved, &target_resolved]);
+
+    let rewrites = match vault_index(&app, target_path) {
+        Ok(index) => plans
+            .iter()
+            .filter_map(|plan| index.rewrite_moved(plan, source_path, target_path))
+            .collect(),
         Err(e) => {
-            let error_msg = format!("Failed to rename file: {}", e);
-            log::error!("{}", error_msg);
-            Err(error_msg)
+            log::error!("Failed to update links after rename: {}", e);
+            Vec::new()
         }
-    }
+    };
+    let links = write_rewrites(&app, rewrites);
+
+    let node_type = if target_path.is_dir() {
+        "folder"
+    } else {
+        "file"
+    };
+    Ok(RenameOutcome {
+        node: FileNode::new(Path::new(&new_path), node_type, None),
+        links,
+    })
 }
 
 /// Start watching a directory for changes
 #[tauri::command]
-pub async fn start_watching(app: AppHandle, path: String) -> Result<(), String> {
+pub async fn start_watching(app: AppHandle, path: String) -> CommandResult<()> {
     log::info!("Starting file watcher for: {}", path);
 
-    let watch_path = Path::new(&path);
+    // Watch the resolved path so event paths match the vault index
+    let resolved = resolve_path(&app, &path)?;
+    let watch_path = resolved.as_path();
 
     if !watch_path.exists() {
-        let error_msg = format!("Directory does not exist: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+        return Err(CommandError::not_found(&path, "Directory").logged());
     }
 
     if !watch_path.is_dir() {
-        let error_msg = format!("Path is not a directory: {}", path);
-        log::error!("{}", error_msg);
-        return Err(error_msg);
+        return Err(CommandError::not_a_directory(&path).logged());
     }
 
     // Get the watcher state from app state
     let state = app.state::<Mutex<WatcherState>>();
     let mut watcher_state = state.lock().map_err(|e| {
-        let error_msg = format!("Failed to lock watcher state: {}", e);
-        log::error!("{}", error_msg);
-        error_msg
+        CommandError::internal(format!("Failed to lock watcher state: {}", e)).logged()
     })?;
 
     // Stop existing watcher if any
@@ -499,12 +646,20 @@ pub async fn start_watching(app: AppHandle, path: String) -> Result<(), String>
     // Clone app handle for the event callback
     let app_handle = app.clone();
     let watched_path = path.clone();
+    let event_root = resolved.clone();
+    let shown_root = path.clone();
 
     // Create raw watcher
     let mut watcher =
         notify::recommended_watcher(move |res: Result<notify::Event, notify::Error>| {
             match res {
                 Ok(event) => {
+                    // Content changes only matter to the index, the tree ignores them
+                    if !matches!(event.kind, notify::EventKind::Access(_)) {
+                        refresh_index(&app_handle, &event.paths);
+                        forget_lock_owner(&app_handle, &event.paths);
+                    }
+
                     let event_type = match event.kind {
                         notify::EventKind::Create(_) => Some("create"),
                         notify::EventKind::Remove(_) => Some("delete"),
@@ -516,9 +671,19 @@ pub async fn start_watching(app: AppHandle, path: String) -> Result<(), String>
 
                     if let Some(event_type_str) = event_type {
                         for path in event.paths {
+                            // Yana's own bookkeeping (locks etc.) is not part of the tree
+                            if path.components().any(|c| c.as_os_str() == VAULT_META_DIR) {
+                                continue;
+                            }
+
+                            // Events carry resolved paths, the tree knows the vault as given
+                            let shown = match path.strip_prefix(&event_root) {
+                                Ok(relative) => Path::new(&shown_root).join(relative),
+                                Err(_) => path.clone(),
+                            };
                             let file_event = FileEvent {
                                 event_type: event_type_str.to_string(),
-                                path: path.to_string_lossy().to_string(),
+                                path: shown.to_string_lossy().to_string(),
                             };
 
                             log::debug!(
@@ -545,18 +710,14 @@ pub async fn start_watching(app: AppHandle, path: String) -> Result<(), String>
             }
         })
         .map_err(|e| {
-            let error_msg = format!("Failed to create file watcher: {}", e);
-            log::error!("{}", error_msg);
-            error_msg
+            CommandError::internal(format!("Failed to create file watcher: {}", e)).logged()
         })?;
 
     // Start watching the directory
     watcher
         .watch(watch_path, RecursiveMode::Recursive)
         .map_err(|e| {
-            let error_msg = format!("Failed to start watching '{}': {}", path, e);
-            log::error!("{}", error_msg);
-            error_msg
+            CommandError::internal(format!("Failed to start watching '{}': {}", path, e)).logged()
         })?;
 
     log::info!("Successfully started watching directory: {}", path);
@@ -570,12 +731,10 @@ pub async fn start_watching(app: AppHandle, path: String) -> Result<(), String>
 
 /// Stop watching the current directory
 #[tauri::command]
-pub async fn stop_watching(app: AppHandle) -> Result<(), String> {
+pub async fn stop_watching(app: AppHandle) -> CommandResult<()> {
     let state = app.state::<Mutex<WatcherState>>();
     let mut watcher_state = state.lock().map_err(|e| {
-        let error_msg = format!("Failed to lock watcher state: {}", e);
-        log::error!("{}", error_msg);
-        error_msg
+        CommandError::internal(format!("Failed to lock watcher state: {}", e)).logged()
     })?;
 
     if let Some(watching_path) = &watcher_state.watching_path {
@@ -598,34 +757,49 @@ pub async fn stop_watching(app: AppHandle) -> Result<(), String> {
 /// Returns the relative path to the saved image (e.g., "attachments/image.png")
 #[tauri::command]
 pub async fn save_image_to_attachments(
+    app: AppHandle,
     md_file_path: String,
     image_name: String,
     image_data: Vec<u8>,
-) -> Result<String, String> {
+) -> CommandResult<String> {
     log::info!(
         "Saving image '{}' for markdown file: {}",
         image_name,
         md_file_path
     );
 
-    let md_path = Path::new(&md_file_path);
+    let resolved = resolve_path(&app, &md_file_path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    let md_path = resolved.as_path();
+
+    // The image name must be a plain file name so it cannot escape the attachments folder
+    if Path::new(&image_name).file_name() != Some(std::ffi::OsStr::new(&image_name)) {
+        return Err(
+            CommandError::invalid_input(format!("Invalid image name: {}", image_name)).logged(),
+        );
+    }
 
     // Get the parent directory of the markdown file
     let parent_dir = md_path.parent().ok_or_else(|| {
-        let error_msg = "Could not determine parent directory of markdown file".to_string();
-        log::error!("{}", error_msg);
-        error_msg
+        CommandError::invalid_path(
+            &md_file_path,
+            "Could not determine parent directory of markdown file",
+        )
+        .logged()
     })?;
 
-    // Create the attachments folder path
-    let attachments_dir = parent_dir.join("attachments");
+    // Create the attachments folder path; it may be a symlink leading out of the vault
+    let attachments_dir = resolve_path(&app, &parent_dir.join(ATTACHMENTS_DIR).to_string_lossy())?;
 
     // Create the attachments folder if it doesn't exist
     if !attachments_dir.exists() {
         fs::create_dir(&attachments_dir).map_err(|e| {
-            let error_msg = format!("Failed to create attachments directory: {}", e);
-            log::error!("{}", error_msg);
-            error_msg
+            CommandError::io(
+                "Failed to create attachments directory",
+                &attachments_dir,
+                e,
+            )
+            .logged()
         })?;
         log::info!(
             "Created attachments directory: {}",
@@ -638,7 +812,7 @@ pub async fn save_image_to_attachments(
     if initial_path.exists() {
         if let Ok(existing_data) = fs::read(&initial_path) {
             if existing_data == image_data {
-                let relative_path = format!("attachments/{}", image_name);
+                let relative_path = format!("{}/{}", ATTACHMENTS_DIR, image_name);
                 log::info!(
                     "Reusing existing identical image: {}",
                     initial_path.display()
@@ -664,7 +838,7 @@ pub async fn save_image_to_attachments(
 
         if let Ok(existing_data) = fs::read(&numbered_path) {
             if existing_data == image_data {
-                let relative_path = format!("attachments/{}", numbered_name);
+                let relative_path = format!("{}/{}", ATTACHMENTS_DIR, numbered_name);
                 log::info!(
                     "Reusing existing identical image: {}",
                     numbered_path.display()
@@ -686,15 +860,95 @@ pub async fn save_image_to_attachments(
         counter += 1;
     }
 
+    // A dangling symlink by that name would not exist, but a write would follow it
+    let image_path = resolve_path(&app, &image_path.to_string_lossy())?;
+
     // Write the image data to the file
-    fs::write(&image_path, &image_data).map_err(|e| {
-        let error_msg = format!("Failed to save image: {}", e);
-        log::error!("{}", error_msg);
-        error_msg
-    })?;
+    fs::write(&image_path, &image_data)
+        .map_err(|e| CommandError::io("Failed to save image", &image_path, e).logged())?;
 
-    let relative_path = format!("attachments/{}", final_name);
+    let relative_path = format!("{}/{}", ATTACHMENTS_DIR, final_name);
     log::info!("Successfully saved image to: {}", image_path.display());
+    refresh_index(&app, [&image_path]);
 
     Ok(relative_path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn scan_skips_symlinked_folders() {
+        let dir = std::env::temp_dir().join(format!("yana-scan-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let vault = dir.join("vault");
+        fs::create_dir_all(vault.join("notes")).unwrap();
+        fs::create_dir_all(dir.join("outside")).unwrap();
+        fs::write(dir.join("outside/secret.md"), "").unwrap();
+        fs::write(vault.join("notes/Note.md"), "").unwrap();
+        std::os::unix::fs::symlink(dir.join("outside"), vault.join("escape")).unwrap();
+
+        // Nodes carry the path the frontend asked for, not the resolved one
+        let shown = Path::new("/shown/vault");
+        let nodes = scan_directory_recursive(&vault, shown).unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].path, "/shown/vault/notes");
+        let children = nodes[0].children.as_ref().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].path, "/shown/vault/notes/Note.md");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn untitled_notes_never_replace_a_file() {
+        let dir = std::env::temp_dir().join(format!("yana-untitled-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Untitled.md"), "mine").unwrap();
+        let queue = WriteQueue::default();
+
+        // Another client creates the picked name before the note is written
+        let picked = std::cell::Cell::new(0);
+        let name = create_untitled(&queue, &dir, |path| {
+            picked.set(picked.get() + 1);
+            if picked.get() == 1 {
+                fs::write(path, "synced").unwrap();
+            }
+            format!("# {}", path.file_stem().unwrap().to_string_lossy())
+        })
+        .unwrap();
+
+        assert_eq!(name, "Untitled 2.md");
+        assert_eq!(picked.get(), 2);
+        assert_eq!(fs::read_to_string(dir.join("Untitled.md")).unwrap(), "mine");
+        assert_eq!(
+            fs::read_to_string(dir.join("Untitled 1.md")).unwrap(),
+            "synced"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("Untitled 2.md")).unwrap(),
+            "# Untitled 2"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dangling_symlinks_take_up_untitled_names() {
+        let dir = std::env::temp_dir().join(format!("yana-untitled-link-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        std::os::unix::fs::symlink(dir.join("gone.md"), dir.join("Untitled.md")).unwrap();
+
+        let queue = WriteQueue::default();
+        let name = create_untitled(&queue, &dir, |_| String::new()).unwrap();
+        assert_eq!(name, "Untitled 1.md");
+        assert!(!dir.join("gone.md").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
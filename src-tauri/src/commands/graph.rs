@@ -0,0 +1,45 @@
+use tauri::AppHandle;
+
+use super::links::vault_index;
+use super::vault::resolve_path;
+use crate::error::CommandResult;
+use crate::index::graph::{GraphData, GraphFilter, DEFAULT_LOCAL_DEPTH};
+
+/// Build the link graph of a vault for the graph view
+/// With `focus` only the neighbourhood of that note is returned (local mode),
+/// otherwise the whole vault; `folder` and `tag` narrow down the notes either way
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn get_graph_data(
+    app: AppHandle,
+    path: String,
+    focus: Option<String>,
+    depth: Option<usize>,
+    folder: Option<String>,
+    tag: Option<String>,
+    include_tags: Option<bool>,
+    include_attachments: Option<bool>,
+) -> CommandResult<GraphData> {
+    log::info!("Building graph for: {}", path);
+
+    let resolved = resolve_path(&app, &path)?;
+    let filter = GraphFilter {
+        focus: focus.map(|f| resolve_path(&app, &f)).transpose()?,
+        depth: depth.unwrap_or(DEFAULT_LOCAL_DEPTH),
+        folder: folder.map(|f| resolve_path(&app, &f)).transpose()?,
+        tag: tag.filter(|t| !t.trim().is_empty()),
+        include_tags: include_tags.unwrap_or(false),
+        include_attachments: include_attachments.unwrap_or(false),
+    };
+
+    let start = std::time::Instant::now();
+    let graph = vault_index(&app, &resolved)?.graph(&filter);
+
+    log::info!(
+        "Built graph with {} node(s) and {} edge(s) in {:.2}ms",
+        graph.nodes.len(),
+        graph.edges.len(),
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(graph)
+}
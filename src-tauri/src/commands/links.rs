@@ -0,0 +1,484 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager};
+
+use super::vault::{ensure_lock_owned, resolve_path, shown_path, vault_root};
+use crate::error::{CommandError, CommandResult};
+use crate::index::convert::{ConvertedLink, SkippedLink};
+use crate::index::heading_links::HeadingRename;
+use crate::index::links::{Link, LinkKind};
+use crate::index::lint::BrokenLink;
+use crate::index::mentions::Mention;
+use crate::index::rewrite::RewrittenNote;
+use crate::index::sections::Heading;
+use crate::index::VaultIndex;
+use crate::write_queue::{Edit, WriteQueue};
+
+/// A link together with the note it is written in and the file it points at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkReference {
+    /// Note containing the link
+    pub source: String,
+    /// File the link resolves to, absent if it points at nothing
+    pub resolved: Option<String>,
+    #[serde(flatten)]
+    pub link: Link,
+}
+
+/// A note whose links were rewritten
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatedFile {
+    pub path: String,
+    /// Number of links changed in the note
+    pub links: usize,
+}
+
+/// Notes changed by a link update, and the ones that could not be written
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkUpdateReport {
+    pub updated: Vec<UpdatedFile>,
+    pub failed: Vec<CommandError>,
+}
+
+/// Links converted between wiki and markdown syntax
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkConversionReport {
+    /// Whether this was only a preview and nothing was written
+    pub dry_run: bool,
+    /// Links converted, or that would be converted in a dry run
+    pub converted: Vec<ConvertedLink>,
+    /// Links left as written
+    pub skipped: Vec<SkippedLink>,
+    /// Notes written, empty in a dry run
+    #[serde(flatten)]
+    pub update: LinkUpdateReport,
+}
+
+/// Lock the vault index, (re)building it first if it does not cover `path`
+///
+/// `path` must already be resolved. The index follows the innermost vault root
+/// containing the path, and names paths in its results the way the frontend
+/// registered that root.
+pub fn vault_index<'a>(
+    app: &'a AppHandle,
+    path: &Path,
+) -> CommandResult<MutexGuard<'a, VaultIndex>> {
+    let root = vault_root(app, path)?;
+    let shown_root = shown_path(app, &root);
+
+    let mut index = app
+        .state::<Mutex<VaultIndex>>()
+        .inner()
+        .lock()
+        .map_err(|e| {
+            CommandError::internal(format!("Failed to lock vault index: {}", e)).logged()
+        })?;
+
+    if index.root() != Some(root.as_path()) {
+        index.rebuild(&root);
+    }
+    index.set_shown_root(shown_root.into());
+    Ok(index)
+}
+
+/// Update the index for paths that changed on disk
+///
+/// Failures are only logged: a stale index must never fail the file operation
+/// that triggered the refresh.
+pub fn refresh_index<P: AsRef<Path>>(app: &AppHandle, paths: impl IntoIterator<Item = P>) {
+    let state = app.state::<Mutex<VaultIndex>>();
+    let mut index = match state.lock() {
+        Ok(index) => index,
+        Err(e) => {
+            log::error!("Failed to lock vault index: {}", e);
+            return;
+        }
+    };
+    for path in paths {
+        index.refresh_path(path.as_ref());
+    }
+}
+
+/// Re-analyze a note from content that was just written to it
+pub fn index_note(app: &AppHandle, path: &Path, content: &str) {
+    let state = app.state::<Mutex<VaultIndex>>();
+    match state.lock() {
+        Ok(mut index) => index.update_note(path, content),
+        Err(e) => log::error!("Failed to lock vault index: {}", e),
+    };
+}
+
+/// Headings of a note as indexed, to compare a save against
+///
+/// Take them before writing: once the new content is on disk the watcher may
+/// re-index the note at any moment.
+pub fn indexed_headings(app: &AppHandle, path: &Path) -> Option<Vec<Heading>> {
+    let state = app.state::<Mutex<VaultIndex>>();
+    let index = state.lock().ok()?;
+    index.note(path).map(|entry| entry.headings.clone())
+}
+
+/// Re-analyze a note the editor saved, tracking the headings it renames
+/// relative to `previous`, the headings from before the save
+///
+/// Returns the renamed headings that links in other notes still point at.
+pub fn index_saved_note(
+    app: &AppHandle,
+    path: &Path,
+    previous: Option<&[Heading]>,
+    content: &str,
+) -> Vec<HeadingRename> {
+    let state = app.state::<Mutex<VaultIndex>>();
+    let mut index = match state.lock() {
+        Ok(index) => index,
+        Err(e) => {
+            log::error!("Failed to lock vault index: {}", e);
+            return Vec::new();
+        }
+    };
+    if let Some(previous) = previous {
+        index.track_heading_renames(path, previous, content);
+    }
+    index.update_note(path, content);
+    index.pending_heading_renames(path)
+}
+
+/// Point links in other notes at the renamed headings of a note, then forget the renames
+pub fn apply_heading_renames(app: &AppHandle, path: &Path) -> CommandResult<LinkUpdateReport> {
+    let rewritten = {
+        let mut index = vault_index(app, path)?;
+        let renames = index.pending_heading_renames(path);
+        let rewritten = index.rewrite_heading_links(path, &renames);
+        index.clear_heading_renames(path);
+        rewritten
+    };
+    Ok(write_rewrites(app, rewritten))
+}
+
+/// Write rewritten notes to disk and into the index
+///
+/// A failed note does not stop the others; it is reported instead.
+pub fn write_rewrites(app: &AppHandle, notes: Vec<RewrittenNote>) -> LinkUpdateReport {
+    let mut report = LinkUpdateReport::default();
+    let queue = app.state::<WriteQueue>();
+
+    for note in notes {
+        match write_rewrite(&queue, &note) {
+            Ok(_) => {
+                log::info!(
+                    "Updated {} link(s) in '{}'",
+                    note.links,
+                    note.path.display()
+                );
+                index_note(app, &note.path, &note.content);
+                report.updated.push(UpdatedFile {
+                    path: shown_path(app, &note.path),
+                    links: note.links,
+                });
+            }
+            Err(e) => report.failed.push(e.logged()),
+        }
+    }
+
+    report.updated.sort_by(|a, b| a.path.cmp(&b.path));
+    report
+}
+
+/// Write one rewritten note, unless it was saved since the rewrite was computed
+///
+/// Taking the queue first puts a save still waiting for the disk on disk, so
+/// the check sees it and the note is reported as a conflict instead of
+/// overwriting the save.
+pub fn write_rewrite(queue: &WriteQueue, note: &RewrittenNote) -> CommandResult<()> {
+    queue.exclusive(&[&note.path], || {
+        ensure_unchanged(note)?;
+        fs::write(&note.path, &note.content)
+            .map_err(|e| CommandError::io("Failed to update links in", &note.path, e))?;
+        Ok(Edit::written(()))
+    })
+}
+
+/// Fail with a conflict if a note no longer holds the content its rewrite was
+/// computed from; the caller holds the note's write queue
+pub fn ensure_unchanged(note: &RewrittenNote) -> CommandResult<()> {
+    let current = fs::read_to_string(&note.path)
+        .map_err(|e| CommandError::io("Failed to read", &note.path, e))?;
+    if current != note.original {
+        return Err(CommandError::conflict(
+            &note.path,
+            format!(
+                "'{}' changed while it was being updated, left as saved",
+                note.path.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Links in other notes pointing at a note or file
+#[tauri::command]
+pub async fn get_backlinks(app: AppHandle, path: String) -> CommandResult<Vec<LinkReference>> {
+    let resolved = resolve_path(&app, &path)?;
+    let index = vault_index(&app, &resolved)?;
+
+    let backlinks: Vec<LinkReference> = index
+        .backlinks(&resolved)
+        .into_iter()
+        .map(|(source, link)| LinkReference {
+            source: index.shown(source),
+            resolved: Some(index.shown(&resolved)),
+            link: link.clone(),
+        })
+        .collect();
+
+    log::info!("Found {} backlink(s) to '{}'", backlinks.len(), path);
+    Ok(backlinks)
+}
+
+/// Links written in a note, resolved against the vault
+#[tauri::command]
+pub async fn get_outgoing_links(app: AppHandle, path: String) -> CommandResult<Vec<LinkReference>> {
+    let resolved = resolve_path(&app, &path)?;
+    let index = vault_index(&app, &resolved)?;
+
+    if index.note(&resolved).is_none() {
+        return Err(CommandError::not_found(&path, "Note").logged());
+    }
+
+    let links: Vec<LinkReference> = index
+        .outgoing(&resolved)
+        .into_iter()
+        .map(|(link, target)| LinkReference {
+            source: index.shown(&resolved),
+            resolved: target.map(|t| index.shown(&t)),
+            link: link.clone(),
+        })
+        .collect();
+
+    log::info!("Found {} outgoing link(s) in '{}'", links.len(), path);
+    Ok(links)
+}
+
+/// Links anywhere in a vault that point at missing notes, files or attachments
+/// Each one comes with the most similar existing file where there is one
+#[tauri::command]
+pub async fn find_broken_links(app: AppHandle, path: String) -> CommandResult<Vec<BrokenLink>> {
+    log::info!("Checking links in vault: {}", path);
+
+    let resolved = resolve_path(&app, &path)?;
+    let index = vault_index(&app, &resolved)?;
+    let broken = index.broken_links();
+
+    log::info!("Found {} broken link(s) in '{}'", broken.len(), path);
+    Ok(broken)
+}
+
+/// Plain-text mentions of a note's title or aliases in other notes
+#[tauri::command]
+pub async fn find_unlinked_mentions(app: AppHandle, path: String) -> CommandResult<Vec<Mention>> {
+    let resolved = resolve_path(&app, &path)?;
+    let index = vault_index(&app, &resolved)?;
+
+    if index.note(&resolved).is_none() {
+        return Err(CommandError::not_found(&path, "Note").logged());
+    }
+
+    let start = std::time::Instant::now();
+    let mentions = index.unlinked_mentions(&resolved);
+
+    log::info!(
+        "Found {} unlinked mention(s) of '{}' in {:.2}ms",
+        mentions.len(),
+        path,
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(mentions)
+}
+
+/// Turn selected unlinked mentions into wiki links to a note
+/// Mentions that changed since they were found are reported as failed and left alone
+#[tauri::command]
+pub async fn link_mentions(
+    app: AppHandle,
+    path: String,
+    mentions: Vec<Mention>,
+) -> CommandResult<LinkUpdateReport> {
+    log::info!("Linking {} mention(s) of: {}", mentions.len(), path);
+
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    let mut by_source: BTreeMap<String, Vec<Mention>> = BTreeMap::new();
+    for mention in mentions {
+        by_source
+            .entry(mention.source.clone())
+            .or_default()
+            .push(mention);
+    }
+
+    let mut failed = Vec::new();
+    let mut rewritten = Vec::new();
+    {
+        let index = vault_index(&app, &resolved)?;
+        if index.note(&resolved).is_none() {
+            return Err(CommandError::not_found(&path, "Note").logged());
+        }
+
+        for (source, selected) in &by_source {
+            let source_path = match resolve_path(&app, source) {
+                Ok(source_path) => source_path,
+                Err(e) => {
+                    failed.push(e);
+                    continue;
+                }
+            };
+            let content = match fs::read_to_string(&source_path) {
+                Ok(content) => content,
+                Err(e) => {
+                    failed.push(CommandError::io("Failed to read", &source_path, e).logged());
+                    continue;
+                }
+            };
+
+            let (note, missing) = index.link_mentions(&resolved, &source_path, &content, selected);
+            failed.extend(missing.into_iter().map(|mention| {
+                CommandError::conflict(
+                    source,
+                    format!(
+                        "'{}' at line {}, column {} is no longer an unlinked mention",
+                        mention.text, mention.line, mention.column
+                    ),
+                )
+                .logged()
+            }));
+            rewritten.extend(note);
+        }
+    }
+
+    let mut report = write_rewrites(&app, rewritten);
+    report.failed.splice(0..0, failed);
+    Ok(report)
+}
+
+/// Renamed headings of a note that links in other notes still use
+#[tauri::command]
+pub async fn get_heading_renames(
+    app: AppHandle,
+    path: String,
+) -> CommandResult<Vec<HeadingRename>> {
+    let resolved = resolve_path(&app, &path)?;
+    let index = vault_index(&app, &resolved)?;
+    Ok(index.pending_heading_renames(&resolved))
+}
+
+/// Update `#heading` links in other notes to follow headings renamed in a note
+#[tauri::command]
+pub async fn update_heading_links(app: AppHandle, path: String) -> CommandResult<LinkUpdateReport> {
+    log::info!("Updating links to renamed headings in: {}", path);
+
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    apply_heading_renames(&app, &resolved)
+}
+
+/// Keep links to the old headings of a note as they are and stop reporting the renames
+#[tauri::command]
+pub async fn dismiss_heading_renames(app: AppHandle, path: String) -> CommandResult<()> {
+    let resolved = resolve_path(&app, &path)?;
+    vault_index(&app, &resolved)?.clear_heading_renames(&resolved);
+    log::info!("Dismissed heading renames in '{}'", path);
+    Ok(())
+}
+
+/// Convert the links in a folder, or a whole vault, to wiki or markdown syntax
+/// With `dry_run` set the conversion is only reported, nothing is written
+#[tauri::command]
+pub async fn convert_links(
+    app: AppHandle,
+    path: String,
+    to: LinkKind,
+    dry_run: Option<bool>,
+) -> CommandResult<LinkConversionReport> {
+    let dry_run = dry_run.unwrap_or(false);
+    log::info!(
+        "Converting links in '{}' to {:?}{}",
+        path,
+        to,
+        if dry_run { " (dry run)" } else { "" }
+    );
+
+    let resolved = resolve_path(&app, &path)?;
+    if !resolved.exists() {
+        return Err(CommandError::not_found(&path, "Folder").logged());
+    }
+    if !dry_run {
+        ensure_lock_owned(&app, &resolved)?;
+    }
+    let conversion = vault_index(&app, &resolved)?.convert_links(&resolved, to);
+
+    log::info!(
+        "{} link(s) to convert in {} note(s), {} skipped",
+        conversion.converted.len(),
+        conversion.notes.len(),
+        conversion.skipped.len()
+    );
+    let update = if dry_run {
+        LinkUpdateReport::default()
+    } else {
+        write_rewrites(&app, conversion.notes)
+    };
+    Ok(LinkConversionReport {
+        dry_run,
+        converted: conversion.converted,
+        skipped: conversion.skipped,
+        update,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn save_between_planning_and_writing_is_kept() {
+        let dir = std::env::temp_dir().join(format!("yana-rewrite-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("note.md");
+        let queue = WriteQueue::default();
+        queue.write(&path, "See [[Old]]\n".into(), Some(1)).unwrap();
+
+        let note = RewrittenNote {
+            path: path.clone(),
+            content: "See [[New]]\n".into(),
+            original: "See [[Old]]\n".into(),
+            links: 1,
+        };
+        // The editor saves after the rewrite was planned
+        queue
+            .write(&path, "See [[Old]] and more\n".into(), Some(2))
+            .unwrap();
+
+        let err = write_rewrite(&queue, &note).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Conflict);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "See [[Old]] and more\n");
+
+        // Planned against the latest save, the rewrite goes through
+        let note = RewrittenNote {
+            original: "See [[Old]] and more\n".into(),
+            content: "See [[New]] and more\n".into(),
+            ..note
+        };
+        write_rewrite(&queue, &note).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "See [[New]] and more\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
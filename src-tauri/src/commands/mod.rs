@@ -1,3 +1,31 @@
+pub mod attachments;
+pub mod embeds;
 pub mod file_tree;
+pub mod graph;
+pub mod links;
+pub mod outline;
+pub mod periodic;
+pub mod previews;
+pub mod properties;
+pub mod query;
+pub mod recovery;
+pub mod tags;
+pub mod tasks;
+pub mod templates;
+pub mod vault;
 
+pub use attachments::*;
+pub use embeds::*;
 pub use file_tree::*;
+pub use graph::*;
+pub use links::*;
+pub use outline::*;
+pub use periodic::*;
+pub use previews::*;
+pub use properties::*;
+pub use query::*;
+pub use recovery::*;
+pub use tags::*;
+pub use tasks::*;
+pub use templates::*;
+pub use vault::*;
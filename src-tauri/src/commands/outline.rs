@@ -0,0 +1,30 @@
+use std::fs;
+use tauri::AppHandle;
+
+use super::vault::resolve_path;
+use crate::error::{CommandError, CommandResult};
+use crate::index::is_note;
+use crate::index::outline::{note_outline, NoteOutline};
+
+/// Heading tree of a note with word, character, link and task counts
+/// Parsed as CommonMark; frontmatter, headings and code are left out of the counts
+#[tauri::command]
+pub async fn get_note_outline(app: AppHandle, path: String) -> CommandResult<NoteOutline> {
+    let resolved = resolve_path(&app, &path)?;
+    if !is_note(&resolved) {
+        return Err(CommandError::invalid_input(format!("Not a note: {}", path)).logged());
+    }
+    let content = fs::read_to_string(&resolved)
+        .map_err(|e| CommandError::io("Failed to read", &resolved, e).logged())?;
+
+    let start = std::time::Instant::now();
+    let outline = note_outline(&content);
+
+    log::info!(
+        "Outlined '{}' ({} words) in {:.2}ms",
+        path,
+        outline.stats.words,
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(outline)
+}
@@ -0,0 +1,313 @@
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use super::file_tree::FileNode;
+use super::links::{refresh_index, vault_index};
+use super::templates::new_note_content;
+use super::vault::{ensure_lock_owned, resolve_path, stored_setting};
+use crate::dates::{format_date, parse_date};
+use crate::error::{CommandError, CommandResult};
+use crate::index::is_note;
+use crate::write_queue::{Edit, WriteQueue};
+
+/// Span of time a periodic note covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Period {
+    /// Key of the period in the `periodicNotes` setting
+    fn key(self) -> &'static str {
+        match self {
+            Period::Daily => "daily",
+            Period::Weekly => "weekly",
+            Period::Monthly => "monthly",
+        }
+    }
+
+    fn default_format(self) -> &'static str {
+        match self {
+            Period::Daily => "YYYY-MM-DD",
+            Period::Weekly => "gggg-[W]ww",
+            Period::Monthly => "YYYY-MM",
+        }
+    }
+
+    /// First day of the period a date falls in; weeks start on Monday
+    fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Daily => date,
+            Period::Weekly => {
+                date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+            }
+            Period::Monthly => date.with_day(1).unwrap_or(date),
+        }
+    }
+}
+
+/// Where the notes of a period go and how they are named
+struct PeriodicConfig {
+    /// Folder relative to the vault root, empty for the root itself
+    folder: String,
+    /// Moment-style file name format, which may contain `/` for subfolders
+    format: String,
+}
+
+/// Read the folder and name format of a period from the settings store
+fn periodic_config(app: &AppHandle, period: Period) -> CommandResult<PeriodicConfig> {
+    let setting =
+        stored_setting(app, "periodicNotes")?.and_then(|notes| notes.get(period.key()).cloned());
+    let field = |name: &str| {
+        setting
+            .as_ref()
+            .and_then(|setting| setting.get(name)?.as_str())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    Ok(PeriodicConfig {
+        folder: field("folder").unwrap_or_default(),
+        format: field("format").unwrap_or_else(|| period.default_format().to_string()),
+    })
+}
+
+/// Path of the note for a period, relative to its folder and without `.md`
+fn note_name(period: Period, format: &str, date: NaiveDate) -> CommandResult<String> {
+    let start = period.start(date).and_hms_opt(0, 0, 0).unwrap_or_default();
+    let name = format_date(&start, format);
+
+    let plain = !name.is_empty()
+        && Path::new(&name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if !plain {
+        return Err(CommandError::invalid_input(format!(
+            "Periodic note format '{}' gives '{}', which is not a file name",
+            format, name
+        ))
+        .logged());
+    }
+    Ok(name)
+}
+
+/// Date of the period a note is for, if its path matches the format exactly
+fn note_date(folder: &Path, note: &Path, period: Period, format: &str) -> Option<NaiveDate> {
+    if !is_note(note) {
+        return None;
+    }
+    let relative = note.strip_prefix(folder).ok()?.with_extension("");
+    let name = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let start = period.start(parse_date(&name, format)?);
+    let written = start.and_hms_opt(0, 0, 0)?;
+    (format_date(&written, format) == name).then_some(start)
+}
+
+/// Open the periodic note for a date, today by default, creating it if needed
+/// A new note gets the template of its folder; returns the note's node either way
+#[tauri::command]
+pub async fn open_periodic_note(
+    app: AppHandle,
+    path: String,
+    period: Period,
+    date: Option<String>,
+) -> CommandResult<FileNode> {
+    let date = match date {
+        Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| {
+            CommandError::invalid_input(format!("'{}' is not a date, use YYYY-MM-DD", date))
+                .logged()
+        })?,
+        None => Local::now().date_naive(),
+    };
+    resolve_path(&app, &path)?;
+
+    let config = periodic_config(&app, period)?;
+    let folder = Path::new(&path).join(&config.folder);
+    let note = folder.join(format!("{}.md", note_name(period, &config.format, date)?));
+    let resolved = resolve_path(&app, &note.to_string_lossy())?;
+
+    if resolved.is_file() {
+        log::info!("Opening {} note: {}", period.key(), note.display());
+        return Ok(FileNode::new(&note, "file", None));
+    }
+    ensure_lock_owned(&app, &resolved)?;
+
+    if let Some(parent) = resolved.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| CommandError::io("Failed to create folder", parent, e).logged())?;
+    }
+    let content = match resolved.parent() {
+        Some(parent) => new_note_content(&app, parent, &resolved),
+        None => String::new(),
+    };
+    // create_new keeps a note created since the check above, e.g. by a sync
+    // client or a second click, instead of overwriting it with the template
+    let created = app.state::<WriteQueue>().exclusive(&[&resolved], || {
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&resolved);
+        match file.and_then(|mut file| file.write_all(content.as_bytes())) {
+            Ok(_) => Ok(Edit::written(true)),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists && resolved.is_file() => {
+                Ok(Edit::unchanged(false))
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                Err(CommandError::not_a_file(&note).logged())
+            }
+            Err(e) => {
+                Err(CommandError::io("Failed to create periodic note", &resolved, e).logged())
+            }
+        }
+    })?;
+
+    if created {
+        refresh_index(&app, [&resolved]);
+        log::info!("Created {} note: {}", period.key(), note.display());
+    } else {
+        log::info!("Opening {} note: {}", period.key(), note.display());
+    }
+    Ok(FileNode::new(&note, "file", None))
+}
+
+/// Closest existing periodic note before the one at `from`, if any
+#[tauri::command]
+pub async fn get_previous_periodic_note(
+    app: AppHandle,
+    path: String,
+    period: Period,
+    from: String,
+) -> CommandResult<Option<FileNode>> {
+    adjacent_note(&app, &path, period, &from, Ordering::Less)
+}
+
+/// Closest existing periodic note after the one at `from`, if any
+#[tauri::command]
+pub async fn get_next_periodic_note(
+    app: AppHandle,
+    path: String,
+    period: Period,
+    from: String,
+) -> CommandResult<Option<FileNode>> {
+    adjacent_note(&app, &path, period, &from, Ordering::Greater)
+}
+
+fn adjacent_note(
+    app: &AppHandle,
+    path: &str,
+    period: Period,
+    from: &str,
+    direction: Ordering,
+) -> CommandResult<Option<FileNode>> {
+    let root = resolve_path(app, path)?;
+    let config = periodic_config(app, period)?;
+    let folder = resolve_path(app, &Path::new(path).join(&config.folder).to_string_lossy())?;
+    let current = resolve_path(app, from)?;
+
+    let date = note_date(&folder, &current, period, &config.format).ok_or_else(|| {
+        CommandError::invalid_input(format!("'{}' is not a {} note", from, period.key())).logged()
+    })?;
+
+    let index = vault_index(app, &root)?;
+    let candidates = index.note_paths().filter_map(|note| {
+        let other = note_date(&folder, note, period, &config.format)?;
+        (other.cmp(&date) == direction).then_some((other, note))
+    });
+    let closest: Option<(NaiveDate, &Path)> = match direction {
+        Ordering::Less => candidates.max_by_key(|(other, _)| *other),
+        _ => candidates.min_by_key(|(other, _)| *other),
+    };
+
+    // Report the note under the vault path as given, like the file tree does
+    Ok(closest.and_then(|(_, note)| {
+        let relative = note.strip_prefix(&root).ok()?;
+        let note: PathBuf = Path::new(path).join(relative);
+        Some(FileNode::new(&note, "file", None))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn names_are_for_the_start_of_the_period() {
+        let day = date(2025, 1, 1);
+        assert_eq!(
+            note_name(Period::Daily, "YYYY/MM/YYYY-MM-DD", day).unwrap(),
+            "2025/01/2025-01-01"
+        );
+        assert_eq!(
+            note_name(Period::Weekly, "gggg-[W]ww", day).unwrap(),
+            "2025-W01"
+        );
+        assert_eq!(
+            note_name(Period::Weekly, "YYYY-MM-DD", day).unwrap(),
+            "2024-12-30"
+        );
+        assert_eq!(
+            note_name(Period::Monthly, "YYYY-MM", date(2024, 2, 29)).unwrap(),
+            "2024-02"
+        );
+
+        // Formats that leave the folder are refused
+        assert!(note_name(Period::Daily, "[../]YYYY", day).is_err());
+        assert!(note_name(Period::Daily, "[/]YYYY", day).is_err());
+        assert!(note_name(Period::Daily, "", day).is_err());
+    }
+
+    #[test]
+    fn note_dates_need_an_exact_name() {
+        let folder = Path::new("/vault/Journal");
+        let note = |name: &str| folder.join(name);
+
+        assert_eq!(
+            note_date(folder, &note("2025-W01.md"), Period::Weekly, "gggg-[W]ww"),
+            Some(date(2024, 12, 30))
+        );
+        assert_eq!(
+            note_date(
+                folder,
+                &note("2024/12/2024-12-31.md"),
+                Period::Daily,
+                "YYYY/MM/YYYY-MM-DD"
+            ),
+            Some(date(2024, 12, 31))
+        );
+        // A weekly name for a day other than Monday is some other note
+        assert_eq!(
+            note_date(folder, &note("2024-12-31.md"), Period::Weekly, "YYYY-MM-DD"),
+            None
+        );
+        assert_eq!(
+            note_date(
+                folder,
+                &note("2024-12-31 notes.md"),
+                Period::Daily,
+                "YYYY-MM-DD"
+            ),
+            None
+        );
+        assert_eq!(
+            note_date(folder, &note("2024-12-31.png"), Period::Daily, "YYYY-MM-DD"),
+            None
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+
+use super::vault::resolve_path;
+use crate::error::{CommandError, CommandResult};
+use crate::index::is_note;
+use crate::index::preview::{excerpt, note_preview, Preview, MAX_EXCERPT};
+
+/// Excerpt length when the caller does not ask for one, in characters
+const DEFAULT_EXCERPT: usize = 200;
+
+/// Cached previews before the least recently used ones are dropped
+const MAX_CACHED: usize = 5000;
+
+/// Version of a note a preview was built from
+///
+/// The length catches a save within the modification time's resolution, which
+/// is coarse on some filesystems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    modified: SystemTime,
+    len: u64,
+}
+
+impl Stamp {
+    fn of(meta: &fs::Metadata) -> std::io::Result<Stamp> {
+        Ok(Stamp {
+            modified: meta.modified()?,
+            len: meta.len(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Cached {
+    stamp: Stamp,
+    preview: Preview,
+    /// Value of the cache's clock when the preview was last used
+    used: u64,
+}
+
+/// Previews of notes by path, with the version of the note they were built from
+#[derive(Debug, Default)]
+pub struct PreviewCache {
+    entries: HashMap<PathBuf, Cached>,
+    clock: u64,
+}
+
+impl PreviewCache {
+    /// The preview of a note, if it was built from the current version
+    fn get(&mut self, path: &Path, stamp: Stamp) -> Option<Preview> {
+        self.clock += 1;
+        let cached = self
+            .entries
+            .get_mut(path)
+            .filter(|cached| cached.stamp == stamp)?;
+        cached.used = self.clock;
+        Some(cached.preview.clone())
+    }
+
+    fn insert(&mut self, path: PathBuf, stamp: Stamp, preview: Preview) {
+        if self.entries.len() >= MAX_CACHED && !self.entries.contains_key(&path) {
+            // Drop the least recently used tenth at once, so evicting stays rare
+            let mut used: Vec<u64> = self.entries.values().map(|cached| cached.used).collect();
+            let cutoff = *used.select_nth_unstable(MAX_CACHED / 10).1;
+            self.entries.retain(|_, cached| cached.used > cutoff);
+        }
+        self.clock += 1;
+        self.entries.insert(
+            path,
+            Cached {
+                stamp,
+                preview,
+                used: self.clock,
+            },
+        );
+    }
+}
+
+/// Title and plain-text excerpt of a note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotePreview {
+    /// Path as given by the caller
+    pub path: String,
+    /// The `title` property, else the first H1, else the file name
+    pub title: String,
+    /// Text without markdown syntax, cut at a word boundary
+    pub excerpt: String,
+}
+
+/// Preview of one note, from the cache when the note has not changed since
+fn preview_of(app: &AppHandle, path: &str) -> CommandResult<Preview> {
+    let resolved = resolve_path(app, path)?;
+    if !is_note(&resolved) {
+        return Err(CommandError::invalid_input(format!("Not a note: {}", path)));
+    }
+    let stamp = fs::metadata(&resolved)
+        .and_then(|meta| Stamp::of(&meta))
+        .map_err(|e| CommandError::io("Failed to read", &resolved, e))?;
+
+    let cache = app.state::<Mutex<PreviewCache>>();
+    if let Some(preview) = cache
+        .lock()
+        .ok()
+        .and_then(|mut cache| cache.get(&resolved, stamp))
+    {
+        return Ok(preview);
+    }
+
+    let content = fs::read_to_string(&resolved)
+        .map_err(|e| CommandError::io("Failed to read", &resolved, e))?;
+    let name = resolved
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let preview = note_preview(&content, &name);
+
+    match cache.lock() {
+        Ok(mut cache) => cache.insert(resolved, stamp, preview.clone()),
+        Err(e) => log::error!("Failed to lock preview cache: {}", e),
+    }
+    Ok(preview)
+}
+
+/// Titles and plain-text excerpts of notes, for hover cards and search results
+/// Notes that cannot be read are left out; previews are cached until a note's
+/// modification time or length changes
+#[tauri::command]
+pub async fn get_note_previews(
+    app: AppHandle,
+    paths: Vec<String>,
+    length: Option<usize>,
+) -> CommandResult<Vec<NotePreview>> {
+    let length = length.unwrap_or(DEFAULT_EXCERPT).min(MAX_EXCERPT);
+    let start = std::time::Instant::now();
+
+    let previews: Vec<NotePreview> = paths
+        .into_iter()
+        .filter_map(|path| match preview_of(&app, &path) {
+            Ok(preview) => Some(NotePreview {
+                excerpt: excerpt(&preview.text, length),
+                title: preview.title,
+                path,
+            }),
+            Err(e) => {
+                log::warn!("Skipping preview of '{}': {}", path, e);
+                None
+            }
+        })
+        .collect();
+
+    log::info!(
+        "Built {} note preview(s) in {:.2}ms",
+        previews.len(),
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(previews)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn stamp(len: u64) -> Stamp {
+        Stamp {
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            len,
+        }
+    }
+
+    fn preview(title: &str) -> Preview {
+        note_preview("Body", title)
+    }
+
+    #[test]
+    fn edit_within_the_same_second_is_noticed() {
+        let mut cache = PreviewCache::default();
+        let path = PathBuf::from("/vault/Note.md");
+        cache.insert(path.clone(), stamp(4), preview("Note"));
+
+        assert!(cache.get(&path, stamp(4)).is_some());
+        assert!(cache.get(&path, stamp(5)).is_none());
+        let later = Stamp {
+            modified: stamp(4).modified + Duration::from_millis(1),
+            ..stamp(4)
+        };
+        assert!(cache.get(&path, later).is_none());
+    }
+
+    #[test]
+    fn full_cache_drops_least_recently_used() {
+        let mut cache = PreviewCache::default();
+        let path = |i: usize| PathBuf::from(format!("/vault/{}.md", i));
+        for i in 0..MAX_CACHED {
+            cache.insert(path(i), stamp(1), preview("Note"));
+        }
+        // The oldest note is in use again
+        assert!(cache.get(&path(0), stamp(1)).is_some());
+
+        cache.insert(path(MAX_CACHED), stamp(1), preview("Note"));
+        assert!(cache.entries.len() < MAX_CACHED);
+        assert!(cache.entries.len() >= MAX_CACHED - MAX_CACHED / 10);
+        assert!(cache.get(&path(0), stamp(1)).is_some());
+        assert!(cache.get(&path(MAX_CACHED), stamp(1)).is_some());
+        assert!(cache.get(&path(1), stamp(1)).is_none());
+        assert!(cache.get(&path(MAX_CACHED - 1), stamp(1)).is_some());
+    }
+}
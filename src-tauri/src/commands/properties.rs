@@ -0,0 +1,183 @@
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use super::links::{index_note, vault_index};
+use super::vault::{ensure_lock_owned, resolve_path};
+use crate::error::{CommandError, CommandResult};
+use crate::index::frontmatter::{self, PropertyEditError};
+use crate::index::is_note;
+use crate::index::properties::{NoteProperty, PropertyFilter, PropertyKey, PropertyMatch};
+use crate::write_queue::{Edit, WriteQueue};
+
+/// Every frontmatter property key used in a vault, with the types of its values
+#[tauri::command]
+pub async fn get_property_keys(app: AppHandle, path: String) -> CommandResult<Vec<PropertyKey>> {
+    let resolved = resolve_path(&app, &path)?;
+    let keys = vault_index(&app, &resolved)?.property_keys();
+
+    log::info!("Found {} property key(s) in '{}'", keys.len(), path);
+    Ok(keys)
+}
+
+/// Frontmatter properties of a note, in the order they are written
+#[tauri::command]
+pub async fn get_note_properties(app: AppHandle, path: String) -> CommandResult<Vec<NoteProperty>> {
+    let resolved = resolve_path(&app, &path)?;
+    vault_index(&app, &resolved)?
+        .note_properties(&resolved)
+        .ok_or_else(|| CommandError::not_found(&path, "Note").logged())
+}
+
+/// Notes in a folder or vault whose property matches a value or lies in a range
+/// Without `value`, `min` or `max`, every note that has the property matches
+#[tauri::command]
+pub async fn find_notes_by_property(
+    app: AppHandle,
+    path: String,
+    key: String,
+    value: Option<Value>,
+    min: Option<Value>,
+    max: Option<Value>,
+) -> CommandResult<Vec<PropertyMatch>> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(CommandError::invalid_input("Property key must not be empty").logged());
+    }
+
+    let resolved = resolve_path(&app, &path)?;
+    let filter = PropertyFilter { value, min, max };
+
+    let start = std::time::Instant::now();
+    let matches = vault_index(&app, &resolved)?.find_by_property(&resolved, key, &filter);
+
+    log::info!(
+        "Found {} note(s) with matching '{}' in {:.2}ms",
+        matches.len(),
+        key,
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(matches)
+}
+
+/// Set one frontmatter property of a note, leaving the rest of the file as written
+/// Adds a frontmatter block when the note has none; returns the note's new properties
+#[tauri::command]
+pub async fn set_note_property(
+    app: AppHandle,
+    path: String,
+    key: String,
+    value: Value,
+) -> CommandResult<Vec<NoteProperty>> {
+    log::info!("Setting property '{}' of: {}", key, path);
+
+    let key = property_key(&key)?;
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    if !is_note(&resolved) {
+        return Err(CommandError::invalid_input(format!("Not a note: {}", path)).logged());
+    }
+    edit_properties(&app, &resolved, &path, |content| {
+        frontmatter::set_property(content, key, &value).map_err(|e| edit_error(&path, e))
+    })
+}
+
+/// Remove one frontmatter property of a note, leaving the rest of the file as written
+/// Returns the note's remaining properties
+#[tauri::command]
+pub async fn remove_note_property(
+    app: AppHandle,
+    path: String,
+    key: String,
+) -> CommandResult<Vec<NoteProperty>> {
+    log::info!("Removing property '{}' of: {}", key, path);
+
+    let key = property_key(&key)?;
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    if !is_note(&resolved) {
+        return Err(CommandError::invalid_input(format!("Not a note: {}", path)).logged());
+    }
+    edit_properties(&app, &resolved, &path, |content| {
+        Ok(frontmatter::remove_property(content, key)
+            .map_err(|e| edit_error(&path, e))?
+            .unwrap_or_else(|| content.to_string()))
+    })
+}
+
+fn property_key(key: &str) -> CommandResult<&str> {
+    let key = key.trim();
+    if key.is_empty() || key.contains(['\n', '\r']) {
+        return Err(
+            CommandError::invalid_input("Property key must be a single non-empty line").logged(),
+        );
+    }
+    Ok(key)
+}
+
+fn edit_error(path: &str, err: PropertyEditError) -> CommandError {
+    match err {
+        PropertyEditError::Malformed => CommandError::invalid_input(format!(
+            "Frontmatter of '{}' does not parse, fix it before editing properties",
+            path
+        )),
+        PropertyEditError::Unsupported(reason) => CommandError::invalid_input(reason),
+    }
+    .logged()
+}
+
+/// Edit a note through the write queue, writing it only if something changed,
+/// and return its properties
+fn edit_properties(
+    app: &AppHandle,
+    resolved: &Path,
+    path: &str,
+    edit: impl FnOnce(&str) -> CommandResult<String>,
+) -> CommandResult<Vec<NoteProperty>> {
+    let updated = app.state::<WriteQueue>().exclusive(&[resolved], || {
+        let content = fs::read_to_string(resolved)
+            .map_err(|e| CommandError::io("Failed to read", resolved, e).logged())?;
+        let updated = edit(&content)?;
+        if updated == content {
+            return Ok(Edit::unchanged(None));
+        }
+        fs::write(resolved, &updated)
+            .map_err(|e| CommandError::io("Failed to write", resolved, e).logged())?;
+        Ok(Edit::written(Some(updated)))
+    })?;
+    if let Some(updated) = updated {
+        index_note(app, resolved, &updated);
+    }
+
+    vault_index(app, resolved)?
+        .note_properties(resolved)
+        .ok_or_else(|| CommandError::not_found(path, "Note").logged())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn keys_are_single_trimmed_lines() {
+        assert_eq!(property_key("  status ").unwrap(), "status");
+        for key in ["", "   ", "a\nb", "a\rb"] {
+            assert_eq!(property_key(key).unwrap_err().code, ErrorCode::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn edit_errors_are_invalid_input() {
+        let malformed = edit_error("Notes/a.md", PropertyEditError::Malformed);
+        assert_eq!(malformed.code, ErrorCode::InvalidInput);
+        assert!(malformed.message.contains("'Notes/a.md' does not parse"));
+
+        let unsupported = edit_error("a.md", PropertyEditError::Unsupported("No tables".into()));
+        assert_eq!(
+            (unsupported.code, unsupported.message.as_str()),
+            (ErrorCode::InvalidInput, "No tables")
+        );
+    }
+}
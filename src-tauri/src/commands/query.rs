@@ -0,0 +1,25 @@
+use tauri::AppHandle;
+
+use super::links::vault_index;
+use super::vault::resolve_path;
+use crate::error::{CommandError, CommandResult};
+use crate::index::query::{parse_query, QueryResult};
+
+/// Run a query over the notes of a vault and return table rows
+/// See `index::query` for the syntax
+#[tauri::command]
+pub async fn run_query(app: AppHandle, path: String, query: String) -> CommandResult<QueryResult> {
+    let parsed = parse_query(&query)
+        .map_err(|e| CommandError::invalid_input(format!("Invalid query: {}", e)).logged())?;
+
+    let resolved = resolve_path(&app, &path)?;
+    let start = std::time::Instant::now();
+    let result = vault_index(&app, &resolved)?.query(&parsed);
+
+    log::info!(
+        "Query returned {} row(s) in {:.2}ms",
+        result.rows.len(),
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(result)
+}
@@ -0,0 +1,60 @@
+use crate::error::{CommandError, CommandResult};
+use crate::journal::{Journal, RecoverableBuffer};
+use crate::sandbox::VaultSandbox;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use super::vault::{resolve_path, shown_path};
+
+/// Directory inside the app data dir holding buffer checkpoints
+pub const JOURNAL_DIR: &str = "recovery";
+
+/// Record an unsaved editor buffer so it survives a crash
+/// Called frequently by the editor, so success is not logged. `seq` orders
+/// checkpoints of a note that overlap; an older one never replaces a newer one
+#[tauri::command]
+pub async fn checkpoint_buffer(
+    app: AppHandle,
+    path: String,
+    content: String,
+    seq: Option<u64>,
+) -> CommandResult<()> {
+    let resolved = resolve_path(&app, &path)?;
+    let journal = app.state::<Journal>();
+    journal
+        .checkpoint(&resolved, content, seq)
+        .map_err(CommandError::logged)
+}
+
+/// Drop the checkpoint for a note, e.g. after the user declined to recover it
+#[tauri::command]
+pub async fn discard_buffer(app: AppHandle, path: String) -> CommandResult<()> {
+    log::info!("Discarding recovery checkpoint for: {}", path);
+
+    let resolved = resolve_path(&app, &path)?;
+    let journal = app.state::<Journal>();
+    journal.discard(&resolved).map_err(CommandError::logged)
+}
+
+/// List notes in the open vaults whose checkpoint is newer than the file on disk
+#[tauri::command]
+pub async fn list_recoverable_buffers(app: AppHandle) -> CommandResult<Vec<RecoverableBuffer>> {
+    // Read the journal without holding up other commands on the sandbox
+    let roots = {
+        let state = app.state::<Mutex<VaultSandbox>>();
+        let sandbox = state.lock().map_err(|e| {
+            CommandError::internal(format!("Failed to lock vault sandbox: {}", e)).logged()
+        })?;
+        sandbox.roots().to_vec()
+    };
+
+    let journal = app.state::<Journal>();
+    let mut buffers = journal.recoverable(|path| roots.iter().any(|root| path.starts_with(root)));
+    for buffer in &mut buffers {
+        buffer.path = shown_path(&app, Path::new(&buffer.path));
+    }
+
+    log::info!("Found {} recoverable buffer(s)", buffers.len());
+    Ok(buffers)
+}
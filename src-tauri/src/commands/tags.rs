@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use super::links::{ensure_unchanged, index_note, vault_index};
+use super::vault::{ensure_lock_owned, resolve_path, shown_path};
+use crate::error::{CommandError, CommandResult};
+use crate::index::rewrite::RewrittenNote;
+use crate::index::tags::{is_tag_name, TagNode, TaggedNote};
+use crate::write_queue::{Edit, WriteQueue};
+
+/// A note whose tags were renamed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagUpdate {
+    pub path: String,
+    /// Number of tags changed in the note
+    pub tags: usize,
+}
+
+/// Every tag in a vault as a hierarchy, with note counts at each level
+#[tauri::command]
+pub async fn get_tag_tree(app: AppHandle, path: String) -> CommandResult<Vec<TagNode>> {
+    let resolved = resolve_path(&app, &path)?;
+    let tree = vault_index(&app, &resolved)?.tag_tree();
+
+    log::info!("Found {} top-level tag(s) in '{}'", tree.len(), path);
+    Ok(tree)
+}
+
+/// Notes carrying a tag, by default including tags nested below it
+#[tauri::command]
+pub async fn get_notes_for_tag(
+    app: AppHandle,
+    path: String,
+    tag: String,
+    include_nested: Option<bool>,
+) -> CommandResult<Vec<TaggedNote>> {
+    let tag = tag_name(&tag)?;
+    let resolved = resolve_path(&app, &path)?;
+    let notes = vault_index(&app, &resolved)?.notes_with_tag(&tag, include_nested.unwrap_or(true));
+
+    log::info!("Found {} note(s) tagged '#{}'", notes.len(), tag);
+    Ok(notes)
+}
+
+/// Rename a tag, and the tags nested below it, across a whole vault
+/// Renaming onto an existing tag merges the two. Either every note is updated
+/// or, if one can't be written or was saved in the meantime, none is
+#[tauri::command]
+pub async fn rename_tag(
+    app: AppHandle,
+    path: String,
+    from: String,
+    to: String,
+) -> CommandResult<Vec<TagUpdate>> {
+    let from = tag_name(&from)?;
+    let to = tag_name(&to)?;
+    log::info!("Renaming tag '#{}' to '#{}' in: {}", from, to, path);
+
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    let rewritten = vault_index(&app, &resolved)?.rename_tag(&from, &to);
+
+    let paths: Vec<&Path> = rewritten.iter().map(|note| note.path.as_path()).collect();
+    app.state::<WriteQueue>().exclusive(&paths, || {
+        for note in &rewritten {
+            ensure_unchanged(note).map_err(CommandError::logged)?;
+        }
+        write_all_or_nothing(&rewritten).map(Edit::written)
+    })?;
+    for note in &rewritten {
+        index_note(&app, &note.path, &note.content);
+    }
+
+    log::info!(
+        "Renamed '#{}' to '#{}' in {} note(s)",
+        from,
+        to,
+        rewritten.len()
+    );
+    Ok(rewritten
+        .into_iter()
+        .map(|note| TagUpdate {
+            path: shown_path(&app, &note.path),
+            tags: note.links,
+        })
+        .collect())
+}
+
+/// Tag as entered by the user, without `#` and trailing `/`
+fn tag_name(tag: &str) -> CommandResult<String> {
+    let name = tag.trim().trim_start_matches('#').trim_end_matches('/');
+    if !is_tag_name(name) {
+        return Err(CommandError::invalid_input(format!(
+            "'{}' is not a valid tag: use letters, digits, '_', '-' and '/'",
+            tag
+        ))
+        .logged());
+    }
+    Ok(name.to_string())
+}
+
+/// Write every note or none
+///
+/// Notes are written in place, so they keep their identity (permissions,
+/// links to the file, watchers of it) and nothing is left next to them. Every
+/// note is first opened for writing, so a read-only note fails the rename
+/// before anything changed; should a write still fail midway, notes already
+/// written get the content the rename started from back.
+fn write_all_or_nothing(notes: &[RewrittenNote]) -> CommandResult<()> {
+    for note in notes {
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&note.path)
+            .map_err(|e| CommandError::io("Failed to update tags in", &note.path, e).logged())?;
+    }
+
+    for (index, note) in notes.iter().enumerate() {
+        if let Err(e) = fs::write(&note.path, &note.content) {
+            for written in notes[..=index].iter().rev() {
+                if let Err(e) = fs::write(&written.path, &written.original) {
+                    log::error!("Failed to restore '{}': {}", written.path.display(), e);
+                }
+            }
+            return Err(CommandError::io("Failed to update tags in", &note.path, e).logged());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_write_restores_every_note() {
+        let dir = std::env::temp_dir().join(format!("yana-tag-write-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("Folder.md")).unwrap();
+        fs::write(dir.join("A.md"), "#old\n").unwrap();
+        fs::write(dir.join("B.md"), "#old\n").unwrap();
+
+        let note = |name: &str| RewrittenNote {
+            path: dir.join(name),
+            content: "#new\n".to_string(),
+            original: "#old\n".to_string(),
+            links: 1,
+        };
+
+        // A note that can't be opened for writing stops the rename up front
+        let err = write_all_or_nothing(&[note("A.md"), note("Folder.md"), note("B.md")]);
+        assert!(err.is_err());
+        assert_eq!(fs::read_to_string(dir.join("A.md")).unwrap(), "#old\n");
+
+        write_all_or_nothing(&[note("A.md"), note("B.md")]).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("A.md")).unwrap(), "#new\n");
+        assert_eq!(fs::read_to_string(dir.join("B.md")).unwrap(), "#new\n");
+
+        // Nothing is staged next to the notes
+        let mut entries: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, ["A.md", "B.md", "Folder.md"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs;
+use tauri::{AppHandle, Manager};
+
+use super::links::{index_note, vault_index};
+use super::vault::{ensure_lock_owned, resolve_path, shown_path};
+use crate::error::{CommandError, CommandResult};
+use crate::index::is_note;
+use crate::index::properties::is_date;
+use crate::index::tasks::{self, TaskFilter, TaskItem, TaskStatus, ToggleError};
+use crate::write_queue::{Edit, WriteQueue};
+
+/// Checkbox tasks across a vault, optionally filtered by status, folder and due date
+/// Dates are `YYYY-MM-DD` and inclusive; a date range leaves out undated tasks
+#[tauri::command]
+pub async fn get_tasks(
+    app: AppHandle,
+    path: String,
+    status: Option<Vec<TaskStatus>>,
+    folder: Option<String>,
+    due_from: Option<String>,
+    due_to: Option<String>,
+) -> CommandResult<Vec<TaskItem>> {
+    for date in [&due_from, &due_to].into_iter().flatten() {
+        if !is_date(date) {
+            return Err(CommandError::invalid_input(format!(
+                "'{}' is not a date, use YYYY-MM-DD",
+                date
+            ))
+            .logged());
+        }
+    }
+
+    let resolved = resolve_path(&app, &path)?;
+    let folder = match folder {
+        Some(folder) => Some(resolve_path(&app, &folder)?),
+        None => None,
+    };
+    let filter = TaskFilter {
+        status: status.unwrap_or_default(),
+        folder,
+        due_from,
+        due_to,
+    };
+
+    let start = std::time::Instant::now();
+    let tasks = vault_index(&app, &resolved)?.tasks(&filter);
+
+    log::info!(
+        "Found {} task(s) in '{}' in {:.2}ms",
+        tasks.len(),
+        path,
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(tasks)
+}
+
+/// Check or uncheck the task on a line of a note, changing only its checkbox
+/// Open and in-progress tasks become done; done and cancelled tasks become open.
+/// `text` and `status` are the task as shown; if the line no longer holds that
+/// task, the note is left alone and a conflict is returned
+#[tauri::command]
+pub async fn toggle_task(
+    app: AppHandle,
+    path: String,
+    line: usize,
+    text: String,
+    status: TaskStatus,
+) -> CommandResult<TaskItem> {
+    log::info!("Toggling task on line {} of: {}", line, path);
+
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    if !is_note(&resolved) {
+        return Err(CommandError::invalid_input(format!("Not a note: {}", path)).logged());
+    }
+    let (updated, task) = app.state::<WriteQueue>().exclusive(&[&resolved], || {
+        let content = fs::read_to_string(&resolved)
+            .map_err(|e| CommandError::io("Failed to read", &resolved, e).logged())?;
+        let (updated, task) = tasks::toggle_task(&content, line, &text, status)
+            .map_err(|e| toggle_error(&path, line, e))?;
+        fs::write(&resolved, &updated)
+            .map_err(|e| CommandError::io("Failed to write", &resolved, e).logged())?;
+        Ok(Edit::written((updated, task)))
+    })?;
+    index_note(&app, &resolved, &updated);
+
+    Ok(TaskItem {
+        path: shown_path(&app, &resolved),
+        task,
+    })
+}
+
+fn toggle_error(path: &str, line: usize, err: ToggleError) -> CommandError {
+    match err {
+        ToggleError::NotATask => {
+            CommandError::invalid_input(format!("Line {} of '{}' is not a task", line, path))
+        }
+        ToggleError::Changed => CommandError::conflict(
+            path,
+            format!(
+                "The task on line {} of '{}' changed since it was shown, left as saved",
+                line, path
+            ),
+        ),
+    }
+    .logged()
+}
@@ -0,0 +1,492 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+use super::file_tree::{create_untitled, FileNode};
+use super::links::refresh_index;
+use super::vault::{ensure_lock_owned, resolve_path, shown_path, stored_setting, vault_root};
+use crate::error::{CommandError, CommandResult};
+use crate::index::is_note;
+use crate::templates::{expand_template, TemplateContext};
+use crate::write_queue::{Edit, WriteQueue};
+
+/// Store file the frontend's TemplateService kept folder templates in, before
+/// templates moved into the vault
+const TEMPLATE_STORE_FILE: &str = "templates.json";
+
+/// Templates folder used while the `templatesFolder` setting is empty
+const DEFAULT_TEMPLATES_FOLDER: &str = "Templates";
+
+/// Name of the template new notes in a folder start from
+const DEFAULT_TEMPLATE: &str = "Default";
+
+/// A note template in the vault's templates folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteTemplate {
+    /// File name without `.md`
+    pub name: String,
+    pub path: String,
+    /// Folder the template is for, relative to the vault root; empty for every folder
+    pub folder: String,
+}
+
+/// The vault's templates folder, resolved
+///
+/// Templates for a folder live at the same relative path below it:
+/// `Templates/Projects/Meeting.md` is offered in `Projects` and its subfolders,
+/// templates directly in `Templates` in every folder.
+fn templates_dir(app: &AppHandle, root: &Path) -> CommandResult<PathBuf> {
+    let folder = stored_setting(app, "templatesFolder")?
+        .and_then(|folder| folder.as_str().map(|folder| folder.trim().to_string()))
+        .filter(|folder| !folder.is_empty())
+        .unwrap_or_else(|| DEFAULT_TEMPLATES_FOLDER.to_string());
+    resolve_path(app, &root.join(folder).to_string_lossy())
+}
+
+/// Relative path with `/` separators, as shown to the user
+fn display_path(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Templates directly in one folder of the templates folder, ordered by name
+fn templates_in(dir: &Path, relative: &Path) -> Vec<NoteTemplate> {
+    let Ok(entries) = fs::read_dir(dir.join(relative)) else {
+        return Vec::new();
+    };
+
+    let mut templates: Vec<NoteTemplate> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_note(path))
+        .map(|path| NoteTemplate {
+            name: path
+                .file_stem()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_string_lossy().to_string(),
+            folder: display_path(relative),
+        })
+        .collect();
+    templates.sort_by_key(|template| template.name.to_lowercase());
+    templates
+}
+
+/// Every template in the templates folder, by folder and name
+fn all_templates(dir: &Path, relative: &Path, templates: &mut Vec<NoteTemplate>) {
+    templates.extend(templates_in(dir, relative));
+
+    let Ok(entries) = fs::read_dir(dir.join(relative)) else {
+        return;
+    };
+    let mut folders: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.path().is_dir() && !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .map(|entry| relative.join(entry.file_name()))
+        .collect();
+    folders.sort();
+    for folder in folders {
+        all_templates(dir, &folder, templates);
+    }
+}
+
+/// Templates offered in a folder: its own first, then those of each parent
+/// A template hides those of the same name further up
+fn templates_for(dir: &Path, root: &Path, folder: &Path) -> Vec<NoteTemplate> {
+    let mut seen = HashSet::new();
+    let mut templates = Vec::new();
+    for ancestor in folder.ancestors() {
+        let Ok(relative) = ancestor.strip_prefix(root) else {
+            break;
+        };
+        for template in templates_in(dir, relative) {
+            if seen.insert(template.name.to_lowercase()) {
+                templates.push(template);
+            }
+        }
+    }
+    templates
+}
+
+/// Templates from the app data store, by resolved folder
+///
+/// Keys are the folder paths the file tree showed when the templates were set,
+/// so they are canonicalized before use.
+fn legacy_templates(app: &AppHandle) -> HashMap<PathBuf, String> {
+    let store = match app.store(TEMPLATE_STORE_FILE) {
+        Ok(store) => store,
+        Err(e) => {
+            log::error!("Failed to open template store: {}", e);
+            return HashMap::new();
+        }
+    };
+
+    store
+        .get("data")
+        .and_then(|data| data.get("templates")?.as_object().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|(key, template)| {
+            Some((fs::canonicalize(key).ok()?, template.as_str()?.to_string()))
+        })
+        .collect()
+}
+
+/// Drop a folder's template from the app data store once it lives in the vault
+fn remove_legacy_template(app: &AppHandle, folder: &Path) {
+    let Ok(store) = app.store(TEMPLATE_STORE_FILE) else {
+        return;
+    };
+    let Some(mut data) = store.get("data") else {
+        return;
+    };
+    let Some(templates) = data
+        .get_mut("templates")
+        .and_then(|templates| templates.as_object_mut())
+    else {
+        return;
+    };
+
+    let before = templates.len();
+    templates.retain(|key, _| fs::canonicalize(key).ok().is_none_or(|key| key != folder));
+    if templates.len() != before {
+        store.set("data", data);
+        if let Err(e) = store.save() {
+            log::error!("Failed to save template store: {}", e);
+        }
+    }
+}
+
+/// Default template of a folder itself, not inherited
+fn own_template(
+    dir: &Path,
+    root: &Path,
+    folder: &Path,
+    legacy: &HashMap<PathBuf, String>,
+) -> Option<String> {
+    let relative = folder.strip_prefix(root).ok()?;
+    let file = dir.join(relative).join(format!("{}.md", DEFAULT_TEMPLATE));
+    if file.is_file() {
+        return fs::read_to_string(&file)
+            .map_err(|e| log::error!("Failed to read template '{}': {}", file.display(), e))
+            .ok();
+    }
+    legacy.get(folder).cloned()
+}
+
+/// Template new notes in a folder start from: the folder's `Default` template,
+/// or else the closest parent folder's
+///
+/// Folders in the templates folder get none, so new templates keep their
+/// variables.
+pub fn folder_template(app: &AppHandle, folder: &Path) -> Option<String> {
+    let root = vault_root(app, folder).ok()?;
+    let dir = templates_dir(app, &root).ok()?;
+    if folder.starts_with(&dir) {
+        return None;
+    }
+
+    find_template(&dir, &root, folder, &legacy_templates(app))
+}
+
+/// The `Default` template of a folder or its closest parent that has one
+fn find_template(
+    dir: &Path,
+    root: &Path,
+    folder: &Path,
+    legacy: &HashMap<PathBuf, String>,
+) -> Option<String> {
+    folder.ancestors().find_map(|ancestor| {
+        let template = own_template(dir, root, ancestor, legacy)?;
+        log::info!("Using template of '{}'", ancestor.display());
+        Some(template)
+    })
+}
+
+/// Expand the variables of a template for a new note
+fn expand_for(template: &str, note: &Path) -> String {
+    let title = note
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let folder = note
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    expand_template(
+        template,
+        &TemplateContext {
+            title: &title,
+            folder: &folder,
+            now: Local::now().naive_local(),
+        },
+    )
+}
+
+/// Content for a new note in a folder: the folder's template with its
+/// variables expanded, or nothing when no template applies
+pub fn new_note_content(app: &AppHandle, folder: &Path, note: &Path) -> String {
+    folder_template(app, folder)
+        .map(|template| expand_for(&template, note))
+        .unwrap_or_default()
+}
+
+/// Templates in the vault's templates folder
+/// With a folder, only those offered there: its own first, then inherited ones
+#[tauri::command]
+pub async fn list_templates(
+    app: AppHandle,
+    path: String,
+    folder: Option<String>,
+) -> CommandResult<Vec<NoteTemplate>> {
+    let resolved = resolve_path(&app, &path)?;
+    let root = vault_root(&app, &resolved)?;
+    let dir = templates_dir(&app, &root)?;
+
+    let mut templates = match folder {
+        Some(folder) => templates_for(&dir, &root, &resolve_path(&app, &folder)?),
+        None => {
+            let mut templates = Vec::new();
+            all_templates(&dir, Path::new(""), &mut templates);
+            templates
+        }
+    };
+    for template in &mut templates {
+        template.path = shown_path(&app, Path::new(&template.path));
+    }
+
+    log::info!(
+        "Found {} template(s) in '{}'",
+        templates.len(),
+        dir.display()
+    );
+    Ok(templates)
+}
+
+/// Create a note in a folder from one of the templates offered there
+#[tauri::command]
+pub async fn create_note_from_template(
+    app: AppHandle,
+    folder: String,
+    template: String,
+) -> CommandResult<FileNode> {
+    log::info!("Creating note from template '{}' in: {}", template, folder);
+
+    let resolved = resolve_path(&app, &folder)?;
+    ensure_lock_owned(&app, &resolved)?;
+    if !resolved.is_dir() {
+        return Err(CommandError::not_a_directory(&folder).logged());
+    }
+    let root = vault_root(&app, &resolved)?;
+    let dir = templates_dir(&app, &root)?;
+
+    let source = templates_for(&dir, &root, &resolved)
+        .into_iter()
+        .find(|candidate| candidate.name.eq_ignore_ascii_case(template.trim()))
+        .ok_or_else(|| CommandError::not_found(&template, "Template").logged())?;
+    let content = fs::read_to_string(&source.path)
+        .map_err(|e| CommandError::io("Failed to read template", &source.path, e).logged())?;
+
+    let file_name = create_untitled(&app.state::<WriteQueue>(), &resolved, |file_path| {
+        expand_for(&content, file_path)
+    })?;
+    let file_path = resolved.join(&file_name);
+    refresh_index(&app, [&file_path]);
+
+    log::info!(
+        "Created '{}' from template '{}'",
+        file_path.display(),
+        source.name
+    );
+    Ok(FileNode::new(
+        &Path::new(&folder).join(&file_name),
+        "file",
+        None,
+    ))
+}
+
+/// Default template of a folder, which new notes there start from
+/// Only the folder's own template, not one inherited from a parent
+#[tauri::command]
+pub async fn get_folder_template(app: AppHandle, path: String) -> CommandResult<Option<String>> {
+    let resolved = resolve_path(&app, &path)?;
+    let root = vault_root(&app, &resolved)?;
+    let dir = templates_dir(&app, &root)?;
+    Ok(own_template(
+        &dir,
+        &root,
+        &resolved,
+        &legacy_templates(&app),
+    ))
+}
+
+/// Set the default template of a folder, or remove it when the content is empty
+/// The template is saved to the templates folder as `Default.md`
+#[tauri::command]
+pub async fn set_folder_template(
+    app: AppHandle,
+    path: String,
+    content: String,
+) -> CommandResult<()> {
+    let resolved = resolve_path(&app, &path)?;
+    ensure_lock_owned(&app, &resolved)?;
+    let root = vault_root(&app, &resolved)?;
+    let dir = templates_dir(&app, &root)?;
+    let relative = resolved
+        .strip_prefix(&root)
+        .map_err(|_| CommandError::outside_vault(&path).logged())?;
+    // Folders inside the templates folder may be symlinks too
+    let file = resolve_path(
+        &app,
+        &dir.join(relative)
+            .join(format!("{}.md", DEFAULT_TEMPLATE))
+            .to_string_lossy(),
+    )?;
+
+    // The template may be open in the editor, like any note
+    app.state::<WriteQueue>().exclusive(&[&file], || {
+        if content.trim().is_empty() {
+            if !file.is_file() {
+                return Ok(Edit::unchanged(()));
+            }
+            fs::remove_file(&file)
+                .map_err(|e| CommandError::io("Failed to remove template", &file, e).logged())?;
+            log::info!("Removed template of: {}", path);
+        } else {
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| CommandError::io("Failed to create folder", parent, e).logged())?;
+            }
+            fs::write(&file, &content)
+                .map_err(|e| CommandError::io("Failed to save template", &file, e).logged())?;
+            log::info!("Saved template of '{}' to: {}", path, file.display());
+        }
+        Ok(Edit::written(()))
+    })?;
+    refresh_index(&app, [&file]);
+    remove_legacy_template(&app, &resolved);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_inherit_the_closest_template() {
+        let root = std::env::temp_dir().join(format!("yana-templates-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let dir = root.join(DEFAULT_TEMPLATES_FOLDER);
+        fs::create_dir_all(dir.join("Projects/Archive")).unwrap();
+        fs::write(dir.join("Default.md"), "vault").unwrap();
+        fs::write(dir.join("Projects/Default.md"), "projects").unwrap();
+        // Other templates are offered, not applied
+        fs::write(dir.join("Projects/Archive/Meeting.md"), "meeting").unwrap();
+
+        let legacy = HashMap::from([
+            (root.join("Journal"), "journal".to_string()),
+            (root.join("Projects"), "stale".to_string()),
+        ]);
+        let find = |folder: &str| find_template(&dir, &root, &root.join(folder), &legacy);
+
+        assert_eq!(find("").as_deref(), Some("vault"));
+        assert_eq!(find("Inbox").as_deref(), Some("vault"));
+        // A template in the store only counts until the vault has one
+        assert_eq!(find("Projects").as_deref(), Some("projects"));
+        assert_eq!(find("Projects/Archive/2023").as_deref(), Some("projects"));
+        assert_eq!(find("Journal/2024").as_deref(), Some("journal"));
+
+        fs::remove_file(dir.join("Default.md")).unwrap();
+        assert_eq!(find("Inbox"), None);
+        assert_eq!(
+            find_template(&dir, &root, Path::new("/elsewhere"), &HashMap::new()),
+            None
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn templates_offered_per_folder() {
+        let root = std::env::temp_dir().join(format!("yana-template-list-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let dir = root.join(DEFAULT_TEMPLATES_FOLDER);
+        fs::create_dir_all(dir.join("Projects/Archive")).unwrap();
+        fs::create_dir_all(dir.join(".hidden")).unwrap();
+        for file in [
+            "meeting.md",
+            "Daily.md",
+            "notes.txt",
+            "Projects/Meeting.md",
+            "Projects/Brief.md",
+            "Projects/Archive/Default.md",
+            ".hidden/Secret.md",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        let names = |templates: Vec<NoteTemplate>| -> Vec<(String, String)> {
+            templates
+                .into_iter()
+                .map(|template| (template.folder, template.name))
+                .collect()
+        };
+        let pair = |folder: &str, name: &str| (folder.to_string(), name.to_string());
+
+        // A folder's own templates come first and hide those of the same name above
+        assert_eq!(
+            names(templates_for(
+                &dir,
+                &root,
+                &root.join("Projects/Archive/2024")
+            )),
+            [
+                pair("Projects/Archive", "Default"),
+                pair("Projects", "Brief"),
+                pair("Projects", "Meeting"),
+                pair("", "Daily"),
+            ]
+        );
+        assert_eq!(
+            names(templates_for(&dir, &root, &root.join("Inbox"))),
+            [pair("", "Daily"), pair("", "meeting")]
+        );
+        assert!(templates_for(&dir, &root, Path::new("/elsewhere")).is_empty());
+
+        let mut all = Vec::new();
+        all_templates(&dir, Path::new(""), &mut all);
+        assert_eq!(
+            names(all),
+            [
+                pair("", "Daily"),
+                pair("", "meeting"),
+                pair("Projects", "Brief"),
+                pair("Projects", "Meeting"),
+                pair("Projects/Archive", "Default"),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn variables_come_from_the_new_note() {
+        assert_eq!(
+            expand_for(
+                "# {{title}}\nin {{ folder }}{{cursor}}",
+                Path::new("/vault/Projects/Launch plan.md")
+            ),
+            "# Launch plan\nin Projects"
+        );
+    }
+}
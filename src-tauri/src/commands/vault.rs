@@ -0,0 +1,341 @@
+use crate::error::{CommandError, CommandResult};
+use crate::sandbox::{read_granted_roots, write_granted_roots, VaultSandbox};
+use crate::vault_lock::{LockStatus, VaultLocks};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_store::StoreExt;
+
+/// Store file the frontend persists the vault list to
+const VAULT_STORE_FILE: &str = "vaults.json";
+
+/// Read the vault paths persisted by the frontend's VaultService
+fn stored_vault_paths(app: &AppHandle) -> CommandResult<Vec<String>> {
+    let store = app.store(VAULT_STORE_FILE).map_err(|e| {
+        CommandError::internal(format!("Failed to open vault store: {}", e)).logged()
+    })?;
+
+    let paths = store
+        .get("data")
+        .and_then(|data| data.get("vaults").cloned())
+        .and_then(|vaults| vaults.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|vault| vault.get("path").and_then(|p| p.as_str()))
+        .map(|p| p.to_string())
+        .collect();
+
+    Ok(paths)
+}
+
+/// File in the app data folder listing the vault roots picked in the folder dialog
+const GRANTED_ROOTS_FILE: &str = "vault-roots";
+
+fn granted_roots_file(app: &AppHandle) -> CommandResult<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(GRANTED_ROOTS_FILE))
+        .map_err(|e| {
+            CommandError::internal(format!("Failed to locate app data folder: {}", e)).logged()
+        })
+}
+
+/// Store file the frontend's SettingsService persists settings to
+const SETTINGS_STORE_FILE: &str = "settings.json";
+
+/// Read one setting persisted by the frontend's SettingsService
+pub fn stored_setting(app: &AppHandle, key: &str) -> CommandResult<Option<Value>> {
+    let store = app.store(SETTINGS_STORE_FILE).map_err(|e| {
+        CommandError::internal(format!("Failed to open settings store: {}", e)).logged()
+    })?;
+
+    Ok(store
+        .get("data")
+        .and_then(|data| data.get("settings")?.get(key).cloned()))
+}
+
+/// Reload the vault roots into the sandbox and widen the asset protocol scope
+/// to cover them.
+///
+/// Only folders the user picked through [`choose_vault_folder`] are ever
+/// granted. The webview can write the vault store, so the persisted vault list
+/// can only narrow the granted roots, never add to them. Grants of vaults no
+/// longer in the list are forgotten. Vaults without a grant, such as ones added
+/// before grants existed, stay closed until the user picks them again.
+///
+/// The asset protocol scope can only grow at runtime, so a removed vault stays
+/// reachable through `asset://` until the app restarts.
+pub fn load_vault_roots(app: &AppHandle) -> CommandResult<Vec<PathBuf>> {
+    let paths = stored_vault_paths(app)?;
+    let file = granted_roots_file(app)?;
+
+    let granted = match read_granted_roots(&file) {
+        Ok(granted) => granted.unwrap_or_default(),
+        Err(e) => {
+            log::error!("Failed to read granted vault roots: {}", e);
+            Vec::new()
+        }
+    };
+
+    let stored: Vec<PathBuf> = paths
+        .iter()
+        .map(|path| fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path)))
+        .collect();
+    let paths: Vec<String> = paths
+        .iter()
+        .zip(&stored)
+        .filter(|(_, canonical)| granted.contains(canonical))
+        .map(|(path, _)| path.clone())
+        .collect();
+    let kept: Vec<PathBuf> = granted
+        .iter()
+        .filter(|root| stored.contains(root))
+        .cloned()
+        .collect();
+    if kept.len() != granted.len() {
+        if let Err(e) = write_granted_roots(&file, &kept) {
+            log::error!("Failed to save granted vault roots: {}", e);
+        }
+    }
+
+    let state = app.state::<Mutex<VaultSandbox>>();
+    let mut sandbox = state.lock().map_err(|e| {
+        CommandError::internal(format!("Failed to lock vault sandbox: {}", e)).logged()
+    })?;
+
+    let roots = sandbox.set_roots(&paths);
+
+    let asset_scope = app.asset_protocol_scope();
+    for root in &roots {
+        if let Err(e) = asset_scope.allow_directory(root, true) {
+            log::error!(
+                "Failed to allow '{}' in asset protocol scope: {}",
+                root.display(),
+                e
+            );
+        }
+    }
+
+    log::info!("Registered {} vault root(s)", roots.len());
+    Ok(roots)
+}
+
+/// Resolve a path for reading or writing, rejecting anything outside the vaults
+pub fn resolve_path(app: &AppHandle, path: &str) -> CommandResult<PathBuf> {
+    let state = app.state::<Mutex<VaultSandbox>>();
+    let sandbox = state.lock().map_err(|e| {
+        CommandError::internal(format!("Failed to lock vault sandbox: {}", e)).logged()
+    })?;
+
+    sandbox.resolve(path).map_err(CommandError::logged)
+}
+
+/// Innermost vault root containing an already resolved path
+pub fn vault_root(app: &AppHandle, path: &Path) -> CommandResult<PathBuf> {
+    let state = app.state::<Mutex<VaultSandbox>>();
+    let sandbox = state.lock().map_err(|e| {
+        CommandError::internal(format!("Failed to lock vault sandbox: {}", e)).logged()
+    })?;
+
+    sandbox
+        .roots()
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .cloned()
+        .ok_or_else(|| CommandError::outside_vault(path).logged())
+}
+
+/// A resolved path the way the frontend names it, under the vault root as it
+/// was registered rather than the canonical root
+///
+/// Paths reported back to the frontend go through this, so they compare equal
+/// to the paths in the file tree on Windows and in vaults behind a symlink.
+pub fn shown_path(app: &AppHandle, path: &Path) -> String {
+    let state = app.state::<Mutex<VaultSandbox>>();
+    let shown = match state.lock() {
+        Ok(sandbox) => sandbox.shown(path),
+        Err(e) => {
+            log::error!("Failed to lock vault sandbox: {}", e);
+            path.to_path_buf()
+        }
+    };
+    shown.to_string_lossy().to_string()
+}
+
+/// Fail unless this instance holds the lock of the vault containing an already
+/// resolved path
+///
+/// Every command that changes a vault checks this, so an instance that was
+/// refused the lock, or had it taken over, cannot overwrite the other one's
+/// changes. The lock file is read again when its modification time changes or
+/// the watcher reports a change to it, which is how a takeover is noticed.
+pub fn ensure_lock_owned(app: &AppHandle, path: &Path) -> CommandResult<()> {
+    let root = vault_root(app, path)?;
+
+    let state = app.state::<Mutex<VaultLocks>>();
+    let mut locks = state.lock().map_err(|e| {
+        CommandError::internal(format!("Failed to lock vault locks: {}", e)).logged()
+    })?;
+
+    if locks.owns(&root) {
+        Ok(())
+    } else {
+        Err(CommandError::conflict(
+            path,
+            "Vault is open in another Yana instance, changes are not saved",
+        )
+        .logged())
+    }
+}
+
+/// Make [`ensure_lock_owned`] read the lock file again after the watcher saw a
+/// change in a vault's meta folder, e.g. another instance taking the lock over
+pub fn forget_lock_owner<P: AsRef<Path>>(app: &AppHandle, paths: impl IntoIterator<Item = P>) {
+    let state = app.state::<Mutex<VaultLocks>>();
+    let mut locks = match state.lock() {
+        Ok(locks) => locks,
+        Err(e) => {
+            log::error!("Failed to lock vault locks: {}", e);
+            return;
+        }
+    };
+    for path in paths {
+        locks.lock_changed(path.as_ref());
+    }
+}
+
+/// Resolve a path for deleting or renaming the entry itself, rejecting anything
+/// outside the vaults as well as the vault roots
+pub fn resolve_entry(app: &AppHandle, path: &str) -> CommandResult<PathBuf> {
+    let state = app.state::<Mutex<VaultSandbox>>();
+    let sandbox = state.lock().map_err(|e| {
+        CommandError::internal(format!("Failed to lock vault sandbox: {}", e)).logged()
+    })?;
+
+    sandbox.resolve_entry(path).map_err(CommandError::logged)
+}
+
+/// Let the user pick a folder to open as a vault, and grant access to it
+/// Returns the picked folder, or nothing when the dialog was cancelled
+#[tauri::command]
+pub async fn choose_vault_folder(app: AppHandle) -> CommandResult<Option<String>> {
+    let Some(folder) = app
+        .dialog()
+        .file()
+        .set_title("Choose Vault")
+        .set_can_create_directories(true)
+        .blocking_pick_folder()
+    else {
+        return Ok(None);
+    };
+    let folder = folder
+        .into_path()
+        .map_err(|e| CommandError::internal(format!("Invalid folder: {}", e)).logged())?;
+
+    let canonical = fs::canonicalize(&folder)
+        .map_err(|e| CommandError::io("Failed to resolve path", &folder, e).logged())?;
+    if canonical.parent().is_none() {
+        return Err(CommandError::invalid_path(
+            &folder,
+            "Refusing to register filesystem root as a vault",
+        )
+        .logged());
+    }
+
+    let file = granted_roots_file(&app)?;
+    let mut granted = read_granted_roots(&file)
+        .map_err(|e| CommandError::io("Failed to read granted vault roots", &file, e).logged())?
+        .unwrap_or_default();
+    if !granted.contains(&canonical) {
+        granted.push(canonical);
+        write_granted_roots(&file, &granted).map_err(|e| {
+            CommandError::io("Failed to save granted vault roots", &file, e).logged()
+        })?;
+    }
+
+    log::info!("Granted vault folder: {}", folder.display());
+    Ok(Some(folder.to_string_lossy().to_string()))
+}
+
+/// Re-read the vault list after the frontend changed it
+/// Returns the canonical vault roots now accessible
+#[tauri::command]
+pub async fn sync_vault_roots(app: AppHandle) -> CommandResult<Vec<String>> {
+    let roots = load_vault_roots(&app)?;
+    Ok(roots
+        .iter()
+        .map(|root| root.to_string_lossy().to_string())
+        .collect())
+}
+
+/// Resolve a path that must be one of the registered vault roots
+fn resolve_vault_root(app: &AppHandle, path: &str) -> CommandResult<PathBuf> {
+    let resolved = resolve_path(app, path)?;
+
+    let state = app.state::<Mutex<VaultSandbox>>();
+    let sandbox = state.lock().map_err(|e| {
+        CommandError::internal(format!("Failed to lock vault sandbox: {}", e)).logged()
+    })?;
+
+    if sandbox.roots().contains(&resolved) {
+        Ok(resolved)
+    } else {
+        Err(CommandError::invalid_path(path, "Path is not a vault root").logged())
+    }
+}
+
+/// Report whether a vault is locked by this or another Yana instance
+#[tauri::command]
+pub async fn get_vault_lock_status(app: AppHandle, path: String) -> CommandResult<LockStatus> {
+    let root = resolve_vault_root(&app, &path)?;
+
+    let state = app.state::<Mutex<VaultLocks>>();
+    let locks = state.lock().map_err(|e| {
+        CommandError::internal(format!("Failed to lock vault locks: {}", e)).logged()
+    })?;
+
+    Ok(locks.status(&root))
+}
+
+/// Take the advisory lock of a vault
+/// Fails with a conflict if another live instance holds it, unless `force` is set
+#[tauri::command]
+pub async fn acquire_vault_lock(
+    app: AppHandle,
+    path: String,
+    force: Option<bool>,
+) -> CommandResult<LockStatus> {
+    log::info!("Acquiring vault lock for: {}", path);
+
+    let root = resolve_vault_root(&app, &path)?;
+
+    let state = app.state::<Mutex<VaultLocks>>();
+    let mut locks = state.lock().map_err(|e| {
+        CommandError::internal(format!("Failed to lock vault locks: {}", e)).logged()
+    })?;
+
+    let status = locks
+        .acquire(&root, force.unwrap_or(false))
+        .map_err(CommandError::logged)?;
+    log::info!("Acquired vault lock for: {}", path);
+    Ok(status)
+}
+
+/// Release the advisory lock of a vault if this instance holds it
+#[tauri::command]
+pub async fn release_vault_lock(app: AppHandle, path: String) -> CommandResult<()> {
+    log::info!("Releasing vault lock for: {}", path);
+
+    let root = resolve_vault_root(&app, &path)?;
+
+    let state = app.state::<Mutex<VaultLocks>>();
+    let mut locks = state.lock().map_err(|e| {
+        CommandError::internal(format!("Failed to lock vault locks: {}", e)).logged()
+    })?;
+
+    locks.release(&root).map_err(CommandError::logged)
+}
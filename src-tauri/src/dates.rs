@@ -0,0 +1,355 @@
+//! Moment-style date formats, as used for periodic note names and template dates
+//!
+//! Supported tokens:
+//!
+//! | Token                      | Meaning                                   |
+//! |----------------------------|-------------------------------------------|
+//! | `YYYY` `YY`                | year                                      |
+//! | `gggg` `GGGG`              | ISO week-numbering year                   |
+//! | `Q`                        | quarter                                   |
+//! | `MMMM` `MMM` `MM` `M`      | month name, short name, number            |
+//! | `DDDD` `DDD`               | day of the year                           |
+//! | `DD` `D` `Do`              | day of the month, `Do` as `1st`           |
+//! | `dddd` `ddd` `dd` `d` `E`  | weekday name; `d` from Sunday = 0, `E` from Monday = 1 |
+//! | `ww` `w` `WW` `W`          | ISO week                                  |
+//! | `HH` `H` `hh` `h` `A` `a`  | hour of the day or of AM/PM               |
+//! | `mm` `m` `ss` `s`          | minute, second                            |
+//!
+//! Text in `[brackets]` is copied as is, as is any other character.
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike, Weekday};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Weekday names from Sunday
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// Tokens, longer ones before those they start with
+const TOKENS: [&str; 33] = [
+    "YYYY", "gggg", "GGGG", "MMMM", "DDDD", "dddd", "MMM", "DDD", "ddd", "YY", "MM", "DD", "Do",
+    "dd", "ww", "WW", "HH", "hh", "mm", "ss", "Q", "M", "D", "d", "E", "w", "W", "H", "h", "m",
+    "s", "A", "a",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Part<'a> {
+    Token(&'static str),
+    Literal(&'a str),
+}
+
+fn parts(format: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = format;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(escaped) = rest.strip_prefix('[') {
+            let end = escaped.find(']').unwrap_or(escaped.len());
+            parts.push(Part::Literal(&escaped[..end]));
+            rest = escaped.get(end + 1..).unwrap_or("");
+        } else if let Some(token) = TOKENS.iter().find(|token| rest.starts_with(*token)) {
+            parts.push(Part::Token(token));
+            rest = &rest[token.len()..];
+        } else {
+            parts.push(Part::Literal(&rest[..c.len_utf8()]));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    parts
+}
+
+/// Format a date and time with a moment-style format such as `YYYY-MM-DD`
+pub fn format_date(at: &NaiveDateTime, format: &str) -> String {
+    let mut out = String::new();
+    for part in parts(format) {
+        let token = match part {
+            Part::Literal(text) => {
+                out.push_str(text);
+                continue;
+            }
+            Part::Token(token) => token,
+        };
+        let month = MONTHS[at.month0() as usize];
+        let weekday = WEEKDAYS[at.weekday().num_days_from_sunday() as usize];
+        let text = match token {
+            "YYYY" => format!("{:04}", at.year()),
+            "YY" => format!("{:02}", at.year().rem_euclid(100)),
+            "gggg" | "GGGG" => format!("{:04}", at.iso_week().year()),
+            "Q" => (at.month0() / 3 + 1).to_string(),
+            "MMMM" => month.to_string(),
+            "MMM" => month[..3].to_string(),
+            "MM" => format!("{:02}", at.month()),
+            "M" => at.month().to_string(),
+            "DDDD" => format!("{:03}", at.ordinal()),
+            "DDD" => at.ordinal().to_string(),
+            "DD" => format!("{:02}", at.day()),
+            "D" => at.day().to_string(),
+            "Do" => format!("{}{}", at.day(), ordinal_suffix(at.day())),
+            "dddd" => weekday.to_string(),
+            "ddd" => weekday[..3].to_string(),
+            "dd" => weekday[..2].to_string(),
+            "d" => at.weekday().num_days_from_sunday().to_string(),
+            "E" => at.weekday().number_from_monday().to_string(),
+            "ww" | "WW" => format!("{:02}", at.iso_week().week()),
+            "w" | "W" => at.iso_week().week().to_string(),
+            "HH" => format!("{:02}", at.hour()),
+            "H" => at.hour().to_string(),
+            "hh" => format!("{:02}", at.hour12().1),
+            "h" => at.hour12().1.to_string(),
+            "mm" => format!("{:02}", at.minute()),
+            "m" => at.minute().to_string(),
+            "ss" => format!("{:02}", at.second()),
+            "s" => at.second().to_string(),
+            "A" => if at.hour12().0 { "PM" } else { "AM" }.to_string(),
+            "a" => if at.hour12().0 { "pm" } else { "am" }.to_string(),
+            _ => token.to_string(),
+        };
+        out.push_str(&text);
+    }
+    out
+}
+
+/// Read a date back from text written with a moment-style format
+///
+/// Fields the format leaves out default to the start of the period it names: a
+/// `YYYY-MM` name is the first of the month, `gggg-[W]ww` the Monday of the
+/// week. Times and weekday names are accepted but ignored. The whole text has to
+/// match the format.
+pub fn parse_date(text: &str, format: &str) -> Option<NaiveDate> {
+    let mut year = None;
+    let mut week_year = None;
+    let mut quarter = None;
+    let mut month = None;
+    let mut day = None;
+    let mut ordinal = None;
+    let mut week = None;
+    let mut rest = text;
+
+    for part in parts(format) {
+        let token = match part {
+            Part::Literal(literal) => {
+                rest = rest.strip_prefix(literal)?;
+                continue;
+            }
+            Part::Token(token) => token,
+        };
+        match token {
+            "YYYY" => year = Some(number(&mut rest, 4, 4)? as i32),
+            "YY" => year = Some(2000 + number(&mut rest, 2, 2)? as i32),
+            "gggg" | "GGGG" => week_year = Some(number(&mut rest, 4, 4)? as i32),
+            "Q" => quarter = Some(number(&mut rest, 1, 1)?),
+            "MMMM" | "MMM" => {
+                let index = name(&mut rest, &MONTHS, token == "MMM")?;
+                month = Some(index as u32 + 1);
+            }
+            "MM" => month = Some(number(&mut rest, 2, 2)?),
+            "M" => month = Some(number(&mut rest, 1, 2)?),
+            "DDDD" => ordinal = Some(number(&mut rest, 3, 3)?),
+            "DDD" => ordinal = Some(number(&mut rest, 1, 3)?),
+            "DD" => day = Some(number(&mut rest, 2, 2)?),
+            "D" => day = Some(number(&mut rest, 1, 2)?),
+            "Do" => {
+                let value = number(&mut rest, 1, 2)?;
+                rest = rest.strip_prefix(ordinal_suffix(value))?;
+                day = Some(value);
+            }
+            "dddd" | "ddd" | "dd" => {
+                let len = match token {
+                    "dddd" => None,
+                    "ddd" => Some(3),
+                    _ => Some(2),
+                };
+                let matched = WEEKDAYS.iter().find_map(|weekday| {
+                    let weekday = len.map_or(*weekday, |len| &weekday[..len]);
+                    strip_prefix_ignore_case(rest, weekday)
+                })?;
+                rest = matched;
+            }
+            "d" | "E" => {
+                number(&mut rest, 1, 1)?;
+            }
+            "ww" | "WW" | "HH" | "hh" | "mm" | "ss" => {
+                let value = number(&mut rest, 2, 2)?;
+                if matches!(token, "ww" | "WW") {
+                    week = Some(value);
+                }
+            }
+            "w" | "W" | "H" | "h" | "m" | "s" => {
+                let value = number(&mut rest, 1, 2)?;
+                if matches!(token, "w" | "W") {
+                    week = Some(value);
+                }
+            }
+            "A" | "a" => {
+                rest = strip_prefix_ignore_case(rest, "am")
+                    .or_else(|| strip_prefix_ignore_case(rest, "pm"))?;
+            }
+            _ => return None,
+        }
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+
+    if let (Some(week_year), Some(week)) = (week_year.or(year), week) {
+        return NaiveDate::from_isoywd_opt(week_year, week, Weekday::Mon);
+    }
+    let year = year?;
+    match (month, day, ordinal, quarter) {
+        (Some(month), Some(day), _, _) => NaiveDate::from_ymd_opt(year, month, day),
+        (_, _, Some(ordinal), _) => NaiveDate::from_yo_opt(year, ordinal),
+        (Some(month), None, _, _) => NaiveDate::from_ymd_opt(year, month, 1),
+        (None, _, _, Some(quarter)) if (1..=4).contains(&quarter) => {
+            NaiveDate::from_ymd_opt(year, (quarter - 1) * 3 + 1, 1)
+        }
+        _ => NaiveDate::from_ymd_opt(year, 1, 1),
+    }
+}
+
+fn ordinal_suffix(day: u32) -> &'static str {
+    match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// Take between `min` and `max` ASCII digits off the front of the text
+fn number(rest: &mut &str, min: usize, max: usize) -> Option<u32> {
+    let len = rest
+        .bytes()
+        .take(max)
+        .take_while(u8::is_ascii_digit)
+        .count();
+    if len < min {
+        return None;
+    }
+    let value = rest[..len].parse().ok()?;
+    *rest = &rest[len..];
+    Some(value)
+}
+
+/// Take a name off the front of the text, returning its index
+fn name(rest: &mut &str, names: &[&str], short: bool) -> Option<usize> {
+    names.iter().enumerate().find_map(|(index, name)| {
+        let name = if short { &name[..3] } else { name };
+        let after = strip_prefix_ignore_case(rest, name)?;
+        *rest = after;
+        Some(index)
+    })
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = text.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &text[prefix.len()..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn format(date: NaiveDate, format: &str) -> String {
+        format_date(&date.and_hms_opt(14, 5, 9).unwrap(), format)
+    }
+
+    #[test]
+    fn formats_tokens() {
+        let day = date(2024, 3, 1);
+        assert_eq!(format(day, "YYYY-MM-DD"), "2024-03-01");
+        assert_eq!(format(day, "YY/M/D"), "24/3/1");
+        assert_eq!(format(day, "dddd, MMMM Do"), "Friday, March 1st");
+        assert_eq!(format(day, "ddd dd d E"), "Fri Fr 5 5");
+        assert_eq!(format(day, "[Q]Q DDDD DDD"), "Q1 061 61");
+        assert_eq!(format(day, "HH:mm:ss h A a"), "14:05:09 2 PM pm");
+        assert_eq!(format(day, "[YYYY today]"), "YYYY today");
+        assert_eq!(format(date(2024, 3, 11), "Do"), "11th");
+        assert_eq!(format(date(2024, 3, 22), "Do"), "22nd");
+    }
+
+    #[test]
+    fn round_trips() {
+        let formats = [
+            "YYYY-MM-DD",
+            "YYYY/MM/DD",
+            "DD.MM.YY",
+            "dddd, MMMM Do YYYY",
+            "ddd D MMM YYYY",
+            "YYYY-DDDD",
+            "YYYY-MM-DD HH:mm",
+        ];
+        let mut day = date(2023, 12, 25);
+        while day < date(2024, 3, 5) {
+            for format_str in formats {
+                let text = format(day, format_str);
+                assert_eq!(parse_date(&text, format_str), Some(day), "{}", text);
+            }
+            day = day.succ_opt().unwrap();
+        }
+    }
+
+    #[test]
+    fn periods_parse_to_their_start() {
+        assert_eq!(parse_date("2024-05", "YYYY-MM"), Some(date(2024, 5, 1)));
+        assert_eq!(parse_date("2024", "YYYY"), Some(date(2024, 1, 1)));
+        assert_eq!(parse_date("2024-Q3", "YYYY-[Q]Q"), Some(date(2024, 7, 1)));
+        assert_eq!(parse_date("2024/May", "YYYY/MMMM"), Some(date(2024, 5, 1)));
+        assert_eq!(parse_date("2024-may", "YYYY-MMM"), Some(date(2024, 5, 1)));
+    }
+
+    #[test]
+    fn iso_week_years_around_new_year() {
+        let cases = [
+            (date(2024, 12, 29), "2024-W52", date(2024, 12, 23)),
+            (date(2024, 12, 30), "2025-W01", date(2024, 12, 30)),
+            (date(2025, 1, 1), "2025-W01", date(2024, 12, 30)),
+            (date(2025, 1, 3), "2025-W01", date(2024, 12, 30)),
+            (date(2020, 12, 31), "2020-W53", date(2020, 12, 28)),
+            (date(2021, 1, 3), "2020-W53", date(2020, 12, 28)),
+            (date(2021, 1, 4), "2021-W01", date(2021, 1, 4)),
+            (date(2026, 12, 29), "2026-W53", date(2026, 12, 28)),
+            (date(2027, 1, 1), "2026-W53", date(2026, 12, 28)),
+        ];
+        for (day, name, monday) in cases {
+            assert_eq!(format(day, "gggg-[W]ww"), name, "{}", day);
+            assert_eq!(format(day, "GGGG-[W]WW"), name, "{}", day);
+            assert_eq!(parse_date(name, "gggg-[W]ww"), Some(monday), "{}", name);
+        }
+        assert_eq!(parse_date("2024-W54", "gggg-[W]ww"), None);
+    }
+
+    #[test]
+    fn rejects_text_that_does_not_match() {
+        assert_eq!(parse_date("2024-02-30", "YYYY-MM-DD"), None);
+        assert_eq!(parse_date("2024-02-01 extra", "YYYY-MM-DD"), None);
+        assert_eq!(parse_date("2024-2-01", "YYYY-MM-DD"), None);
+        assert_eq!(parse_date("March 2th 2024", "MMMM Do YYYY"), None);
+        assert_eq!(parse_date("Meeting notes", "YYYY-MM-DD"), None);
+    }
+}
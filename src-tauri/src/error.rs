@@ -0,0 +1,241 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// Stable, machine-readable error codes shared with the frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    PermissionDenied,
+    AlreadyExists,
+    Conflict,
+    OutsideVault,
+    InvalidPath,
+    NotADirectory,
+    NotAFile,
+    InvalidInput,
+    Io,
+    Internal,
+}
+
+impl ErrorCode {
+    /// Map an io error kind to the closest error code
+    fn from_io_kind(kind: io::ErrorKind) -> Self {
+        match kind {
+            io::ErrorKind::NotFound => ErrorCode::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            io::ErrorKind::AlreadyExists => ErrorCode::AlreadyExists,
+            io::ErrorKind::InvalidInput => ErrorCode::InvalidInput,
+            _ => ErrorCode::Io,
+        }
+    }
+}
+
+/// Error returned by every command
+///
+/// Serialized as `{ code, message, path, ioKind }` so the TypeScript services can
+/// branch on `code` instead of parsing `message`.
+#[derive(Debug, Clone)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub path: Option<String>,
+    pub io_kind: Option<io::ErrorKind>,
+}
+
+/// Result type for commands
+pub type CommandResult<T> = Result<T, CommandError>;
+
+impl CommandError {
+    fn new(code: ErrorCode, message: String, path: Option<&Path>) -> Self {
+        CommandError {
+            code,
+            message,
+            path: path.map(|p| p.to_string_lossy().to_string()),
+            io_kind: None,
+        }
+    }
+
+    /// The path does not exist
+    pub fn not_found(path: impl AsRef<Path>, what: &str) -> Self {
+        let path = path.as_ref();
+        Self::new(
+            ErrorCode::NotFound,
+            format!("{} does not exist: {}", what, path.display()),
+            Some(path),
+        )
+    }
+
+    /// The target path is already taken
+    pub fn already_exists(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        Self::new(
+            ErrorCode::AlreadyExists,
+            format!("Target path already exists: {}", path.display()),
+            Some(path),
+        )
+    }
+
+    /// The operation would clobber a change it did not expect
+    pub fn conflict(path: impl AsRef<Path>, message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message.into(), Some(path.as_ref()))
+    }
+
+    /// The path resolves outside of every registered vault
+    pub fn outside_vault(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        Self::new(
+            ErrorCode::OutsideVault,
+            format!("Path is outside of the open vaults: {}", path.display()),
+            Some(path),
+        )
+    }
+
+    /// The path is malformed or not allowed for this operation
+    pub fn invalid_path(path: impl AsRef<Path>, reason: &str) -> Self {
+        let path = path.as_ref();
+        Self::new(
+            ErrorCode::InvalidPath,
+            format!("{}: {}", reason, path.display()),
+            Some(path),
+        )
+    }
+
+    /// A directory was expected
+    pub fn not_a_directory(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        Self::new(
+            ErrorCode::NotADirectory,
+            format!("Path is not a directory: {}", path.display()),
+            Some(path),
+        )
+    }
+
+    /// A file was expected
+    pub fn not_a_file(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        Self::new(
+            ErrorCode::NotAFile,
+            format!("Path is not a file: {}", path.display()),
+            Some(path),
+        )
+    }
+
+    /// A command argument is invalid
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message.into(), None)
+    }
+
+    /// An io operation failed; the code is derived from the io error kind
+    pub fn io(context: &str, path: impl AsRef<Path>, err: io::Error) -> Self {
+        let path = path.as_ref();
+        CommandError {
+            code: ErrorCode::from_io_kind(err.kind()),
+            message: format!("{} '{}': {}", context, path.display(), err),
+            path: Some(path.to_string_lossy().to_string()),
+            io_kind: Some(err.kind()),
+        }
+    }
+
+    /// Something went wrong inside the backend itself (poisoned lock, watcher setup)
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message.into(), None)
+    }
+
+    /// Log the error and hand it back, for use at the point of return
+    pub fn logged(self) -> Self {
+        log::error!("{}", self);
+        self
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CommandError", 4)?;
+        state.serialize_field("code", &self.code)?;
+        state.serialize_field("message", &self.message)?;
+        state.serialize_field("path", &self.path)?;
+        // io::ErrorKind has no stable serialized form, so use its Debug name
+        state.serialize_field("ioKind", &self.io_kind.map(|kind| format!("{:?}", kind)))?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serializes_for_the_frontend() {
+        let err = CommandError::not_found("/vault/Note.md", "File");
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            json!({
+                "code": "not_found",
+                "message": "File does not exist: /vault/Note.md",
+                "path": "/vault/Note.md",
+                "ioKind": null,
+            })
+        );
+
+        let err = CommandError::invalid_input("Bad tag");
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            json!({ "code": "invalid_input", "message": "Bad tag", "path": null, "ioKind": null })
+        );
+    }
+
+    #[test]
+    fn io_errors_keep_their_kind() {
+        let err = CommandError::io(
+            "Failed to read",
+            "/vault/Note.md",
+            io::Error::from(io::ErrorKind::PermissionDenied),
+        );
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["code"], "permission_denied");
+        assert_eq!(value["ioKind"], "PermissionDenied");
+        assert_eq!(value["path"], "/vault/Note.md");
+        assert!(err
+            .to_string()
+            .starts_with("Failed to read '/vault/Note.md': "));
+
+        let code = |kind| CommandError::io("", "", io::Error::from(kind)).code;
+        assert_eq!(code(io::ErrorKind::NotFound), ErrorCode::NotFound);
+        assert_eq!(code(io::ErrorKind::AlreadyExists), ErrorCode::AlreadyExists);
+        assert_eq!(code(io::ErrorKind::InvalidInput), ErrorCode::InvalidInput);
+        assert_eq!(code(io::ErrorKind::TimedOut), ErrorCode::Io);
+    }
+
+    #[test]
+    fn every_code_has_a_stable_name() {
+        let codes = [
+            (ErrorCode::NotFound, "not_found"),
+            (ErrorCode::PermissionDenied, "permission_denied"),
+            (ErrorCode::AlreadyExists, "already_exists"),
+            (ErrorCode::Conflict, "conflict"),
+            (ErrorCode::OutsideVault, "outside_vault"),
+            (ErrorCode::InvalidPath, "invalid_path"),
+            (ErrorCode::NotADirectory, "not_a_directory"),
+            (ErrorCode::NotAFile, "not_a_file"),
+            (ErrorCode::InvalidInput, "invalid_input"),
+            (ErrorCode::Io, "io"),
+            (ErrorCode::Internal, "internal"),
+        ];
+        for (code, name) in codes {
+            assert_eq!(serde_json::to_value(code).unwrap(), name);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::links::LinkKind;
+use super::VaultIndex;
+
+/// Folder name save_image_to_attachments stores pasted images in
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+impl VaultIndex {
+    /// Files inside attachment folders that no note references
+    pub fn orphaned_attachments(&self) -> Vec<PathBuf> {
+        let referenced = self.referenced_files();
+        self.files
+            .iter()
+            .filter(|file| self.is_attachment(file) && !referenced.contains(file.as_path()))
+            .cloned()
+            .collect()
+    }
+
+    /// Whether a file lives in an attachment folder anywhere in the vault
+    fn is_attachment(&self, path: &Path) -> bool {
+        let Some(relative) = self.root().and_then(|root| path.strip_prefix(root).ok()) else {
+            return false;
+        };
+        relative.parent().is_some_and(|folders| {
+            folders
+                .components()
+                .any(|c| c.as_os_str().eq_ignore_ascii_case(ATTACHMENTS_DIR))
+        })
+    }
+
+    /// Every file a note references: through a link, an HTML tag, or a
+    /// frontmatter value naming the file
+    fn referenced_files(&self) -> HashSet<PathBuf> {
+        let mut referenced = HashSet::new();
+        for (source, entry) in &self.notes {
+            referenced.extend(
+                entry
+                    .links
+                    .iter()
+                    .filter_map(|link| self.resolve(source, link)),
+            );
+            referenced.extend(
+                entry
+                    .html_refs
+                    .iter()
+                    .filter_map(|target| self.resolve_target(source, LinkKind::Markdown, target)),
+            );
+            for (_, value) in &entry.properties {
+                collect_strings(value, &mut |text| {
+                    referenced.extend(self.resolve_property(source, text));
+                });
+            }
+        }
+        referenced
+    }
+
+    /// File a frontmatter string refers to, written as `[[name]]`, as a path
+    /// relative to the note or as a bare file name
+    fn resolve_property(&self, source: &Path, text: &str) -> Option<PathBuf> {
+        let text = text.trim();
+        if let Some(inner) = text.strip_prefix("[[").and_then(|t| t.strip_suffix("]]")) {
+            let target = inner.split(['|', '#']).next().unwrap_or_default();
+            return self.resolve_target(source, LinkKind::Wiki, target);
+        }
+        // An empty target is the note itself
+        if text.is_empty() {
+            return None;
+        }
+        self.resolve_target(source, LinkKind::Markdown, text)
+            .or_else(|| self.resolve_target(source, LinkKind::Wiki, text))
+    }
+}
+
+/// Call `found` with every string in a frontmatter value, including nested ones
+fn collect_strings(value: &Value, found: &mut impl FnMut(&str)) {
+    match value {
+        Value::String(text) => found(text),
+        Value::Array(items) => items.iter().for_each(|item| collect_strings(item, found)),
+        Value::Object(map) => map.values().for_each(|item| collect_strings(item, found)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn vault(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yana-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (file, content) in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        fs::canonicalize(&dir).unwrap()
+    }
+
+    #[test]
+    fn spaces_in_attachment_names() {
+        let dir = std::env::temp_dir().join(format!("yana-attachments-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(ATTACHMENTS_DIR)).unwrap();
+        fs::write(dir.join("attachments/Screen Shot.png"), b"").unwrap();
+        fs::write(dir.join("attachments/Unused.png"), b"").unwrap();
+        fs::write(dir.join("Note.md"), "![](attachments/Screen Shot.png)\n").unwrap();
+        let root = fs::canonicalize(&dir).unwrap();
+
+        let mut index = VaultIndex::default();
+        index.rebuild(&root);
+        assert_eq!(
+            index.orphaned_attachments(),
+            [root.join("attachments/Unused.png")]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn html_tags_reference_attachments() {
+        let root = vault(
+            "attachments-html",
+            &[
+                ("attachments/Chart.png", ""),
+                ("attachments/Q3 Report.pdf", ""),
+                ("attachments/Code.png", ""),
+                ("attachments/Unused.png", ""),
+                (
+                    "notes/Note.md",
+                    "<img width=200 src=\"../attachments/Chart.png\">\n\
+                     <A HREF=\"/attachments/Q3%20Report.pdf#page=2\">\n\
+                     `<img src=\"../attachments/Code.png\">`\n",
+                ),
+            ],
+        );
+
+        let mut index = VaultIndex::default();
+        index.rebuild(&root);
+        let mut orphans = index.orphaned_attachments();
+        orphans.sort();
+        assert_eq!(
+            orphans,
+            [
+                root.join("attachments/Code.png"),
+                root.join("attachments/Unused.png")
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn frontmatter_values_reference_attachments() {
+        let root = vault(
+            "attachments-frontmatter",
+            &[
+                ("attachments/Cover.png", ""),
+                ("attachments/Banner.jpg", ""),
+                ("attachments/Scan.pdf", ""),
+                ("attachments/Unused.png", ""),
+                (
+                    "Note.md",
+                    "---\ncover: attachments/Cover.png\nbanner: \"[[Banner.jpg|top]]\"\n\
+                     sources:\n  - Scan.pdf\ntitle: Unused\n---\n",
+                ),
+            ],
+        );
+
+        let mut index = VaultIndex::default();
+        index.rebuild(&root);
+        assert_eq!(
+            index.orphaned_attachments(),
+            [root.join("attachments/Unused.png")]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
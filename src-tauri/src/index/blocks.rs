@@ -0,0 +1,405 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::Range;
+
+use super::markdown::{prose_lines, snippet, Line};
+use super::sections::heading_of;
+
+/// Longest block text stored per block id, in characters
+const SNIPPET_CHARS: usize = 160;
+
+/// Length of generated block ids
+const GENERATED_ID_LEN: usize = 6;
+
+/// A `^block-id` marker in a note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockId {
+    /// Id without the `^`
+    pub id: String,
+    /// 1-based line number of the marker
+    pub line: usize,
+    /// Text of the marked block without the marker, clipped
+    pub text: String,
+}
+
+/// Result of adding a block id to a line
+#[derive(Debug, Clone)]
+pub struct BlockInsert {
+    pub id: String,
+    /// 1-based line number of the marker
+    pub line: usize,
+    /// New note content, `None` if the block already had an id
+    pub content: Option<String>,
+}
+
+/// Why a block id could not be added
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockInsertError {
+    /// The line does not exist, or is blank, frontmatter or code
+    NotABlock,
+}
+
+/// Every block id in a note, in document order
+pub fn parse_block_ids(content: &str) -> Vec<BlockId> {
+    let lines = prose_lines(content);
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let id = block_id_of(line.text)?;
+            let text = block_lines(&lines, index)
+                .map(|range| block_text(&lines[range]))
+                .unwrap_or_default();
+            Some(BlockId {
+                id: id.to_string(),
+                line: line.number,
+                text: snippet(&text, 0..0, SNIPPET_CHARS),
+            })
+        })
+        .collect()
+}
+
+/// Byte range of the block carrying `^id`, marker line included unless the
+/// marker stands on a line of its own
+///
+/// A marker at the end of a line belongs to that list item or heading, or to
+/// the paragraph the line ends. A lone marker belongs to the block above it,
+/// for tables and quotes.
+pub fn block_range(content: &str, id: &str) -> Option<Range<usize>> {
+    let lines = prose_lines(content);
+    let marked = lines.iter().position(|line| {
+        block_id_of(line.text).is_some_and(|found| found.eq_ignore_ascii_case(id))
+    })?;
+
+    let range = block_lines(&lines, marked)?;
+    let first = &lines[range.start];
+    let last = &lines[range.end - 1];
+    Some(first.start..last.start + last.text.len())
+}
+
+/// Give the block containing a line an id, reusing the one it already has
+///
+/// The id goes at the end of the block's last line, or on a line of its own
+/// after tables and quotes, where a trailing marker would become part of the
+/// last row or quote line.
+pub fn insert_block_id(content: &str, line_number: usize) -> Result<BlockInsert, BlockInsertError> {
+    let lines = prose_lines(content);
+    let index = lines
+        .iter()
+        .position(|line| line.number == line_number)
+        .filter(|index| !lines[*index].text.trim().is_empty())
+        .ok_or(BlockInsertError::NotABlock)?;
+
+    let range = surrounding_block(&lines, index);
+    let last = &lines[range.end - 1];
+    let standalone = is_standalone(lines[range.start].text);
+
+    // Reuse an id the block already carries
+    let existing = if standalone {
+        lines[range.end..]
+            .iter()
+            .find(|line| !line.text.trim().is_empty())
+            .filter(|line| strip_block_id(line.text).trim().is_empty())
+    } else {
+        Some(last)
+    };
+    if let Some(marker) = existing {
+        if let Some(id) = block_id_of(marker.text) {
+            return Ok(BlockInsert {
+                id: id.to_string(),
+                line: marker.number,
+                content: None,
+            });
+        }
+    }
+
+    let taken: Vec<String> = parse_block_ids(content)
+        .into_iter()
+        .map(|block| block.id.to_lowercase())
+        .collect();
+    let id = generate_id(|candidate| taken.iter().any(|t| t == candidate));
+
+    let at = last.start + last.text.len();
+    let (insert, line) = if standalone {
+        let followed = content[at..]
+            .split_inclusive('\n')
+            .nth(1)
+            .is_some_and(|next| !next.trim().is_empty());
+        let newline = line_ending(&content[at..]);
+        let mut text = format!("{nl}{nl}^{id}", nl = newline, id = id);
+        if followed {
+            text.push_str(newline);
+        }
+        (text, last.number + 2)
+    } else {
+        let space = if last.text.ends_with(char::is_whitespace) {
+            ""
+        } else {
+            " "
+        };
+        (format!("{}^{}", space, id), last.number)
+    };
+
+    let mut updated = String::with_capacity(content.len() + insert.len());
+    updated.push_str(&content[..at]);
+    updated.push_str(&insert);
+    updated.push_str(&content[at..]);
+    Ok(BlockInsert {
+        id,
+        line,
+        content: Some(updated),
+    })
+}
+
+/// Block id at the end of a line (`text ^id` or a lone `^id`)
+pub fn block_id_of(text: &str) -> Option<&str> {
+    let trimmed = text.trim_end();
+    let caret = trimmed.rfind('^')?;
+    let id = &trimmed[caret + 1..];
+    let before = &trimmed[..caret];
+    let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    (valid && (before.is_empty() || before.ends_with(char::is_whitespace))).then_some(id)
+}
+
+/// Line text with a trailing block id removed
+pub fn strip_block_id(text: &str) -> &str {
+    match block_id_of(text) {
+        Some(id) => text.trim_end()[..text.trim_end().len() - id.len() - 1].trim_end(),
+        None => text,
+    }
+}
+
+/// Indices into `lines` of the block marked on line `marked`
+fn block_lines(lines: &[Line<'_>], marked: usize) -> Option<Range<usize>> {
+    let line = &lines[marked];
+    if strip_block_id(line.text).trim().is_empty() {
+        // Lone marker: the block is the run of lines above, past blank lines
+        let mut end = marked;
+        while end > 0 && lines[end - 1].text.trim().is_empty() {
+            end -= 1;
+        }
+        if end == 0 {
+            return None;
+        }
+        return Some(block_start(lines, end - 1)..end);
+    }
+
+    if heading_of(line.text).is_some() || is_list_item(line.text) {
+        return Some(marked..marked + 1);
+    }
+    Some(block_start(lines, marked)..marked + 1)
+}
+
+/// Indices into `lines` of the whole block a line belongs to
+fn surrounding_block(lines: &[Line<'_>], index: usize) -> Range<usize> {
+    let text = lines[index].text;
+    if heading_of(text).is_some() || is_list_item(text) {
+        return index..index + 1;
+    }
+
+    let mut end = index + 1;
+    while end < lines.len() {
+        let below = &lines[end];
+        if below.number != lines[end - 1].number + 1
+            || below.text.trim().is_empty()
+            || heading_of(below.text).is_some()
+            || is_list_item(below.text)
+        {
+            break;
+        }
+        end += 1;
+    }
+    block_start(lines, index)..end
+}
+
+/// First line of the block ending on `last`: up to a blank line, a heading or a gap
+fn block_start(lines: &[Line<'_>], last: usize) -> usize {
+    let mut start = last;
+    while start > 0 {
+        let above = &lines[start - 1];
+        if above.number + 1 != lines[start].number
+            || above.text.trim().is_empty()
+            || heading_of(above.text).is_some()
+        {
+            break;
+        }
+        start -= 1;
+    }
+    start
+}
+
+fn block_text(lines: &[Line<'_>]) -> String {
+    lines
+        .iter()
+        .map(|line| strip_block_id(line.text).trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_list_item(text: &str) -> bool {
+    let trimmed = text.trim_start();
+    if trimmed.starts_with("- ") || trimmed.starts_with("* ") || trimmed.starts_with("+ ") {
+        return true;
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && matches!(&trimmed[digits..].get(..2), Some(". ") | Some(") "))
+}
+
+/// Tables and quotes take their id on a separate line
+fn is_standalone(text: &str) -> bool {
+    let trimmed = text.trim_start();
+    trimmed.starts_with('|') || trimmed.starts_with('>')
+}
+
+/// Line terminator of the line `rest` continues, `\n` if it has none
+fn line_ending(rest: &str) -> &'static str {
+    if rest.starts_with("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+/// Random lowercase alphanumeric id that `taken` does not reject
+fn generate_id(taken: impl Fn(&str) -> bool) -> String {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    loop {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default(),
+        );
+        let mut value = hasher.finish();
+        let id: String = (0..GENERATED_ID_LEN)
+            .map(|_| {
+                let c = ALPHABET[(value % ALPHABET.len() as u64) as usize] as char;
+                value /= ALPHABET.len() as u64;
+                c
+            })
+            .collect();
+        if !taken(&id) {
+            return id;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Insert into `content` and return the new id, marker line and content
+    fn insert(content: &str, line: usize) -> (String, usize, Option<String>) {
+        let insert = insert_block_id(content, line).unwrap();
+        (insert.id, insert.line, insert.content)
+    }
+
+    #[test]
+    fn block_ids_at_line_ends() {
+        assert_eq!(block_id_of("text ^abc-1"), Some("abc-1"));
+        assert_eq!(block_id_of("^abc  "), Some("abc"));
+        assert_eq!(block_id_of("x^abc"), None);
+        assert_eq!(block_id_of("text ^ab_c"), None);
+        assert_eq!(block_id_of("text ^"), None);
+
+        assert_eq!(strip_block_id("text ^abc "), "text");
+        assert_eq!(strip_block_id("^abc"), "");
+        assert_eq!(strip_block_id("2^10 "), "2^10 ");
+    }
+
+    #[test]
+    fn parses_ids_outside_frontmatter_and_code() {
+        let content = "---\nid: ^meta\n---\nFirst line\nsecond line ^para\n\n- item ^item\n\n```\ncode ^code\n```\n| a | b |\n| - | - |\n\n^table\n";
+        let ids: Vec<_> = parse_block_ids(content)
+            .into_iter()
+            .map(|block| (block.id, block.line, block.text))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                ("para".into(), 5, "First line second line".into()),
+                ("item".into(), 7, "- item".into()),
+                ("table".into(), 15, "| a | b | | - | - |".into()),
+            ]
+        );
+
+        let range = |id| block_range(content, id).map(|range| &content[range]);
+        assert_eq!(range("PARA"), Some("First line\nsecond line ^para"));
+        assert_eq!(range("item"), Some("- item ^item"));
+        assert_eq!(range("table"), Some("| a | b |\n| - | - |"));
+        assert_eq!(range("code"), None);
+        assert_eq!(range("meta"), None);
+    }
+
+    #[test]
+    fn inserts_at_the_end_of_the_block() {
+        let (id, line, content) = insert("One\ntwo\n\nThree\n", 1);
+        assert_eq!(line, 2);
+        assert_eq!(content, Some(format!("One\ntwo ^{}\n\nThree\n", id)));
+        assert_eq!(id.len(), GENERATED_ID_LEN);
+
+        // A second request for the same block reuses the id
+        let again = insert(content.as_deref().unwrap(), 2);
+        assert_eq!(again, (id, 2, None));
+
+        let (id, line, content) = insert("# Title\nbody\n", 1);
+        assert_eq!(line, 1);
+        assert_eq!(content, Some(format!("# Title ^{}\nbody\n", id)));
+
+        let (id, _, content) = insert("- one\n- two \n", 2);
+        assert_eq!(content, Some(format!("- one\n- two ^{}\n", id)));
+
+        let (id, _, content) = insert("Text\r\nmore\r\n", 1);
+        assert_eq!(content, Some(format!("Text\r\nmore ^{}\r\n", id)));
+    }
+
+    #[test]
+    fn tables_and_quotes_get_a_marker_line() {
+        let (id, line, content) = insert("> quote\n", 1);
+        assert_eq!(line, 3);
+        assert_eq!(content, Some(format!("> quote\n\n^{}\n", id)));
+
+        let table = "| a |\n| - |\n# Next\n";
+        let (id, line, content) = insert(table, 2);
+        assert_eq!(line, 4);
+        let content = content.unwrap();
+        assert_eq!(content, format!("| a |\n| - |\n\n^{}\n\n# Next\n", id));
+        assert_eq!(
+            block_range(&content, &id).map(|range| &content[range]),
+            Some("| a |\n| - |")
+        );
+
+        // The marker line is found again from any row
+        assert_eq!(insert(&content, 1), (id, 4, None));
+    }
+
+    #[test]
+    fn blank_code_and_missing_lines_are_not_blocks() {
+        let content = "---\na: 1\n---\ntext\n\n```\ncode\n```\n";
+        for line in [2, 5, 7, 42] {
+            assert_eq!(
+                insert_block_id(content, line).unwrap_err(),
+                BlockInsertError::NotABlock
+            );
+        }
+    }
+
+    #[test]
+    fn generated_ids_avoid_taken_ones() {
+        let first = generate_id(|_| false);
+        let second = generate_id(|candidate| candidate == first);
+        assert_ne!(first, second);
+        assert!(second
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+
+        // Ids differing only in case count as taken
+        let content = "a ^ABC\n\nb\n";
+        let (id, _, _) = insert(content, 3);
+        assert_ne!(id, "abc");
+    }
+}
@@ -0,0 +1,352 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::links::{parse_links, Link, LinkKind};
+use super::markdown::percent_encode_path;
+use super::rewrite::{apply_edits, RewrittenNote, TextEdit};
+use super::VaultIndex;
+
+/// Why a link was left in its original syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The link points at nothing, so there is no path to write
+    Unresolved,
+    /// The target, heading or label can't be written in the other syntax
+    Unrepresentable,
+}
+
+/// A link converted to the other syntax, or that would be in a dry run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertedLink {
+    /// Note containing the link
+    pub source: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based character column of the link start
+    pub column: usize,
+    /// Link as written before the conversion
+    pub before: String,
+    /// Link as written after the conversion
+    pub after: String,
+}
+
+/// A link left as written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedLink {
+    /// Note containing the link
+    pub source: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based character column of the link start
+    pub column: usize,
+    /// Link as written
+    pub text: String,
+    pub reason: SkipReason,
+}
+
+/// Planned conversion of the links under a folder
+#[derive(Debug, Clone, Default)]
+pub struct LinkConversion {
+    /// Notes with converted links, ready to be written
+    pub notes: Vec<RewrittenNote>,
+    pub converted: Vec<ConvertedLink>,
+    pub skipped: Vec<SkippedLink>,
+}
+
+impl VaultIndex {
+    /// Convert every link under `scope` (a folder or a single note) to `style`
+    ///
+    /// Targets are resolved the way the index resolves them, so a converted link
+    /// points at the same file as before: wiki links become relative markdown links
+    /// with the `.md` extension spelled out, markdown links become wiki links by
+    /// file name, or by path where the name alone is ambiguous. Links that resolve
+    /// to nothing are left alone. Nothing is written; notes are re-read so the
+    /// spans match the files on disk.
+    pub fn convert_links(&self, scope: &Path, style: LinkKind) -> LinkConversion {
+        let from = match style {
+            LinkKind::Wiki => LinkKind::Markdown,
+            LinkKind::Markdown => LinkKind::Wiki,
+        };
+
+        let mut sources: Vec<&PathBuf> = self
+            .notes
+            .iter()
+            .filter(|(path, entry)| {
+                path.starts_with(scope) && entry.links.iter().any(|link| link.kind == from)
+            })
+            .map(|(path, _)| path)
+            .collect();
+        sources.sort();
+
+        let mut conversion = LinkConversion::default();
+        for source in sources {
+            let content = match fs::read_to_string(source) {
+                Ok(content) => content,
+                Err(e) => {
+                    log::warn!("Skipping link conversion in '{}': {}", source.display(), e);
+                    continue;
+                }
+            };
+
+            let mut edits = Vec::new();
+            for link in parse_links(&content).into_iter().filter(|l| l.kind == from) {
+                let before = content[link.span.clone()].to_string();
+                let converted = match self.resolve(source, &link) {
+                    None => Err(SkipReason::Unresolved),
+                    Some(target) => match style {
+                        LinkKind::Markdown => self.to_markdown(source, &link, &content, &target),
+                        LinkKind::Wiki => self.to_wiki(source, &link, &content, &target),
+                    }
+                    .ok_or(SkipReason::Unrepresentable),
+                };
+
+                match converted {
+                    Ok(after) => {
+                        conversion.converted.push(ConvertedLink {
+                            source: self.shown(source),
+                            line: link.line,
+                            column: link.column,
+                            before,
+                            after: after.clone(),
+                        });
+                        edits.push(TextEdit {
+                            range: link.span.clone(),
+                            text: after,
+                        });
+                    }
+                    Err(reason) => conversion.skipped.push(SkippedLink {
+                        source: self.shown(source),
+                        line: link.line,
+                        column: link.column,
+                        text: before,
+                        reason,
+                    }),
+                }
+            }
+
+            if !edits.is_empty() {
+                conversion.notes.push(RewrittenNote {
+                    path: source.clone(),
+                    links: edits.len(),
+                    content: apply_edits(&content, edits),
+                    original: content,
+                });
+            }
+        }
+        conversion
+    }
+
+    /// `[[target#fragment|alias]]` written as `[label](relative/path.md#fragment)`
+    fn to_markdown(
+        &self,
+        source: &Path,
+        link: &Link,
+        content: &str,
+        target: &Path,
+    ) -> Option<String> {
+        let mut destination = if link.target.is_empty() {
+            String::new()
+        } else {
+            self.markdown_target(source, target, false, true, true)?
+        };
+        if let Some(fragment) = &link.fragment {
+            // Nested heading paths keep their `#` separators
+            destination.push('#');
+            destination.push_str(&percent_encode_path(fragment).replace("%23", "#"));
+        }
+
+        // Without an alias, show the link text the wiki link displayed
+        let label = match &link.label {
+            Some(label) => label.clone(),
+            None if link.embed => String::new(),
+            None => {
+                let end = if link.fragment.is_some() {
+                    link.fragment_span.end
+                } else {
+                    link.target_span.end
+                };
+                content.get(link.target_span.start..end)?.to_string()
+            }
+        };
+        let label = label.replace('[', "\\[").replace(']', "\\]");
+
+        Some(format!(
+            "{}[{}]({})",
+            if link.embed { "!" } else { "" },
+            label,
+            destination
+        ))
+    }
+
+    /// `[label](path.md#fragment)` written as `[[target#fragment|label]]`
+    fn to_wiki(&self, source: &Path, link: &Link, content: &str, target: &Path) -> Option<String> {
+        let name = if link.target.is_empty() {
+            String::new()
+        } else {
+            self.wiki_target(source, target, false, false)?
+        };
+        if name.contains(['#', '|', '[', ']', '^']) {
+            return None;
+        }
+
+        let mut reference = name.clone();
+        if let Some(fragment) = &link.fragment {
+            if fragment.contains(['|', '[', ']']) {
+                return None;
+            }
+            reference.push('#');
+            reference.push_str(fragment);
+        }
+        if reference.is_empty() {
+            return None;
+        }
+
+        // A label that only repeats the target adds nothing
+        let alias = link
+            .label
+            .as_deref()
+            .map(|label| label.replace("\\[", "[").replace("\\]", "]"))
+            .filter(|label| {
+                !label.eq_ignore_ascii_case(&reference) && !label.eq_ignore_ascii_case(&name)
+            });
+        if alias
+            .as_deref()
+            .is_some_and(|alias| alias.contains(['[', ']']))
+        {
+            return None;
+        }
+
+        let mut text = format!("{}[[{}", if link.embed { "!" } else { "" }, reference);
+        if let Some(alias) = alias {
+            // A bare `|` would end the cell inside a table row
+            let line_start = content[..link.span.start].rfind('\n').map_or(0, |i| i + 1);
+            if content[line_start..].trim_start().starts_with('|') {
+                text.push('\\');
+            }
+            text.push('|');
+            text.push_str(&alias);
+        }
+        text.push_str("]]");
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault(name: &str, files: &[(&str, &str)]) -> (PathBuf, VaultIndex) {
+        let dir =
+            std::env::temp_dir().join(format!("yana-convert-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let dir = fs::canonicalize(dir).unwrap();
+        let mut index = VaultIndex::default();
+        index.rebuild(&dir);
+        (dir, index)
+    }
+
+    fn skipped(conversion: &LinkConversion) -> Vec<(&str, SkipReason)> {
+        conversion
+            .skipped
+            .iter()
+            .map(|link| (link.text.as_str(), link.reason))
+            .collect()
+    }
+
+    #[test]
+    fn wiki_links_become_relative_markdown_links() {
+        let (root, index) = vault(
+            "to-markdown",
+            &[
+                (
+                    "Notes/Source.md",
+                    "See [[Target]], [[Target#Some Heading|the part]] and ![[Target]].\n\
+                     [[Missing]] [[Deep Note#A#B]] [[#Local]] [md](Target.md)\n",
+                ),
+                ("Notes/Target.md", "# Some Heading\n"),
+                ("Notes/Sub/Deep Note.md", ""),
+                ("Elsewhere.md", "[[Target]]\n"),
+            ],
+        );
+
+        let conversion = index.convert_links(&root.join("Notes"), LinkKind::Markdown);
+        assert_eq!(conversion.notes.len(), 1);
+        let note = &conversion.notes[0];
+        assert_eq!(note.path, root.join("Notes/Source.md"));
+        assert_eq!(note.links, 5);
+        assert_eq!(
+            note.content,
+            "See [Target](Target.md), [the part](Target.md#Some%20Heading) and ![](Target.md).\n\
+             [[Missing]] [Deep Note#A#B](Sub/Deep%20Note.md#A#B) [#Local](#Local) [md](Target.md)\n"
+        );
+        assert_eq!(
+            skipped(&conversion),
+            [("[[Missing]]", SkipReason::Unresolved)]
+        );
+
+        let first = &conversion.converted[0];
+        assert_eq!(
+            (
+                first.line,
+                first.column,
+                first.before.as_str(),
+                first.after.as_str()
+            ),
+            (1, 5, "[[Target]]", "[Target](Target.md)")
+        );
+
+        // Nothing is written by the plan
+        assert!(fs::read_to_string(root.join("Notes/Source.md"))
+            .unwrap()
+            .starts_with("See [[Target]]"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn markdown_links_become_wiki_links() {
+        let (root, index) = vault(
+            "to-wiki",
+            &[
+                (
+                    "Source.md",
+                    "[Target](Target.md) [see](Target.md#Part) ![](Sub/Deep%20Note.md)\n\
+                     [x](Gone.md) [d](B/Dup.md) [s](C%23.md) [t](Target.md#a|b)\n\
+                     | [cell](Target.md) |\n",
+                ),
+                ("Target.md", ""),
+                ("Sub/Deep Note.md", ""),
+                ("A/Dup.md", ""),
+                ("B/Dup.md", ""),
+                ("C#.md", ""),
+            ],
+        );
+
+        let conversion = index.convert_links(&root.join("Source.md"), LinkKind::Wiki);
+        assert_eq!(
+            conversion.notes[0].content,
+            "[[Target]] [[Target#Part|see]] ![[Deep Note]]\n\
+             [x](Gone.md) [[B/Dup|d]] [s](C%23.md) [t](Target.md#a|b)\n\
+             | [[Target\\|cell]] |\n"
+        );
+        assert_eq!(
+            skipped(&conversion),
+            [
+                ("[x](Gone.md)", SkipReason::Unresolved),
+                ("[s](C%23.md)", SkipReason::Unrepresentable),
+                ("[t](Target.md#a|b)", SkipReason::Unrepresentable),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
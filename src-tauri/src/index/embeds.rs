@@ -0,0 +1,397 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::blocks::{block_id_of, block_range, strip_block_id};
+use super::links::{parse_links, Link, LinkKind};
+use super::markdown::frontmatter_range;
+use super::rewrite::{apply_edits, TextEdit};
+use super::sections::section_range;
+use super::{is_note, VaultIndex};
+
+/// Nesting levels expanded when the caller does not ask for a limit
+pub const DEFAULT_EMBED_DEPTH: usize = 4;
+
+/// Upper bound on the requested nesting limit
+pub const MAX_EMBED_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbedProblemKind {
+    /// The embedded note does not exist
+    MissingNote,
+    /// The note exists but has no such heading or block
+    MissingSection,
+    /// The embed would include itself
+    Cycle,
+    /// The embed is nested deeper than the limit
+    TooDeep,
+}
+
+/// An embed that could not be expanded and was left as written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedProblem {
+    pub kind: EmbedProblemKind,
+    /// Note containing the embed
+    pub source: String,
+    /// Embed target as written, with its fragment
+    pub target: String,
+    /// 1-based line number in the source note, absent for the requested embed itself
+    pub line: Option<usize>,
+}
+
+/// Markdown to show in place of an embed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedEmbed {
+    /// Note the embed points at, absent if it does not exist
+    pub path: Option<String>,
+    /// Heading path or `^block` that was addressed
+    pub fragment: Option<String>,
+    /// Content with nested note embeds expanded, absent if the target is missing
+    pub markdown: Option<String>,
+    /// Embeds that could not be expanded, the requested one first
+    pub problems: Vec<EmbedProblem>,
+}
+
+impl VaultIndex {
+    /// Resolve `![[target#fragment]]` written in `source` to markdown
+    ///
+    /// Without a fragment the whole note is returned, minus its frontmatter. A
+    /// fragment addresses a heading section (`Heading` or `Parent#Child`) or a
+    /// block (`^id`). Note embeds inside the result are expanded in place up to
+    /// `max_depth` levels; embeds that would reach themselves again or go deeper
+    /// are left as written and reported. Embedded images and other files are left for the editor.
+    pub fn resolve_embed(
+        &self,
+        source: &Path,
+        target: &str,
+        fragment: Option<&str>,
+        max_depth: usize,
+    ) -> ResolvedEmbed {
+        let mut resolver = EmbedResolver {
+            index: self,
+            contents: HashMap::new(),
+            stack: Vec::new(),
+            problems: Vec::new(),
+            max_depth,
+        };
+        let fragment = fragment.map(str::trim).filter(|f| !f.is_empty());
+        let written = written_target(target, fragment);
+
+        let Some(path) = self
+            .resolve_target(source, LinkKind::Wiki, target)
+            .filter(|path| is_note(path))
+        else {
+            return ResolvedEmbed {
+                fragment: fragment.map(str::to_string),
+                problems: vec![EmbedProblem {
+                    kind: EmbedProblemKind::MissingNote,
+                    source: self.shown(source),
+                    target: written,
+                    line: None,
+                }],
+                ..Default::default()
+            };
+        };
+
+        let markdown = match resolver.expand(&path, fragment, 0) {
+            Ok(markdown) => Some(markdown),
+            Err(kind) => {
+                resolver.problems.insert(
+                    0,
+                    EmbedProblem {
+                        kind,
+                        source: self.shown(source),
+                        target: written,
+                        line: None,
+                    },
+                );
+                None
+            }
+        };
+
+        ResolvedEmbed {
+            path: Some(self.shown(&path)),
+            fragment: fragment.map(str::to_string),
+            markdown,
+            problems: resolver.problems,
+        }
+    }
+}
+
+/// State of one embed resolution
+struct EmbedResolver<'a> {
+    index: &'a VaultIndex,
+    /// Notes read so far; `None` if a note could not be read
+    contents: HashMap<PathBuf, Option<String>>,
+    /// Embeds currently being expanded, by note and byte offset, outermost first
+    stack: Vec<(PathBuf, usize)>,
+    problems: Vec<EmbedProblem>,
+    max_depth: usize,
+}
+
+impl EmbedResolver<'_> {
+    /// Markdown of a note or part of it, with nested note embeds expanded
+    fn expand(
+        &mut self,
+        path: &Path,
+        fragment: Option<&str>,
+        depth: usize,
+    ) -> Result<String, EmbedProblemKind> {
+        let content = self.content(path).ok_or(EmbedProblemKind::MissingNote)?;
+        let range = match fragment {
+            Some(fragment) => {
+                fragment_range(&content, fragment).ok_or(EmbedProblemKind::MissingSection)?
+            }
+            None => frontmatter_range(&content).map_or(0, |fm| fm.end)..content.len(),
+        };
+        // Expanding an embed that is already being expanded would never end
+        if self
+            .stack
+            .iter()
+            .any(|(open, offset)| open == path && range.contains(offset))
+        {
+            return Err(EmbedProblemKind::Cycle);
+        }
+
+        let nested: Vec<Link> = parse_links(&content)
+            .into_iter()
+            .filter(|link| {
+                link.embed && link.span.start >= range.start && link.span.end <= range.end
+            })
+            .collect();
+
+        let mut edits = Vec::new();
+        for link in nested {
+            let problem = match self.index.resolve(path, &link) {
+                Some(target) if !is_note(&target) => None,
+                None if !names_note(&link) => None,
+                None => Some(EmbedProblemKind::MissingNote),
+                Some(_) if depth + 1 > self.max_depth => Some(EmbedProblemKind::TooDeep),
+                Some(target) => {
+                    self.stack.push((path.to_path_buf(), link.span.start));
+                    let expanded = self.expand(&target, link.fragment.as_deref(), depth + 1);
+                    self.stack.pop();
+                    match expanded {
+                        Ok(markdown) => {
+                            edits.push(TextEdit {
+                                range: link.span.start - range.start..link.span.end - range.start,
+                                text: markdown,
+                            });
+                            None
+                        }
+                        Err(kind) => Some(kind),
+                    }
+                }
+            };
+
+            if let Some(kind) = problem {
+                self.problems.push(EmbedProblem {
+                    kind,
+                    source: self.index.shown(path),
+                    target: written_target(&link.target, link.fragment.as_deref()),
+                    line: Some(link.line),
+                });
+            }
+        }
+
+        let expanded = apply_edits(&content[range], edits);
+        Ok(strip_block_ids(expanded.trim_matches('\n')))
+    }
+
+    fn content(&mut self, path: &Path) -> Option<String> {
+        self.contents
+            .entry(path.to_path_buf())
+            .or_insert_with(|| match fs::read_to_string(path) {
+                Ok(content) => Some(content),
+                Err(e) => {
+                    log::warn!("Failed to read embedded note '{}': {}", path.display(), e);
+                    None
+                }
+            })
+            .clone()
+    }
+}
+
+/// Byte range a `#fragment` addresses: a `^block` or a heading path
+fn fragment_range(content: &str, fragment: &str) -> Option<Range<usize>> {
+    match fragment.strip_prefix('^') {
+        Some(id) => block_range(content, id),
+        None => {
+            let path: Vec<&str> = fragment
+                .split('#')
+                .filter(|part| !part.trim().is_empty())
+                .collect();
+            section_range(content, &path)
+        }
+    }
+}
+
+/// Whether a link that resolves to nothing was meant to point at a note
+fn names_note(link: &Link) -> bool {
+    let target = Path::new(&link.target);
+    target.extension().is_none() || is_note(target)
+}
+
+fn written_target(target: &str, fragment: Option<&str>) -> String {
+    match fragment {
+        Some(fragment) => format!("{}#{}", target, fragment),
+        None => target.to_string(),
+    }
+}
+
+/// Remove `^id` markers, which are not meant to be rendered
+fn strip_block_ids(text: &str) -> String {
+    text.split_inclusive('\n')
+        .filter_map(|line| {
+            let body = line.trim_end_matches(['\n', '\r']);
+            if block_id_of(body).is_none() {
+                return Some(line.to_string());
+            }
+            let stripped = strip_block_id(body);
+            // A lone marker line disappears entirely
+            (!stripped.trim().is_empty()).then(|| format!("{}{}", stripped, &line[body.len()..]))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault(name: &str, notes: &[(&str, &str)]) -> (PathBuf, VaultIndex) {
+        let dir = std::env::temp_dir().join(format!("yana-embeds-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (path, content) in notes {
+            fs::write(dir.join(path), content).unwrap();
+        }
+        let dir = fs::canonicalize(dir).unwrap();
+        let mut index = VaultIndex::default();
+        index.rebuild(&dir);
+        (dir, index)
+    }
+
+    fn kinds(embed: &ResolvedEmbed) -> Vec<(EmbedProblemKind, String, Option<usize>)> {
+        embed
+            .problems
+            .iter()
+            .map(|problem| (problem.kind, problem.target.clone(), problem.line))
+            .collect()
+    }
+
+    #[test]
+    fn whole_notes_sections_and_blocks() {
+        let (root, index) = vault(
+            "parts",
+            &[
+                (
+                    "Other.md",
+                    "---\ntags: [x]\n---\nIntro\n\n# Plans\nText\n## Next\nSoon\n# Later\nA quote ^cite\n",
+                ),
+                ("Home.md", ""),
+            ],
+        );
+        let source = root.join("Home.md");
+        let resolve = |fragment: Option<&str>| index.resolve_embed(&source, "Other", fragment, 4);
+
+        let whole = resolve(None);
+        assert_eq!(
+            whole.markdown.as_deref(),
+            Some("Intro\n\n# Plans\nText\n## Next\nSoon\n# Later\nA quote")
+        );
+        assert_eq!(
+            whole.path,
+            Some(root.join("Other.md").to_string_lossy().to_string())
+        );
+        assert!(whole.problems.is_empty());
+
+        assert_eq!(
+            resolve(Some("Plans")).markdown.as_deref(),
+            Some("# Plans\nText\n## Next\nSoon")
+        );
+        assert_eq!(
+            resolve(Some("Plans#Next")).markdown.as_deref(),
+            Some("## Next\nSoon")
+        );
+        assert_eq!(resolve(Some("^cite")).markdown.as_deref(), Some("A quote"));
+
+        let missing = resolve(Some("Nowhere"));
+        assert_eq!(missing.markdown, None);
+        assert_eq!(
+            kinds(&missing),
+            [(
+                EmbedProblemKind::MissingSection,
+                "Other#Nowhere".into(),
+                None
+            )]
+        );
+
+        let note = index.resolve_embed(&source, "Nothing", None, 4);
+        assert_eq!((&note.path, &note.markdown), (&None, &None));
+        assert_eq!(
+            kinds(&note),
+            [(EmbedProblemKind::MissingNote, "Nothing".into(), None)]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn nested_embeds_expand_with_guards() {
+        let (root, index) = vault(
+            "nested",
+            &[
+                ("A.md", "A starts\n![[B]]\n![[img.png]]\n![[Gone]]\n"),
+                ("B.md", "B here\n![[C#Part]]\n"),
+                ("C.md", "# Part\nC part\n![[A]]\n"),
+                ("img.png", ""),
+            ],
+        );
+        let source = root.join("Home.md");
+
+        // C embeds A again, which is already being expanded
+        let embed = index.resolve_embed(&source, "A", None, 4);
+        assert_eq!(
+            embed.markdown.as_deref(),
+            Some("A starts\nB here\n# Part\nC part\n![[A]]\n![[img.png]]\n![[Gone]]")
+        );
+        assert_eq!(
+            kinds(&embed),
+            [
+                (EmbedProblemKind::Cycle, "A".into(), Some(3)),
+                (EmbedProblemKind::MissingNote, "Gone".into(), Some(4)),
+            ]
+        );
+
+        // Nesting stops at the limit
+        let shallow = index.resolve_embed(&source, "A", None, 1);
+        assert_eq!(
+            shallow.markdown.as_deref(),
+            Some("A starts\nB here\n![[C#Part]]\n![[img.png]]\n![[Gone]]")
+        );
+        assert_eq!(shallow.problems[0].kind, EmbedProblemKind::TooDeep);
+
+        // A note embedding a section of itself is fine, embedding itself whole is not
+        let (root2, index2) = vault(
+            "self",
+            &[("S.md", "# One\nfirst\n# Two\n![[S#One]]\n![[S]]\n")],
+        );
+        let embed = index2.resolve_embed(&root2.join("S.md"), "S", Some("Two"), 4);
+        assert_eq!(
+            embed.markdown.as_deref(),
+            Some("# Two\n# One\nfirst\n![[S]]")
+        );
+        assert_eq!(
+            kinds(&embed),
+            [(EmbedProblemKind::Cycle, "S".into(), Some(5))]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&root2).unwrap();
+    }
+}
@@ -0,0 +1,700 @@
+use serde_json::{Map, Number, Value};
+use std::ops::Range;
+
+use super::markdown::{frontmatter_range, split_lines};
+
+/// Frontmatter properties of a note, in the order they are written
+pub type Properties = Vec<(String, Value)>;
+
+/// Parse the YAML (`---`) or TOML (`+++`) frontmatter of a note into properties
+///
+/// Notes without frontmatter, or whose frontmatter is not a mapping, yield `None`.
+/// Malformed frontmatter is logged and treated as absent. TOML dates become strings.
+pub fn parse_frontmatter(content: &str) -> Option<Properties> {
+    let block = frontmatter_block(content)?;
+    match parse_body(&content[block.body], block.toml) {
+        Ok(properties) => properties,
+        Err(e) => {
+            log::debug!("Ignoring malformed frontmatter: {}", e);
+            None
+        }
+    }
+}
+
+/// Value of a property, matching the key ignoring case
+pub fn property<'a>(properties: &'a [(String, Value)], key: &str) -> Option<&'a Value> {
+    properties
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        .map(|(_, value)| value)
+}
+
+/// Alternative names of a note from its `aliases` (or `alias`) property
+///
+/// Accepts a list or a single string; a string may hold several comma-separated aliases.
+pub fn aliases(properties: &[(String, Value)]) -> Vec<String> {
+    let value = property(properties, "aliases").or_else(|| property(properties, "alias"));
+    let items: Vec<&Value> = match value {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(value) => vec![value],
+        None => return Vec::new(),
+    };
+
+    items
+        .into_iter()
+        .flat_map(|item| match item {
+            Value::String(text) => text.split(',').map(|s| s.trim().to_string()).collect(),
+            Value::Number(n) => vec![n.to_string()],
+            _ => Vec::new(),
+        })
+        .filter(|alias| !alias.is_empty())
+        .collect()
+}
+
+/// Why a frontmatter property could not be changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyEditError {
+    /// The frontmatter does not parse, so editing it could lose data
+    Malformed,
+    /// The value can't be written in the note's frontmatter format
+    Unsupported(String),
+}
+
+/// Set a top-level frontmatter property, adding a YAML frontmatter block if there is none
+///
+/// Only the lines of that property change: other properties, their order, comments
+/// and the body stay byte for byte. An existing key is matched ignoring case and
+/// keeps its spelling, as do a flow-style list (`[a, b]`), the indentation of
+/// block list items and a trailing comment. New lines end like the first line
+/// of the note, which is the opening fence when there is frontmatter.
+pub fn set_property(content: &str, key: &str, value: &Value) -> Result<String, PropertyEditError> {
+    let newline = line_ending(content);
+
+    let Some(block) = frontmatter_block(content) else {
+        let entry = yaml_entry(key, value, None, "", newline)?;
+        return Ok(format!("---{nl}{}---{nl}{}", entry, content, nl = newline));
+    };
+    check_parses(&content[block.body.clone()], block.toml)?;
+    let body_indent = list_indent(&content[block.body.clone()]);
+
+    let entries = top_level_entries(content, &block);
+    let existing = entries
+        .iter()
+        .find(|entry| entry.key.eq_ignore_ascii_case(key));
+
+    let (range, text) = match existing {
+        Some(entry) => {
+            let old = &content[entry.range.clone()];
+            let text = if block.toml {
+                toml_entry(&entry.key, value, Some(old), newline)?
+            } else {
+                let indent = list_indent(old).or(body_indent).unwrap_or("");
+                yaml_entry(&entry.key, value, Some(old), indent, newline)?
+            };
+            (entry.range.clone(), text)
+        }
+        None => {
+            // New keys go last, but before the first table in TOML
+            let at = if block.toml {
+                split_lines(&content[block.body.clone()])
+                    .find(|(_, line)| line.trim_start().starts_with('['))
+                    .map_or(block.body.end, |(start, _)| block.body.start + start)
+            } else {
+                block.body.end
+            };
+            let text = if block.toml {
+                toml_entry(key, value, None, newline)?
+            } else {
+                yaml_entry(key, value, None, body_indent.unwrap_or(""), newline)?
+            };
+            (at..at, text)
+        }
+    };
+
+    let mut updated = String::with_capacity(content.len() + text.len());
+    updated.push_str(&content[..range.start]);
+    updated.push_str(&text);
+    updated.push_str(&content[range.end..]);
+
+    // Never write frontmatter the index could no longer read
+    let parsed = parse_frontmatter(&updated).unwrap_or_default();
+    if property(&parsed, key).is_none() {
+        return Err(PropertyEditError::Unsupported(format!(
+            "Setting '{}' would leave frontmatter that does not parse",
+            key
+        )));
+    }
+    Ok(updated)
+}
+
+/// Remove a top-level frontmatter property, leaving everything else as written
+///
+/// Returns `None` when the note does not have the property.
+pub fn remove_property(content: &str, key: &str) -> Result<Option<String>, PropertyEditError> {
+    let Some(block) = frontmatter_block(content) else {
+        return Ok(None);
+    };
+    check_parses(&content[block.body.clone()], block.toml)?;
+
+    let Some(entry) = top_level_entries(content, &block)
+        .into_iter()
+        .find(|entry| entry.key.eq_ignore_ascii_case(key))
+    else {
+        return Ok(None);
+    };
+
+    let mut updated = String::with_capacity(content.len());
+    updated.push_str(&content[..entry.range.start]);
+    updated.push_str(&content[entry.range.end..]);
+    Ok(Some(updated))
+}
+
+/// Byte range of the lines a top-level property is written on
+pub fn property_range(content: &str, key: &str) -> Option<Range<usize>> {
+    let block = frontmatter_block(content)?;
+    top_level_entries(content, &block)
+        .into_iter()
+        .find(|entry| entry.key.eq_ignore_ascii_case(key))
+        .map(|entry| entry.range)
+}
+
+/// Format and body of a frontmatter block
+struct Block {
+    toml: bool,
+    /// Byte range between the fence lines, ending with the last line terminator
+    body: Range<usize>,
+}
+
+fn frontmatter_block(content: &str) -> Option<Block> {
+    let range = frontmatter_range(content)?;
+    let block = &content[range];
+
+    // The body starts after the opening fence line and ends where the closing one begins
+    let body_start = block.find('\n')? + 1;
+    let closing = block.trim_end().rfind('\n').map_or(0, |i| i + 1);
+    Some(Block {
+        toml: block.starts_with("+++"),
+        body: body_start..closing.max(body_start),
+    })
+}
+
+fn parse_body(body: &str, toml: bool) -> Result<Option<Properties>, String> {
+    if toml {
+        toml::from_str::<toml::Table>(body)
+            .map(|table| {
+                Some(
+                    table
+                        .into_iter()
+                        .map(|(key, value)| (key, toml_value(value)))
+                        .collect(),
+                )
+            })
+            .map_err(|e| e.to_string())
+    } else {
+        serde_yaml::from_str::<serde_yaml::Value>(body)
+            .map(|value| match value {
+                serde_yaml::Value::Mapping(mapping) => Some(
+                    mapping
+                        .into_iter()
+                        .filter_map(|(key, value)| Some((yaml_key(key)?, yaml_value(value))))
+                        .collect(),
+                ),
+                _ => None,
+            })
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Frontmatter must be a mapping (or empty) before it is edited line by line
+fn check_parses(body: &str, toml: bool) -> Result<(), PropertyEditError> {
+    match parse_body(body, toml) {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) if body.trim().is_empty() => Ok(()),
+        _ => Err(PropertyEditError::Malformed),
+    }
+}
+
+/// A top-level property as written: its key and the lines it spans
+struct Entry {
+    key: String,
+    /// Byte range of the lines in the note, line terminators included
+    range: Range<usize>,
+}
+
+/// Top-level properties of a frontmatter block, in document order
+///
+/// A property spans its key line plus the indented (or, in YAML, `- ` list)
+/// lines below it. TOML properties inside `[tables]` are not top-level.
+fn top_level_entries(content: &str, block: &Block) -> Vec<Entry> {
+    let body = &content[block.body.clone()];
+    let lines: Vec<(usize, &str)> = split_lines(body).collect();
+    let line_end = |index: usize| lines.get(index + 1).map_or(body.len(), |(start, _)| *start);
+
+    let mut entries = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let (start, text) = lines[index];
+        if block.toml && text.trim_start().starts_with('[') {
+            break;
+        }
+        let key = if block.toml {
+            toml_line_key(text)
+        } else {
+            yaml_line_key(text).map(|(key, _)| key)
+        };
+        let Some(key) = key else {
+            index += 1;
+            continue;
+        };
+
+        // Continuation lines, including blank lines inside them
+        let mut last = index;
+        let mut next = index + 1;
+        while next < lines.len() {
+            let line = lines[next].1;
+            if line.trim().is_empty() {
+                next += 1;
+                continue;
+            }
+            let continues = line.starts_with([' ', '\t'])
+                || if block.toml {
+                    line.starts_with(']')
+                } else {
+                    line.starts_with('-')
+                };
+            if !continues {
+                break;
+            }
+            last = next;
+            next += 1;
+        }
+
+        entries.push(Entry {
+            key,
+            range: block.body.start + start..block.body.start + line_end(last),
+        });
+        index = last + 1;
+    }
+    entries
+}
+
+/// Key of a top-level YAML `key: value` line and the offset of its value
+fn yaml_line_key(text: &str) -> Option<(String, usize)> {
+    if text.starts_with([' ', '\t', '#', '-']) || text.trim().is_empty() {
+        return None;
+    }
+
+    let (key, rest) = match text.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let close = text[1..].find(quote)? + 1;
+            (text[1..close].to_string(), close + 1)
+        }
+        _ => {
+            let colon = text
+                .match_indices(':')
+                .find(|(i, _)| text[i + 1..].is_empty() || text[i + 1..].starts_with([' ', '\t']))?
+                .0;
+            (text[..colon].trim().to_string(), colon)
+        }
+    };
+    let after = text[rest..].trim_start();
+    after.starts_with(':').then(|| {
+        let colon = text.len() - after.len();
+        (key, colon + 1)
+    })
+}
+
+/// Key of a top-level TOML `key = value` line
+fn toml_line_key(text: &str) -> Option<String> {
+    if text.starts_with([' ', '\t', '#']) || text.trim().is_empty() {
+        return None;
+    }
+    let key = text[..text.find('=')?].trim();
+    let key = key
+        .strip_prefix('"')
+        .and_then(|k| k.strip_suffix('"'))
+        .or_else(|| key.strip_prefix('\'').and_then(|k| k.strip_suffix('\'')))
+        .unwrap_or(key);
+    Some(key.to_string())
+}
+
+/// Line terminator of the first line, `\n` when it has none
+fn line_ending(text: &str) -> &'static str {
+    match text.find('\n') {
+        Some(end) if text[..end].ends_with('\r') => "\r\n",
+        _ => "\n",
+    }
+}
+
+/// Indentation of the first block list item (`  - item`) in YAML lines
+fn list_indent(text: &str) -> Option<&str> {
+    split_lines(text).find_map(|(_, line)| {
+        let item = line.trim_start_matches([' ', '\t']);
+        (item.starts_with("- ") || item == "-").then(|| &line[..line.len() - item.len()])
+    })
+}
+
+/// YAML lines for `key: value`, in the style of the entry they replace
+///
+/// Block list items are indented by `indent`, which serde_yaml leaves out.
+fn yaml_entry(
+    key: &str,
+    value: &Value,
+    old: Option<&str>,
+    indent: &str,
+    newline: &str,
+) -> Result<String, PropertyEditError> {
+    let old_line = old.and_then(|old| split_lines(old).next().map(|(_, line)| line));
+    let old_value = old_line
+        .and_then(|line| Some(line[yaml_line_key(line)?.1..].trim()))
+        .unwrap_or("");
+
+    let mut mapping = serde_yaml::Mapping::new();
+    let yaml =
+        serde_yaml::to_value(value).map_err(|e| PropertyEditError::Unsupported(e.to_string()))?;
+    mapping.insert(serde_yaml::Value::String(key.to_string()), yaml);
+    let rendered = serde_yaml::to_string(&mapping)
+        .map_err(|e| PropertyEditError::Unsupported(e.to_string()))?;
+    let mut lines: Vec<String> = rendered.lines().map(str::to_string).collect();
+    if lines.get(1).is_some_and(|line| line.starts_with('-')) {
+        for line in &mut lines[1..] {
+            line.insert_str(0, indent);
+        }
+    }
+
+    // Keep a flow-style list flow-style
+    if let (true, Value::Array(items)) = (old_value.starts_with('['), value) {
+        if let Some(items) = items.iter().map(flow_item).collect::<Option<Vec<String>>>() {
+            let key_end = yaml_line_key(&lines[0]).map_or(lines[0].len(), |(_, at)| at);
+            lines = vec![format!("{} [{}]", &lines[0][..key_end], items.join(", "))];
+        }
+    }
+
+    // Keep a trailing comment on a single-line value
+    let single_line = old.is_some_and(|old| old.lines().count() == 1) && lines.len() == 1;
+    if single_line && !old_value.starts_with(['"', '\'']) {
+        if let Some(comment) = old_value.find(" #").map(|i| &old_value[i..]) {
+            lines[0].push_str(comment);
+        }
+    }
+
+    Ok(lines
+        .iter()
+        .map(|line| format!("{}{}", line, newline))
+        .collect())
+}
+
+/// A list item written inside `[...]`, `None` for nested lists and objects
+fn flow_item(item: &Value) -> Option<String> {
+    match item {
+        Value::Array(_) | Value::Object(_) => None,
+        Value::String(text) => {
+            let plain = serde_yaml::to_string(item).ok()?;
+            let plain = plain.trim_end();
+            // Characters that end or nest a flow item need quotes
+            if plain.contains('\n') || text.contains([',', '[', ']', '{', '}', '#']) {
+                serde_json::to_string(text).ok()
+            } else {
+                Some(plain.to_string())
+            }
+        }
+        _ => Some(item.to_string()),
+    }
+}
+
+/// TOML line for `key = value`, keeping the key as written
+fn toml_entry(
+    key: &str,
+    value: &Value,
+    old: Option<&str>,
+    newline: &str,
+) -> Result<String, PropertyEditError> {
+    if value.is_null() {
+        return Err(PropertyEditError::Unsupported(
+            "TOML frontmatter has no empty values".to_string(),
+        ));
+    }
+    if value.is_object() {
+        return Err(PropertyEditError::Unsupported(
+            "Tables can't be set in TOML frontmatter".to_string(),
+        ));
+    }
+    let toml =
+        toml::Value::try_from(value).map_err(|e| PropertyEditError::Unsupported(e.to_string()))?;
+
+    let key_text = match old.and_then(|old| old.find('=')) {
+        Some(eq) => old.map_or("", |old| old[..eq].trim_end()).to_string(),
+        None if !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')) =>
+        {
+            key.to_string()
+        }
+        None => {
+            serde_json::to_string(key).map_err(|e| PropertyEditError::Unsupported(e.to_string()))?
+        }
+    };
+    Ok(format!("{} = {}{}", key_text, toml, newline))
+}
+
+/// Property names are strings; YAML also allows numbers and booleans as keys
+fn yaml_key(key: serde_yaml::Value) -> Option<String> {
+    match key {
+        serde_yaml::Value::String(key) => Some(key),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn yaml_value(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                n.as_f64()
+                    .and_then(Number::from_f64)
+                    .map_or(Value::Null, Value::Number)
+            }
+        }
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(items) => {
+            Value::Array(items.into_iter().map(yaml_value).collect())
+        }
+        serde_yaml::Value::Mapping(mapping) => Value::Object(
+            mapping
+                .into_iter()
+                .filter_map(|(key, value)| Some((yaml_key(key)?, yaml_value(value))))
+                .collect::<Map<String, Value>>(),
+        ),
+        // `!tag value` keeps only the value
+        serde_yaml::Value::Tagged(tagged) => yaml_value(tagged.value),
+    }
+}
+
+fn toml_value(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_value).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn set_and_remove_without_frontmatter() {
+        assert_eq!(
+            set_property("", "status", &json!("open")).unwrap(),
+            "---\nstatus: open\n---\n"
+        );
+        assert_eq!(
+            set_property("# Title\r\nBody\r\n", "status", &json!("open")).unwrap(),
+            "---\r\nstatus: open\r\n---\r\n# Title\r\nBody\r\n"
+        );
+        assert_eq!(remove_property("", "status"), Ok(None));
+        assert_eq!(remove_property("# Title\n", "status"), Ok(None));
+    }
+
+    #[test]
+    fn set_keeps_everything_else() {
+        let content = "---\n# comment\nTitle: Old # kept\nrating: 3\n---\nBody\n";
+        assert_eq!(
+            set_property(content, "title", &json!("New")).unwrap(),
+            "---\n# comment\nTitle: New # kept\nrating: 3\n---\nBody\n"
+        );
+        assert_eq!(
+            set_property(content, "done", &json!(true)).unwrap(),
+            "---\n# comment\nTitle: Old # kept\nrating: 3\ndone: true\n---\nBody\n"
+        );
+        assert_eq!(
+            remove_property(content, "TITLE").unwrap().unwrap(),
+            "---\n# comment\nrating: 3\n---\nBody\n"
+        );
+        assert_eq!(
+            set_property("---\n---\n", "a", &json!(1)).unwrap(),
+            "---\na: 1\n---\n"
+        );
+        assert_eq!(
+            set_property("---\n: [\n---\n", "a", &json!(1)),
+            Err(PropertyEditError::Malformed)
+        );
+    }
+
+    #[test]
+    fn lists_keep_their_style() {
+        let indented = "---\ntags:\n  - a\n  - b\nrating: 3\n---\n";
+        assert_eq!(
+            set_property(indented, "tags", &json!(["a", "c", "d"])).unwrap(),
+            "---\ntags:\n  - a\n  - c\n  - d\nrating: 3\n---\n"
+        );
+        // A new list follows the lists already in the note
+        assert_eq!(
+            set_property(indented, "aliases", &json!(["x"])).unwrap(),
+            "---\ntags:\n  - a\n  - b\nrating: 3\naliases:\n  - x\n---\n"
+        );
+
+        let flush = "---\ntags:\n- a\n---\n";
+        assert_eq!(
+            set_property(flush, "tags", &json!(["b", "c"])).unwrap(),
+            "---\ntags:\n- b\n- c\n---\n"
+        );
+
+        let flow = "---\ntags: [a, b]\n---\n";
+        assert_eq!(
+            set_property(flow, "tags", &json!(["a", "x, y"])).unwrap(),
+            "---\ntags: [a, \"x, y\"]\n---\n"
+        );
+
+        assert_eq!(
+            remove_property(indented, "tags").unwrap().unwrap(),
+            "---\nrating: 3\n---\n"
+        );
+    }
+
+    #[test]
+    fn line_endings_come_from_the_frontmatter() {
+        // The body was pasted with different line endings than the frontmatter
+        let content = "---\ntitle: Old\n---\nBody\r\nMore\r\n";
+        assert_eq!(
+            set_property(content, "tags", &json!(["a"])).unwrap(),
+            "---\ntitle: Old\ntags:\n- a\n---\nBody\r\nMore\r\n"
+        );
+
+        let crlf = "---\r\ntags:\r\n  - a\r\n---\r\nBody\n";
+        assert_eq!(
+            set_property(crlf, "tags", &json!(["a", "b"])).unwrap(),
+            "---\r\ntags:\r\n  - a\r\n  - b\r\n---\r\nBody\n"
+        );
+        assert_eq!(
+            remove_property(crlf, "tags").unwrap().unwrap(),
+            "---\r\n---\r\nBody\n"
+        );
+    }
+
+    #[test]
+    fn toml_frontmatter() {
+        let content = "+++\ntitle = \"Old\"\n\n[extra]\nx = 1\n+++\n";
+        assert_eq!(
+            set_property(content, "rating", &json!(3)).unwrap(),
+            "+++\ntitle = \"Old\"\n\nrating = 3\n[extra]\nx = 1\n+++\n"
+        );
+        assert!(matches!(
+            set_property(content, "rating", &Value::Null),
+            Err(PropertyEditError::Unsupported(_))
+        ));
+    }
+
+    /// Value of `key` once the edited frontmatter is read back
+    fn read_back(content: &str, key: &str) -> Option<Value> {
+        property(&parse_frontmatter(content)?, key).cloned()
+    }
+
+    #[test]
+    fn values_read_back_as_set() {
+        let values = [
+            json!("yes"),
+            json!("12"),
+            json!("a: b"),
+            json!("#tag"),
+            json!("two\nlines"),
+            json!(null),
+            json!(2.5),
+            json!({"nested": {"list": [1, "x"]}}),
+        ];
+        for value in values {
+            let content = "---\nkey: old\nafter: 1\n---\nBody\n";
+            let updated = set_property(content, "key", &value).unwrap();
+            assert_eq!(
+                read_back(&updated, "key"),
+                Some(value.clone()),
+                "{}",
+                updated
+            );
+            assert_eq!(read_back(&updated, "after"), Some(json!(1)));
+            assert!(updated.ends_with("after: 1\n---\nBody\n"));
+        }
+    }
+
+    #[test]
+    fn entries_span_their_continuation_lines() {
+        let content =
+            "---\n\"my key\": 1\ntag: x\ntags:\n  - a\n\n  - b\nmeta:\n  a: 1\nend: 2\n---\n";
+        let range = |key| property_range(content, key).map(|range| &content[range]);
+        assert_eq!(range("My Key"), Some("\"my key\": 1\n"));
+        assert_eq!(range("tag"), Some("tag: x\n"));
+        assert_eq!(range("tags"), Some("tags:\n  - a\n\n  - b\n"));
+        assert_eq!(range("meta"), Some("meta:\n  a: 1\n"));
+        assert_eq!(range("a"), None);
+
+        // A list replaced by a scalar takes all of its lines along
+        assert_eq!(
+            set_property(content, "tags", &json!("none")).unwrap(),
+            "---\n\"my key\": 1\ntag: x\ntags: none\nmeta:\n  a: 1\nend: 2\n---\n"
+        );
+        assert_eq!(
+            remove_property(content, "meta").unwrap().unwrap(),
+            "---\n\"my key\": 1\ntag: x\ntags:\n  - a\n\n  - b\nend: 2\n---\n"
+        );
+        assert_eq!(remove_property(content, "missing"), Ok(None));
+    }
+
+    #[test]
+    fn only_mappings_are_edited() {
+        for content in [
+            "---\n- a\n- b\n---\n",
+            "---\njust text\n---\n",
+            "+++\nx = \n+++\n",
+        ] {
+            assert_eq!(
+                set_property(content, "a", &json!(1)),
+                Err(PropertyEditError::Malformed)
+            );
+            assert_eq!(
+                remove_property(content, "a"),
+                Err(PropertyEditError::Malformed)
+            );
+        }
+    }
+
+    #[test]
+    fn toml_keys_and_tables() {
+        let content = "+++\n\"odd key\" = 1\ntags = [\n  \"a\",\n]\n[extra]\nx = 1\n+++\n";
+        assert_eq!(
+            set_property(content, "Odd Key", &json!(2)).unwrap(),
+            "+++\n\"odd key\" = 2\ntags = [\n  \"a\",\n]\n[extra]\nx = 1\n+++\n"
+        );
+        assert_eq!(
+            set_property(content, "new key", &json!(["b"])).unwrap(),
+            "+++\n\"odd key\" = 1\ntags = [\n  \"a\",\n]\n\"new key\" = [\"b\"]\n[extra]\nx = 1\n+++\n"
+        );
+        assert_eq!(
+            remove_property(content, "tags").unwrap().unwrap(),
+            "+++\n\"odd key\" = 1\n[extra]\nx = 1\n+++\n"
+        );
+        // Keys inside tables are not top-level properties
+        assert_eq!(remove_property(content, "x"), Ok(None));
+        assert!(matches!(
+            set_property(content, "extra", &json!({"x": 2})),
+            Err(PropertyEditError::Unsupported(_))
+        ));
+    }
+}
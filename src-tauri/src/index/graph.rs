@@ -0,0 +1,430 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use super::tags::tag_matches;
+use super::{is_note, VaultIndex};
+
+/// Default number of hops from the focus note in local mode
+pub const DEFAULT_LOCAL_DEPTH: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphNodeKind {
+    Note,
+    Tag,
+    Attachment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNode {
+    /// Absolute path for notes and attachments, `#name` for tags
+    pub id: String,
+    pub kind: GraphNodeKind,
+    /// Display name: file stem for notes, file name for attachments, `#name` for tags
+    pub label: String,
+    /// Sum of the weights of all edges touching the node, for sizing
+    pub weight: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphEdge {
+    /// Id of the linking note
+    pub source: String,
+    /// Id of the linked note, attachment or tag
+    pub target: String,
+    /// Number of links (or tag occurrences) the edge stands for
+    pub weight: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphData {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Which part of the vault to draw
+#[derive(Debug, Clone, Default)]
+pub struct GraphFilter {
+    /// Draw only the neighbourhood of this note (local mode)
+    pub focus: Option<PathBuf>,
+    /// Hops from the focus note, following links in either direction
+    pub depth: usize,
+    /// Only notes inside this folder
+    pub folder: Option<PathBuf>,
+    /// Only notes carrying this tag or a tag nested below it
+    pub tag: Option<String>,
+    pub include_tags: bool,
+    pub include_attachments: bool,
+}
+
+impl VaultIndex {
+    /// Nodes and weighted edges of the link graph
+    ///
+    /// Folder and tag filters pick the notes first; local mode then keeps what is
+    /// within `depth` links of the focus note among them. Attachments and tags
+    /// hang off the notes that remain.
+    pub fn graph(&self, filter: &GraphFilter) -> GraphData {
+        let mut notes: BTreeSet<&PathBuf> = self
+            .notes
+            .iter()
+            .filter(|(path, entry)| {
+                filter
+                    .folder
+                    .as_ref()
+                    .is_none_or(|folder| path.starts_with(folder))
+                    && filter
+                        .tag
+                        .as_ref()
+                        .is_none_or(|tag| entry.tags.iter().any(|t| tag_matches(&t.name, tag)))
+            })
+            .map(|(path, _)| path)
+            .collect();
+        if let Some((focus, _)) = filter
+            .focus
+            .as_ref()
+            .and_then(|f| self.notes.get_key_value(f))
+        {
+            notes.insert(focus);
+        }
+
+        // Weighted note-to-file edges, keyed by (source, target)
+        let mut links: BTreeMap<(&Path, PathBuf), usize> = BTreeMap::new();
+        for source in &notes {
+            for link in &self.notes[*source].links {
+                let Some(target) = self.resolve(source, link) else {
+                    continue;
+                };
+                if target == **source {
+                    continue;
+                }
+                let wanted = if is_note(&target) {
+                    notes.contains(&target)
+                } else {
+                    filter.include_attachments
+                };
+                if wanted {
+                    *links.entry((source.as_path(), target)).or_default() += 1;
+                }
+            }
+        }
+
+        if let Some(focus) = &filter.focus {
+            let reached = neighbourhood(focus, &links, filter.depth);
+            notes.retain(|note| reached.contains(*note));
+            links.retain(|(source, target), _| {
+                reached.contains(*source) && (!is_note(target) || reached.contains(target))
+            });
+        }
+
+        let mut graph = GraphData::default();
+        let mut weights: HashMap<String, usize> = HashMap::new();
+        let mut extra_nodes: BTreeMap<String, GraphNode> = BTreeMap::new();
+
+        for ((source, target), weight) in links {
+            let source = self.shown(source);
+            let target_id = self.shown(&target);
+            if !is_note(&target) {
+                extra_nodes
+                    .entry(target_id.clone())
+                    .or_insert_with(|| GraphNode {
+                        id: target_id.clone(),
+                        kind: GraphNodeKind::Attachment,
+                        label: file_label(&target, false),
+                        weight: 0,
+                    });
+            }
+            *weights.entry(source.clone()).or_default() += weight;
+            *weights.entry(target_id.clone()).or_default() += weight;
+            graph.edges.push(GraphEdge {
+                source,
+                target: target_id,
+                weight,
+            });
+        }
+
+        if filter.include_tags {
+            for note in &notes {
+                let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+                for tag in &self.notes[*note].tags {
+                    *counts
+                        .entry(format!("#{}", tag.name.to_lowercase()))
+                        .or_default() += 1;
+                }
+                let source = self.shown(note);
+                for (tag, weight) in counts {
+                    extra_nodes.entry(tag.clone()).or_insert_with(|| GraphNode {
+                        id: tag.clone(),
+                        kind: GraphNodeKind::Tag,
+                        label: tag.clone(),
+                        weight: 0,
+                    });
+                    *weights.entry(source.clone()).or_default() += weight;
+                    *weights.entry(tag.clone()).or_default() += weight;
+                    graph.edges.push(GraphEdge {
+                        source: source.clone(),
+                        target: tag,
+                        weight,
+                    });
+                }
+            }
+        }
+
+        graph.nodes = notes
+            .iter()
+            .map(|note| GraphNode {
+                id: self.shown(note),
+                kind: GraphNodeKind::Note,
+                label: file_label(note, true),
+                weight: 0,
+            })
+            .chain(extra_nodes.into_values())
+            .map(|mut node| {
+                node.weight = weights.get(&node.id).copied().unwrap_or(0);
+                node
+            })
+            .collect();
+
+        graph
+    }
+}
+
+/// Notes within `depth` links of `focus`, following links in either direction
+fn neighbourhood(
+    focus: &Path,
+    links: &BTreeMap<(&Path, PathBuf), usize>,
+    depth: usize,
+) -> BTreeSet<PathBuf> {
+    let mut adjacent: HashMap<&Path, Vec<&Path>> = HashMap::new();
+    for (source, target) in links.keys() {
+        if is_note(target) {
+            adjacent.entry(*source).or_default().push(target.as_path());
+            adjacent.entry(target.as_path()).or_default().push(*source);
+        }
+    }
+
+    let mut reached: BTreeSet<PathBuf> = BTreeSet::from([focus.to_path_buf()]);
+    let mut queue: VecDeque<(&Path, usize)> = VecDeque::from([(focus, 0)]);
+    while let Some((note, hops)) = queue.pop_front() {
+        if hops == depth {
+            continue;
+        }
+        for next in adjacent.get(note).into_iter().flatten() {
+            if reached.insert(next.to_path_buf()) {
+                queue.push_back((next, hops + 1));
+            }
+        }
+    }
+    reached
+}
+
+fn file_label(path: &Path, strip_extension: bool) -> String {
+    let name = if strip_extension {
+        path.file_stem()
+    } else {
+        path.file_name()
+    };
+    name.map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Each test gets its own copy, since tests run in parallel
+    fn vault(name: &str) -> (PathBuf, VaultIndex) {
+        let dir = std::env::temp_dir().join(format!("yana-graph-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in [
+            (
+                "A.md",
+                "[[B]], again [[B]] and [[Missing]] ![[pic.png]] #project\n",
+            ),
+            ("B.md", "[[C]] [[B]] #project/sub\n"),
+            ("C.md", "[[D]]\n"),
+            ("D.md", ""),
+            ("archive/E.md", "[[A]] #project/old\n"),
+            ("pic.png", ""),
+        ] {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let dir = fs::canonicalize(dir).unwrap();
+        let mut index = VaultIndex::default();
+        index.rebuild(&dir);
+        (dir, index)
+    }
+
+    /// Node ids relative to the vault, tags as they are
+    fn nodes(root: &Path, graph: &GraphData) -> Vec<(String, GraphNodeKind, usize)> {
+        graph
+            .nodes
+            .iter()
+            .map(|node| (relative(root, &node.id), node.kind, node.weight))
+            .collect()
+    }
+
+    fn edges(root: &Path, graph: &GraphData) -> Vec<(String, String, usize)> {
+        graph
+            .edges
+            .iter()
+            .map(|edge| {
+                (
+                    relative(root, &edge.source),
+                    relative(root, &edge.target),
+                    edge.weight,
+                )
+            })
+            .collect()
+    }
+
+    fn relative(root: &Path, id: &str) -> String {
+        Path::new(id)
+            .strip_prefix(root)
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_else(|_| id.to_string())
+    }
+
+    fn note_names(root: &Path, graph: &GraphData) -> Vec<String> {
+        nodes(root, graph)
+            .into_iter()
+            .filter(|(_, kind, _)| *kind == GraphNodeKind::Note)
+            .map(|(id, _, _)| id)
+            .collect()
+    }
+
+    #[test]
+    fn repeated_links_add_up_and_unresolved_ones_are_left_out() {
+        let (root, index) = vault("weights");
+        let graph = index.graph(&GraphFilter::default());
+
+        assert_eq!(
+            edges(&root, &graph),
+            [
+                ("A.md".to_string(), "B.md".to_string(), 2),
+                ("B.md".to_string(), "C.md".to_string(), 1),
+                ("C.md".to_string(), "D.md".to_string(), 1),
+                ("archive/E.md".to_string(), "A.md".to_string(), 1),
+            ]
+        );
+        // Self-links don't count towards B's weight
+        assert_eq!(
+            nodes(&root, &graph),
+            [
+                ("A.md".to_string(), GraphNodeKind::Note, 3),
+                ("B.md".to_string(), GraphNodeKind::Note, 3),
+                ("C.md".to_string(), GraphNodeKind::Note, 2),
+                ("D.md".to_string(), GraphNodeKind::Note, 1),
+                ("archive/E.md".to_string(), GraphNodeKind::Note, 1),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn attachments_and_tags_are_opt_in() {
+        let (root, index) = vault("extras");
+        let graph = index.graph(&GraphFilter {
+            include_tags: true,
+            include_attachments: true,
+            ..GraphFilter::default()
+        });
+
+        let extras: Vec<(String, GraphNodeKind, usize)> = nodes(&root, &graph)
+            .into_iter()
+            .filter(|(_, kind, _)| *kind != GraphNodeKind::Note)
+            .collect();
+        assert_eq!(
+            extras,
+            [
+                ("#project".to_string(), GraphNodeKind::Tag, 1),
+                ("#project/old".to_string(), GraphNodeKind::Tag, 1),
+                ("#project/sub".to_string(), GraphNodeKind::Tag, 1),
+                ("pic.png".to_string(), GraphNodeKind::Attachment, 1),
+            ]
+        );
+        assert!(edges(&root, &graph).contains(&("A.md".to_string(), "pic.png".to_string(), 1)));
+        // The unresolved link gets no node of any kind
+        assert!(!graph.nodes.iter().any(|node| node.id.contains("Missing")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn local_graph_follows_links_both_ways_up_to_depth() {
+        let (root, index) = vault("local");
+        let local = |depth: usize| {
+            let graph = index.graph(&GraphFilter {
+                focus: Some(root.join("B.md")),
+                depth,
+                include_attachments: true,
+                ..GraphFilter::default()
+            });
+            note_names(&root, &graph)
+        };
+
+        assert_eq!(local(0), ["B.md"]);
+        assert_eq!(local(1), ["A.md", "B.md", "C.md"]);
+        assert_eq!(local(2), ["A.md", "B.md", "C.md", "D.md", "archive/E.md"]);
+
+        // Attachments of notes that are out of reach go with them
+        let graph = index.graph(&GraphFilter {
+            focus: Some(root.join("D.md")),
+            depth: 1,
+            include_attachments: true,
+            ..GraphFilter::default()
+        });
+        assert_eq!(
+            nodes(&root, &graph),
+            [
+                ("C.md".to_string(), GraphNodeKind::Note, 1),
+                ("D.md".to_string(), GraphNodeKind::Note, 1),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn folder_and_tag_filters_pick_the_notes() {
+        let (root, index) = vault("filters");
+
+        let graph = index.graph(&GraphFilter {
+            folder: Some(root.join("archive")),
+            ..GraphFilter::default()
+        });
+        assert_eq!(note_names(&root, &graph), ["archive/E.md"]);
+        // The link to A leaves the folder, so there is nothing to draw it to
+        assert!(graph.edges.is_empty());
+
+        // Nested tags match their parent, but not the other way around
+        let graph = index.graph(&GraphFilter {
+            tag: Some("project".to_string()),
+            ..GraphFilter::default()
+        });
+        assert_eq!(note_names(&root, &graph), ["A.md", "B.md", "archive/E.md"]);
+        let graph = index.graph(&GraphFilter {
+            tag: Some("project/sub".to_string()),
+            ..GraphFilter::default()
+        });
+        assert_eq!(note_names(&root, &graph), ["B.md"]);
+
+        // The focus note stays even when the filters would drop it
+        let graph = index.graph(&GraphFilter {
+            focus: Some(root.join("C.md")),
+            depth: 1,
+            folder: Some(root.join("archive")),
+            ..GraphFilter::default()
+        });
+        assert_eq!(note_names(&root, &graph), ["C.md"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,412 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::links::{parse_links, Link, LinkKind};
+use super::markdown::percent_encode_path;
+use super::rewrite::{apply_edits, RewrittenNote, TextEdit};
+use super::sections::{normalize_heading, parse_headings, Heading};
+use super::VaultIndex;
+
+/// A heading renamed in a note while links elsewhere still use the old text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeadingRename {
+    /// Heading text the links use
+    pub from: String,
+    /// Heading text now in the note
+    pub to: String,
+    /// Number of links in other notes pointing at the old heading
+    pub links: usize,
+}
+
+impl VaultIndex {
+    /// Remember headings that new content for a note renames
+    ///
+    /// `previous` are the note's headings from before the content was written.
+    /// They have to be taken from the index before the write, as the watcher
+    /// may re-index the note from disk as soon as the content lands. Renames are
+    /// chained, so typing a heading over several saves yields a single rename
+    /// from the text links use to the latest one.
+    pub fn track_heading_renames(&mut self, path: &Path, previous: &[Heading], content: &str) {
+        let renames = outline_renames(previous, &parse_headings(content));
+        if renames.is_empty() {
+            return;
+        }
+
+        let pending = self.renamed_headings.entry(path.to_path_buf()).or_default();
+        for (from, to) in renames {
+            let from_key = normalize_heading(&from);
+            match pending
+                .iter_mut()
+                .find(|(_, current)| normalize_heading(current) == from_key)
+            {
+                Some((_, current)) => *current = to,
+                None => pending.push((from, to)),
+            }
+        }
+        pending.retain(|(from, to)| normalize_heading(from) != normalize_heading(to));
+        if pending.is_empty() {
+            self.renamed_headings.remove(path);
+        }
+    }
+
+    /// Renamed headings of a note that links in other notes still point at
+    pub fn pending_heading_renames(&self, path: &Path) -> Vec<HeadingRename> {
+        let (Some(pending), Some(entry)) = (self.renamed_headings.get(path), self.notes.get(path))
+        else {
+            return Vec::new();
+        };
+        let inbound = self.inbound_heading_links(path);
+
+        pending
+            .iter()
+            // A heading renamed and then deleted has nothing left to point at
+            .filter(|(_, to)| {
+                let to = normalize_heading(to);
+                entry
+                    .headings
+                    .iter()
+                    .any(|h| normalize_heading(&h.text) == to)
+            })
+            .map(|(from, to)| HeadingRename {
+                from: from.clone(),
+                to: to.clone(),
+                links: inbound
+                    .iter()
+                    .filter(|(_, link)| fragment_uses(link, from))
+                    .count(),
+            })
+            .filter(|rename| rename.links > 0)
+            .collect()
+    }
+
+    /// Forget the renamed headings of a note, e.g. once links were updated
+    pub fn clear_heading_renames(&mut self, path: &Path) {
+        self.renamed_headings.remove(path);
+    }
+
+    /// Rewrite links in other notes so their `#heading` fragments follow renames
+    ///
+    /// The renamed note itself is left alone, its editor owns the content.
+    pub fn rewrite_heading_links(
+        &self,
+        path: &Path,
+        renames: &[HeadingRename],
+    ) -> Vec<RewrittenNote> {
+        let mut by_source: BTreeMap<&PathBuf, Vec<&Link>> = BTreeMap::new();
+        for (source, link) in self.inbound_heading_links(path) {
+            if renames
+                .iter()
+                .any(|rename| fragment_uses(link, &rename.from))
+            {
+                by_source.entry(source).or_default().push(link);
+            }
+        }
+
+        let mut notes = Vec::new();
+        for source in by_source.keys() {
+            let content = match fs::read_to_string(source) {
+                Ok(content) => content,
+                Err(e) => {
+                    log::warn!(
+                        "Skipping heading link update in '{}': {}",
+                        source.display(),
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            // Re-parse so the spans match the file on disk
+            let edits: Vec<TextEdit> = parse_links(&content)
+                .into_iter()
+                .filter(|link| self.resolve(source, link).as_deref() == Some(path))
+                .filter_map(|link| {
+                    let text = renamed_fragment(&link, &content, renames)?;
+                    Some(TextEdit {
+                        range: link.fragment_span.clone(),
+                        text,
+                    })
+                })
+                .collect();
+
+            if !edits.is_empty() {
+                notes.push(RewrittenNote {
+                    path: (*source).clone(),
+                    links: edits.len(),
+                    content: apply_edits(&content, edits),
+                    original: content,
+                });
+            }
+        }
+        notes
+    }
+
+    /// Links in other notes that point at a heading of `path`
+    fn inbound_heading_links(&self, path: &Path) -> Vec<(&PathBuf, &Link)> {
+        self.backlinks(path)
+            .into_iter()
+            .filter(|(source, link)| {
+                source.as_path() != path
+                    && link
+                        .fragment
+                        .as_deref()
+                        .is_some_and(|fragment| !fragment.starts_with('^'))
+            })
+            .collect()
+    }
+}
+
+/// Headings whose text changed between two outlines, as (old, new) pairs
+///
+/// Headings that stay the same anchor both ends; in between, a rename is only
+/// recognized when as many headings were removed as added, at the same levels.
+/// Anything else is treated as added and deleted headings.
+fn outline_renames(old: &[Heading], new: &[Heading]) -> Vec<(String, String)> {
+    let same = |a: &Heading, b: &Heading| {
+        a.level == b.level && normalize_heading(&a.text) == normalize_heading(&b.text)
+    };
+    let prefix = old.iter().zip(new).take_while(|(a, b)| same(a, b)).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| same(a, b))
+        .count();
+
+    let removed = &old[prefix..old.len() - suffix];
+    let added = &new[prefix..new.len() - suffix];
+    if removed.len() != added.len() || removed.iter().zip(added).any(|(a, b)| a.level != b.level) {
+        return Vec::new();
+    }
+
+    removed
+        .iter()
+        .zip(added)
+        .filter(|(a, b)| !a.text.trim().is_empty() && !b.text.trim().is_empty())
+        .map(|(a, b)| (a.text.clone(), b.text.clone()))
+        .collect()
+}
+
+/// Whether any `#`-separated part of a link's heading fragment names `heading`
+fn fragment_uses(link: &Link, heading: &str) -> bool {
+    link.fragment
+        .as_deref()
+        .is_some_and(|fragment| fragment.split('#').any(|part| names_heading(part, heading)))
+}
+
+/// Whether one part of a fragment names a heading, as written or as a slug
+fn names_heading(part: &str, heading: &str) -> bool {
+    normalize_heading(part) == normalize_heading(heading) || part.trim() == slug(heading)
+}
+
+/// New fragment text for a link, in the style it was written in
+fn renamed_fragment(link: &Link, content: &str, renames: &[HeadingRename]) -> Option<String> {
+    let fragment = link.fragment.as_deref()?;
+    content.get(link.fragment_span.clone())?;
+
+    let mut changed = false;
+    let parts: Vec<String> = fragment
+        .split('#')
+        .map(|part| {
+            let Some(rename) = renames.iter().find(|r| names_heading(part, &r.from)) else {
+                return part.to_string();
+            };
+            changed = true;
+            if normalize_heading(part) != normalize_heading(&rename.from) {
+                slug(&rename.to)
+            } else {
+                rename.to.clone()
+            }
+        })
+        .collect();
+    if !changed {
+        return None;
+    }
+
+    let text = parts.join("#");
+    Some(match link.kind {
+        LinkKind::Wiki => text,
+        LinkKind::Markdown => {
+            // `<...>` destinations may contain spaces, bare ones must be encoded
+            let angled = content[link.span.clone()].contains("](<");
+            if angled {
+                text
+            } else {
+                percent_encode_path(&text).replace("%23", "#")
+            }
+        }
+    })
+}
+
+/// GitHub-style heading anchor: lowercase, punctuation dropped, spaces as `-`
+fn slug(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rename_survives_reindex_before_tracking() {
+        let dir = std::env::temp_dir().join(format!("yana-headings-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("n.md"), "# Top\n## Old Heading\ntext\n").unwrap();
+        fs::write(
+            dir.join("a.md"),
+            "[[n#Old Heading]] [t](n.md#Old%20Heading)\n",
+        )
+        .unwrap();
+        let root = fs::canonicalize(&dir).unwrap();
+        let note = root.join("n.md");
+
+        let mut index = VaultIndex::default();
+        index.rebuild(&root);
+        let mut previous = index.note(&note).unwrap().headings.clone();
+        for step in ["New", "New Heading"] {
+            let content = format!("# Top\n## {}\ntext\n", step);
+            // The watcher picks up the write before the save is tracked
+            index.update_note(&note, &content);
+            index.track_heading_renames(&note, &previous, &content);
+            index.update_note(&note, &content);
+            previous = index.note(&note).unwrap().headings.clone();
+        }
+
+        let renames = index.pending_heading_renames(&note);
+        assert_eq!(renames.len(), 1);
+        assert_eq!(
+            (
+                renames[0].from.as_str(),
+                renames[0].to.as_str(),
+                renames[0].links
+            ),
+            ("Old Heading", "New Heading", 2)
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn outline(content: &str) -> Vec<Heading> {
+        parse_headings(content)
+    }
+
+    /// Write a note the way a save does: headings from the index, then track
+    fn save(index: &mut VaultIndex, path: &Path, content: &str) {
+        let previous = index.note(path).unwrap().headings.clone();
+        fs::write(path, content).unwrap();
+        index.track_heading_renames(path, &previous, content);
+        index.update_note(path, content);
+    }
+
+    #[test]
+    fn renames_need_matching_outlines() {
+        let renames = |old, new| outline_renames(&outline(old), &outline(new));
+
+        assert_eq!(
+            renames("# A\n## B\n## C\n# D\n", "# A\n## X\n## Y\n# D\n"),
+            [("B".into(), "X".into()), ("C".into(), "Y".into())]
+        );
+        // Changes in case or spacing only are not renames
+        assert!(renames("# A\n## Some  Text\n", "# A\n## some text\n").is_empty());
+        // Added or deleted headings, or a changed level, are not renames either
+        assert!(renames("# A\n## B\n", "# A\n## B\n## C\n").is_empty());
+        assert!(renames("# A\n## B\n## C\n", "# A\n## X\n").is_empty());
+        assert!(renames("# A\n## B\n", "# A\n### X\n").is_empty());
+        // A heading emptied while typing is not renamed yet
+        assert!(renames("# A\n## B\n", "# A\n## \n").is_empty());
+    }
+
+    #[test]
+    fn rewrites_fragments_in_their_own_style() {
+        let dir = std::env::temp_dir().join(format!("yana-heading-links-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("n.md"),
+            "# Top\n## Old Heading\n[[#Old Heading]]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("a.md"),
+            "[[n#Old Heading]] [[n#Top#old heading|alias]] [t](n.md#Old%20Heading)\n\
+             [s](n.md#old-heading) [b](<n.md#Old Heading>) [[n#^blk]] [[n#Top]]\n",
+        )
+        .unwrap();
+        fs::write(dir.join("b.md"), "[[n]] [[n#Top]]\n").unwrap();
+        let root = fs::canonicalize(&dir).unwrap();
+        let note = root.join("n.md");
+        let mut index = VaultIndex::default();
+        index.rebuild(&root);
+
+        save(
+            &mut index,
+            &note,
+            "# Top\n## New Heading\n[[#Old Heading]]\n",
+        );
+        let renames = index.pending_heading_renames(&note);
+        assert_eq!(renames.len(), 1);
+        assert_eq!(renames[0].links, 5);
+
+        let rewritten = index.rewrite_heading_links(&note, &renames);
+        // The renamed note and notes without matching links are left alone
+        assert_eq!(rewritten.len(), 1);
+        assert_eq!(rewritten[0].path, root.join("a.md"));
+        assert_eq!(rewritten[0].links, 5);
+        assert_eq!(
+            rewritten[0].content,
+            "[[n#New Heading]] [[n#Top#New Heading|alias]] [t](n.md#New%20Heading)\n\
+             [s](n.md#new-heading) [b](<n.md#New Heading>) [[n#^blk]] [[n#Top]]\n"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pending_renames_drop_undone_and_deleted_headings() {
+        let dir = std::env::temp_dir().join(format!("yana-heading-pending-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("n.md"), "# One\n# Two\n").unwrap();
+        fs::write(dir.join("a.md"), "[[n#One]] [[n#Two]]\n").unwrap();
+        let root = fs::canonicalize(&dir).unwrap();
+        let note = root.join("n.md");
+        let mut index = VaultIndex::default();
+        index.rebuild(&root);
+        let pending = |index: &VaultIndex| -> Vec<(String, String)> {
+            index
+                .pending_heading_renames(&note)
+                .into_iter()
+                .map(|rename| (rename.from, rename.to))
+                .collect()
+        };
+
+        // Renaming back to the original text leaves nothing to update
+        save(&mut index, &note, "# Uno\n# Two\n");
+        assert_eq!(pending(&index), [("One".into(), "Uno".into())]);
+        save(&mut index, &note, "# one\n# Two\n");
+        assert!(pending(&index).is_empty());
+
+        // A renamed heading that is then deleted has nothing left to point at
+        save(&mut index, &note, "# One\n# Deux\n");
+        assert_eq!(pending(&index), [("Two".into(), "Deux".into())]);
+        save(&mut index, &note, "# One\n");
+        assert!(pending(&index).is_empty());
+
+        save(&mut index, &note, "# Eins\n");
+        assert_eq!(pending(&index).len(), 1);
+        index.clear_heading_renames(&note);
+        assert!(pending(&index).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
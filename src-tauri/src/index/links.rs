@@ -0,0 +1,527 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use super::markdown::{code_spans, column_of, in_ranges, percent_decode, prose_lines, snippet};
+
+/// Longest context snippet stored per link, in characters
+const SNIPPET_CHARS: usize = 160;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// `[[target#fragment|alias]]`
+    Wiki,
+    /// `[label](relative/path.md#fragment)`
+    Markdown,
+}
+
+/// A link to another note or file, as written in a note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Link {
+    pub kind: LinkKind,
+    /// Whether the link is an embed (`![[...]]` or `![...](...)`)
+    pub embed: bool,
+    /// Link target without fragment, percent-decoded for markdown links
+    pub target: String,
+    /// Heading or `^block` after the `#`
+    pub fragment: Option<String>,
+    /// Wiki alias or markdown label
+    pub label: Option<String>,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based character column of the link start
+    pub column: usize,
+    /// Trimmed text of the line the link is on
+    pub context: String,
+    /// Byte range of the whole link in the note, including a leading `!`
+    #[serde(skip)]
+    pub span: Range<usize>,
+    /// Byte range of the written target (before decoding) in the note
+    #[serde(skip)]
+    pub target_span: Range<usize>,
+    /// Byte range of the written fragment (before decoding), empty if there is none
+    #[serde(skip)]
+    pub fragment_span: Range<usize>,
+}
+
+/// Extract every internal link from a note
+///
+/// Links inside frontmatter, fenced code blocks and inline code are ignored, as are
+/// markdown links with a URL scheme (`https:`, `mailto:` ...).
+pub fn parse_links(content: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    for line in prose_lines(content) {
+        let code = code_spans(line.text);
+        let mut found = Vec::new();
+        scan_line(line.text, 0..line.text.len(), &code, &mut found);
+
+        for mut link in found {
+            link.line = line.number;
+            link.column = column_of(line.text, link.span.start);
+            link.context = snippet(line.text, link.span.clone(), SNIPPET_CHARS);
+            link.span = shift(&link.span, line.start);
+            link.target_span = shift(&link.target_span, line.start);
+            link.fragment_span = shift(&link.fragment_span, line.start);
+            links.push(link);
+        }
+    }
+    links
+}
+
+fn shift(range: &Range<usize>, by: usize) -> Range<usize> {
+    range.start + by..range.end + by
+}
+
+/// Scan a range of a line for links; spans in the results are relative to the line
+fn scan_line(text: &str, range: Range<usize>, code: &[Range<usize>], out: &mut Vec<Link>) {
+    let bytes = text.as_bytes();
+    let mut i = range.start;
+
+    while i < range.end {
+        if bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if in_ranges(code, i) {
+            i += 1;
+            continue;
+        }
+
+        let embed = bytes[i] == b'!' && bytes.get(i + 1) == Some(&b'[');
+        let open = if embed { i + 1 } else { i };
+        if bytes.get(open) != Some(&b'[') {
+            i += 1;
+            continue;
+        }
+
+        if bytes.get(open + 1) == Some(&b'[') {
+            if let Some(link) = parse_wiki(text, i, open, embed, range.end) {
+                i = link.span.end;
+                out.push(link);
+                continue;
+            }
+        } else if let Some((link, label_range, end)) =
+            parse_markdown(text, i, open, embed, range.end)
+        {
+            i = end;
+            // Images inside a link label, e.g. a linked thumbnail
+            scan_line(text, label_range, code, out);
+            if let Some(link) = link {
+                out.push(link);
+            }
+            continue;
+        }
+        i = open + 1;
+    }
+}
+
+/// Parse `[[target#fragment|alias]]` starting at `open`
+fn parse_wiki(text: &str, start: usize, open: usize, embed: bool, limit: usize) -> Option<Link> {
+    let inner_start = open + 2;
+    let close = text[inner_start..limit].find("]]")? + inner_start;
+    let inner = &text[inner_start..close];
+    if inner.contains('[') {
+        return None;
+    }
+
+    // `\|` separates the alias inside tables, where a bare `|` would end the cell
+    let (reference, alias) = match inner.find('|') {
+        Some(bar) => {
+            let reference = inner[..bar].strip_suffix('\\').unwrap_or(&inner[..bar]);
+            (reference, Some(inner[bar + 1..].trim().to_string()))
+        }
+        None => (inner, None),
+    };
+    // The target is always a prefix of the inner text
+    let (target, fragment, fragment_range) = split_fragment(reference);
+
+    let trimmed = target.trim();
+    if trimmed.is_empty() && fragment.is_none() {
+        return None;
+    }
+    let lead = target.len() - target.trim_start().len();
+
+    Some(Link {
+        kind: LinkKind::Wiki,
+        embed,
+        target: trimmed.to_string(),
+        fragment,
+        label: alias.filter(|a| !a.is_empty()),
+        line: 0,
+        column: 0,
+        context: String::new(),
+        span: start..close + 2,
+        target_span: inner_start + lead..inner_start + lead + trimmed.len(),
+        fragment_span: shift(&fragment_range, inner_start),
+    })
+}
+
+/// Parse `[label](destination "title")` starting at `open`
+///
+/// Returns the link (absent for external URLs), the label range, so the label can
+/// be scanned for nested images, and the end offset. `None` means this was not a
+/// link at all.
+fn parse_markdown(
+    text: &str,
+    start: usize,
+    open: usize,
+    embed: bool,
+    limit: usize,
+) -> Option<(Option<Link>, Range<usize>, usize)> {
+    let bytes = text.as_bytes();
+
+    // Label: balanced brackets, honouring escapes
+    let mut depth = 0usize;
+    let mut i = open;
+    let label_end = loop {
+        if i >= limit {
+            return None;
+        }
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break i;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    };
+    if bytes.get(label_end + 1) != Some(&b'(') {
+        return None;
+    }
+
+    // Destination: `<...>` or a run with balanced parentheses
+    //
+    // The editor saves destinations with raw spaces (`attachments/Screen Shot.png`),
+    // so a bare destination runs up to a quoted title or the closing parenthesis.
+    let mut j = label_end + 2;
+    while j < limit && bytes[j] == b' ' {
+        j += 1;
+    }
+    let (dest_range, mut j) = if bytes.get(j) == Some(&b'<') {
+        let close = text[j + 1..limit].find('>')? + j + 1;
+        (j + 1..close, close + 1)
+    } else {
+        let dest_start = j;
+        let mut parens = 0usize;
+        while j < limit {
+            match bytes[j] {
+                b'\\' => j += 1,
+                b'(' => parens += 1,
+                b')' if parens == 0 => break,
+                b')' => parens -= 1,
+                _ => {}
+            }
+            j += 1;
+        }
+        let j = j.min(limit);
+        let raw = &text[dest_start..j];
+        let dest = raw[..title_start(raw).unwrap_or(raw.len())].trim_end();
+        (dest_start..dest_start + dest.len(), j)
+    };
+
+    // Optional title, then the closing parenthesis
+    while j < limit && bytes[j] != b')' {
+        if bytes[j] == b'\\' {
+            j += 1;
+        }
+        j += 1;
+    }
+    if j >= limit {
+        return None;
+    }
+    let end = j + 1;
+    let label_range = open + 1..label_end;
+
+    let destination = &text[dest_range.clone()];
+    if destination.is_empty() || has_scheme(destination) {
+        return Some((None, label_range, end));
+    }
+
+    let (target, fragment, fragment_range) = split_fragment(destination);
+    let label = text[label_range.clone()].trim();
+
+    let link = Link {
+        kind: LinkKind::Markdown,
+        embed,
+        target: percent_decode(target),
+        fragment: fragment.map(|f| percent_decode(&f)),
+        label: (!label.is_empty()).then(|| label.to_string()),
+        line: 0,
+        column: 0,
+        context: String::new(),
+        span: start..end,
+        target_span: dest_range.start..dest_range.start + target.len(),
+        fragment_span: shift(&fragment_range, dest_range.start),
+    };
+    Some((Some(link), label_range, end))
+}
+
+/// Offset of the whitespace before a `"title"`, `'title'` or `(title)` that ends
+/// a bare destination
+fn title_start(raw: &str) -> Option<usize> {
+    let trimmed = raw.trim_end();
+    raw.char_indices()
+        .filter(|(_, c)| c.is_whitespace())
+        .find(|(space, _)| {
+            let rest = raw[*space..].trim_start();
+            let close = match rest.chars().next() {
+                Some('"') => '"',
+                Some('\'') => '\'',
+                Some('(') => ')',
+                _ => return false,
+            };
+            rest.len() > 1 && trimmed.ends_with(close)
+        })
+        .map(|(space, _)| space)
+}
+
+/// Split `target#fragment`, dropping an empty fragment
+///
+/// Also returns the byte range of the trimmed fragment within `reference`.
+fn split_fragment(reference: &str) -> (&str, Option<String>, Range<usize>) {
+    match reference.find('#') {
+        Some(hash) => {
+            let raw = &reference[hash + 1..];
+            let fragment = raw.trim();
+            let start = hash + 1 + (raw.len() - raw.trim_start().len());
+            (
+                &reference[..hash],
+                (!fragment.is_empty()).then(|| fragment.to_string()),
+                start..start + fragment.len(),
+            )
+        }
+        None => (reference, None, reference.len()..reference.len()),
+    }
+}
+
+/// Targets of HTML `<img src>` and `<a href>` tags, percent-decoded
+///
+/// Only references to files count: URLs with a scheme, fragments and query
+/// strings are dropped. Tags in frontmatter and code are ignored, like links.
+pub fn parse_html_refs(content: &str) -> Vec<String> {
+    let mut refs = Vec::new();
+    for line in prose_lines(content) {
+        let code = code_spans(line.text);
+        let mut rest = 0;
+        while let Some(found) = line.text[rest..].find('<') {
+            let open = rest + found;
+            rest = open + 1;
+            if in_ranges(&code, open) {
+                continue;
+            }
+            let tag = &line.text[open + 1..];
+            let name_end = tag
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(tag.len());
+            let attribute = match tag[..name_end].to_ascii_lowercase().as_str() {
+                "img" => "src",
+                "a" => "href",
+                _ => continue,
+            };
+            let Some(close) = tag_end(&tag[name_end..]) else {
+                continue;
+            };
+            rest = open + 1 + name_end + close + 1;
+
+            let Some(value) = html_attribute(&tag[name_end..name_end + close], attribute) else {
+                continue;
+            };
+            let value = value.split(['#', '?']).next().unwrap_or_default().trim();
+            if !value.is_empty() && !has_scheme(value) {
+                refs.push(percent_decode(value));
+            }
+        }
+    }
+    refs
+}
+
+/// Offset of the `>` closing the tag at the start of `text`, skipping quoted values
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i),
+            (Some(open), _) if c == open => quote = None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Value of an attribute in the text of a tag after its name
+fn html_attribute<'a>(mut attributes: &'a str, name: &str) -> Option<&'a str> {
+    loop {
+        attributes = attributes.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+        if attributes.is_empty() {
+            return None;
+        }
+        let key_end = attributes
+            .find(|c: char| c.is_ascii_whitespace() || c == '=' || c == '/')
+            .unwrap_or(attributes.len());
+        let key = &attributes[..key_end];
+        attributes = attributes[key_end..].trim_start();
+
+        let Some(after) = attributes.strip_prefix('=') else {
+            continue;
+        };
+        let after = after.trim_start();
+        let (value, remaining) = match after.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = after[1..].find(quote)? + 1;
+                (&after[1..end], &after[end + 1..])
+            }
+            _ => {
+                let end = after
+                    .find(|c: char| c.is_ascii_whitespace())
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        if key.eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+        attributes = remaining;
+    }
+}
+
+/// Whether a destination starts with a URL scheme such as `https:` or `mailto:`
+fn has_scheme(destination: &str) -> bool {
+    let Some(colon) = destination.find(':') else {
+        return false;
+    };
+    let scheme = &destination[..colon];
+    // A single letter is a Windows drive, not a scheme
+    scheme.len() > 1
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(content: &str) -> Vec<(String, Option<String>)> {
+        parse_links(content)
+            .into_iter()
+            .map(|link| (link.target, link.fragment))
+            .collect()
+    }
+
+    #[test]
+    fn html_image_and_anchor_targets() {
+        let content = "---\ncover: <img src=\"skip.png\">\n---\n\
+            <img alt=\"a > b\" src=\"attachments/Screen%20Shot.png\"> <IMG SRC=x.png />\n\
+            <a class=file href='docs/Plan B.pdf#page=2'>plan</a> <a href=\"https://example.com\">\n\
+            `<img src=\"code.png\">` <p src=\"para.png\"> <a name=top>\n\
+            ```\n<img src=\"fenced.png\">\n```\n";
+        assert_eq!(
+            parse_html_refs(content),
+            ["attachments/Screen Shot.png", "x.png", "docs/Plan B.pdf"]
+        );
+    }
+
+    #[test]
+    fn destination_with_spaces() {
+        let content = "![](attachments/Screen Shot.png) and [doc](My Notes/Plan B.md#Next steps)";
+        assert_eq!(
+            targets(content),
+            [
+                ("attachments/Screen Shot.png".to_string(), None),
+                (
+                    "My Notes/Plan B.md".to_string(),
+                    Some("Next steps".to_string())
+                ),
+            ]
+        );
+
+        let link = &parse_links(content)[0];
+        assert_eq!(
+            &content[link.target_span.clone()],
+            "attachments/Screen Shot.png"
+        );
+        assert_eq!(
+            &content[link.span.clone()],
+            "![](attachments/Screen Shot.png)"
+        );
+    }
+
+    #[test]
+    fn destination_with_title() {
+        assert_eq!(
+            targets(
+                r#"[a](Plan B.md "The plan") [b](c.md 'title') [d](e f.md (title)) [g](h.md )"#
+            ),
+            [
+                ("Plan B.md".to_string(), None),
+                ("c.md".to_string(), None),
+                ("e f.md".to_string(), None),
+                ("h.md".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn destination_forms() {
+        assert_eq!(
+            targets("[a](<Plan B.md>) [b](Plan%20C.md) [c](dir/(1).md) [d](https://x.org/a b)"),
+            [
+                ("Plan B.md".to_string(), None),
+                ("Plan C.md".to_string(), None),
+                ("dir/(1).md".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn wiki_links() {
+        let links = parse_links("[[Note#Heading|Alias]] ![[img.png]] [[#Local]] [[Table\\|cell]]");
+        let found: Vec<_> = links
+            .iter()
+            .map(|link| {
+                (
+                    link.target.as_str(),
+                    link.fragment.as_deref(),
+                    link.label.as_deref(),
+                    link.embed,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("Note", Some("Heading"), Some("Alias"), false),
+                ("img.png", None, None, true),
+                ("", Some("Local"), None, false),
+                ("Table", None, Some("cell"), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn code_is_skipped() {
+        let content = "---\nlink: \"[[Front]]\"\n---\n`[[Inline]]`\n```\n[[Fenced]]\n```\n[[Real]]";
+        let links = parse_links(content);
+        assert_eq!(links.len(), 1);
+        assert_eq!((links[0].target.as_str(), links[0].line), ("Real", 8));
+    }
+
+    #[test]
+    fn nested_image_in_label() {
+        assert_eq!(
+            targets("[![thumb](small.png)](big.png)"),
+            [
+                ("small.png".to_string(), None),
+                ("big.png".to_string(), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::links::{Link, LinkKind};
+use super::{is_note, VaultIndex};
+
+/// Image extensions, for telling missing embedded images from other files
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp", "ico"];
+
+/// Minimum similarity for a file to be suggested as the intended target
+const SUGGESTION_THRESHOLD: f64 = 0.5;
+
+/// What a broken link was meant to point at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingKind {
+    /// A link to a note that does not exist
+    Note,
+    /// An embedded image that does not exist
+    Attachment,
+    /// A link to any other file that does not exist
+    File,
+}
+
+/// A link that resolves to nothing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenLink {
+    /// Note containing the link
+    pub source: String,
+    pub missing: MissingKind,
+    #[serde(flatten)]
+    pub link: Link,
+    /// Existing file most similar to the link target
+    pub suggestion: Option<String>,
+    /// Link target that would point at the suggestion, written in the link's style
+    pub suggested_target: Option<String>,
+}
+
+impl VaultIndex {
+    /// Every link in the vault that does not resolve to an existing file
+    pub fn broken_links(&self) -> Vec<BrokenLink> {
+        let mut broken = Vec::new();
+
+        for (source, entry) in &self.notes {
+            // Only needed to write suggestions in the link's style, so read lazily
+            let mut content: Option<String> = None;
+
+            for link in &entry.links {
+                if self.resolve(source, link).is_some() {
+                    continue;
+                }
+
+                let missing = classify(link);
+                let suggestion = self.most_similar(source, &link.target, missing);
+                let suggested_target = suggestion.as_ref().and_then(|target| {
+                    let content = content
+                        .get_or_insert_with(|| std::fs::read_to_string(source).unwrap_or_default());
+                    // The note may have changed since it was indexed
+                    content.get(link.target_span.clone())?;
+                    self.link_target_text(source, link, content, target)
+                });
+
+                broken.push(BrokenLink {
+                    source: self.shown(source),
+                    missing,
+                    link: link.clone(),
+                    suggestion: suggestion.map(|s| self.shown(&s)),
+                    suggested_target,
+                });
+            }
+        }
+
+        broken.sort_by(|a, b| {
+            a.source
+                .cmp(&b.source)
+                .then(a.link.line.cmp(&b.link.line))
+                .then(a.link.column.cmp(&b.link.column))
+        });
+        broken
+    }
+
+    /// Existing file whose name is closest to a link target
+    ///
+    /// Only files of the same sort are considered: notes for note links, images
+    /// for embedded images, everything else for other files.
+    fn most_similar(&self, source: &Path, target: &str, missing: MissingKind) -> Option<PathBuf> {
+        let wanted = comparable_name(target);
+        if wanted.is_empty() {
+            return None;
+        }
+
+        let folder = source.parent();
+        self.files
+            .iter()
+            .filter(|file| match missing {
+                MissingKind::Note => is_note(file),
+                MissingKind::Attachment => is_image(file),
+                MissingKind::File => !is_note(file),
+            })
+            .filter_map(|file| {
+                let name = comparable_name(&file.file_name()?.to_string_lossy());
+                let score = strsim::normalized_damerau_levenshtein(&wanted, &name);
+                (score >= SUGGESTION_THRESHOLD).then_some((file, score))
+            })
+            // Highest score wins, ties go to files next to the linking note
+            .max_by(|(a, a_score), (b, b_score)| {
+                a_score
+                    .total_cmp(b_score)
+                    .then((a.parent() == folder).cmp(&(b.parent() == folder)))
+                    .then(b.cmp(a))
+            })
+            .map(|(file, _)| file.clone())
+    }
+}
+
+fn classify(link: &Link) -> MissingKind {
+    let target = Path::new(&link.target);
+    if (link.kind == LinkKind::Wiki && target.extension().is_none()) || is_note(target) {
+        MissingKind::Note
+    } else if link.embed && is_image(target) {
+        MissingKind::Attachment
+    } else if target.extension().is_none() {
+        // Markdown links may leave out the `.md`
+        MissingKind::Note
+    } else {
+        MissingKind::File
+    }
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Lowercase file name without folders and without a `.md` extension
+fn comparable_name(target: &str) -> String {
+    let name = target
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(target)
+        .to_lowercase();
+    match name.strip_suffix(".md") {
+        Some(stem) => stem.to_string(),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::links::parse_links;
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn broken_links_with_suggestions() {
+        let root = std::env::temp_dir().join(format!("yana-lint-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("Work/attachments")).unwrap();
+        fs::write(root.join("Work/Meeting Notes.md"), "").unwrap();
+        fs::write(root.join("Work/attachments/diagram.png"), "").unwrap();
+        fs::write(root.join("Work/report.pdf"), "").unwrap();
+        fs::write(
+            root.join("Work/Index.md"),
+            "[[Meeting Note]] [[Meeting Notes]]\n\
+             ![](attachments/diagam.png) [pdf](reprt.pdf)\n\
+             [[Nothing like it at all]] `[[In code]]`\n",
+        )
+        .unwrap();
+        let root = fs::canonicalize(root).unwrap();
+        let mut index = VaultIndex::default();
+        index.rebuild(&root);
+
+        let broken = index.broken_links();
+        let found: Vec<_> = broken
+            .iter()
+            .map(|broken| {
+                (
+                    broken.link.line,
+                    broken.link.column,
+                    broken.missing,
+                    broken.suggested_target.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, 1, MissingKind::Note, Some("Meeting Notes")),
+                (
+                    2,
+                    1,
+                    MissingKind::Attachment,
+                    Some("attachments/diagram.png")
+                ),
+                (2, 29, MissingKind::File, Some("report.pdf")),
+                (3, 1, MissingKind::Note, None),
+            ]
+        );
+        assert_eq!(
+            broken[0].suggestion.as_deref(),
+            Some(
+                root.join("Work/Meeting Notes.md")
+                    .to_string_lossy()
+                    .as_ref()
+            )
+        );
+        assert!(broken.iter().all(|b| b.source.ends_with("Index.md")));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn missing_targets_are_classified() {
+        let link = |target: &str, kind: LinkKind, embed: bool| {
+            let mut link = parse_links("[[x]]").remove(0);
+            link.target = target.to_string();
+            link.kind = kind;
+            link.embed = embed;
+            classify(&link)
+        };
+        assert_eq!(link("Note", LinkKind::Wiki, false), MissingKind::Note);
+        assert_eq!(
+            link("Note.md", LinkKind::Markdown, false),
+            MissingKind::Note
+        );
+        assert_eq!(link("Note", LinkKind::Markdown, false), MissingKind::Note);
+        assert_eq!(link("a.PNG", LinkKind::Wiki, true), MissingKind::Attachment);
+        assert_eq!(link("a.png", LinkKind::Markdown, false), MissingKind::File);
+        assert_eq!(link("a.pdf", LinkKind::Wiki, true), MissingKind::File);
+    }
+}
@@ -0,0 +1,251 @@
+use std::ops::Range;
+
+/// A line of note content outside frontmatter and fenced code blocks
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    /// 1-based line number
+    pub number: usize,
+    /// Byte offset of the line start in the note
+    pub start: usize,
+    /// Line text without the line terminator
+    pub text: &'a str,
+}
+
+/// Byte range of the frontmatter block, including both `---` (or `+++`) fences
+pub fn frontmatter_range(content: &str) -> Option<Range<usize>> {
+    let fence = if content.starts_with("---") {
+        "---"
+    } else if content.starts_with("+++") {
+        "+++"
+    } else {
+        return None;
+    };
+
+    let mut lines = split_lines(content);
+    let (_, first) = lines.next()?;
+    if first.trim_end() != fence {
+        return None;
+    }
+
+    for (start, line) in lines {
+        let trimmed = line.trim_end();
+        if trimmed == fence || (fence == "---" && trimmed == "...") {
+            let mut end = start + line.len();
+            // Include the line terminator of the closing fence
+            if content[end..].starts_with("\r\n") {
+                end += 2;
+            } else if content[end..].starts_with('\n') {
+                end += 1;
+            }
+            return Some(0..end);
+        }
+    }
+    None
+}
+
+/// Iterate every line with its byte offset, without line terminators
+pub fn split_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+    content.split_inclusive('\n').map(move |raw| {
+        let start = offset;
+        offset += raw.len();
+        let text = raw.strip_suffix('\n').unwrap_or(raw);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        (start, text)
+    })
+}
+
+/// Lines that hold markdown prose, skipping frontmatter and fenced code blocks
+pub fn prose_lines(content: &str) -> Vec<Line<'_>> {
+    let body_start = frontmatter_range(content).map(|r| r.end).unwrap_or(0);
+    let mut fence: Option<(char, usize)> = None;
+    let mut lines = Vec::new();
+
+    for (index, (start, text)) in split_lines(content).enumerate() {
+        if start < body_start {
+            continue;
+        }
+
+        if let Some(marker) = fence_marker(text) {
+            match fence {
+                None => {
+                    fence = Some(marker);
+                    continue;
+                }
+                Some((ch, len)) if marker.0 == ch && marker.1 >= len && is_closing_fence(text) => {
+                    fence = None;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        if fence.is_some() {
+            continue;
+        }
+
+        lines.push(Line {
+            number: index + 1,
+            start,
+            text,
+        });
+    }
+
+    lines
+}
+
+/// Fence character and run length if the line opens or closes a fenced code block
+fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let ch = trimmed.chars().next()?;
+    if ch != '`' && ch != '~' {
+        return None;
+    }
+    let run = trimmed.chars().take_while(|c| *c == ch).count();
+    if run < 3 {
+        return None;
+    }
+    // A backtick fence's info string may not contain backticks
+    if ch == '`' && trimmed[run..].contains('`') {
+        return None;
+    }
+    Some((ch, run))
+}
+
+/// Closing fences carry no info string
+fn is_closing_fence(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.chars().all(|c| c == '`' || c == '~')
+}
+
+/// Byte ranges of inline code spans within a line, including the backticks
+pub fn code_spans(text: &str) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+
+        let open_start = i;
+        while i < bytes.len() && bytes[i] == b'`' {
+            i += 1;
+        }
+        let run = i - open_start;
+
+        // Find a closing run of exactly the same length
+        let mut j = i;
+        let mut closed = false;
+        while j < bytes.len() {
+            if bytes[j] == b'`' {
+                let close_start = j;
+                while j < bytes.len() && bytes[j] == b'`' {
+                    j += 1;
+                }
+                if j - close_start == run {
+                    spans.push(open_start..j);
+                    i = j;
+                    closed = true;
+                    break;
+                }
+            } else {
+                j += 1;
+            }
+        }
+        if !closed {
+            // An unmatched run is literal text
+            i = open_start + run;
+        }
+    }
+
+    spans
+}
+
+/// Whether a byte offset falls inside any of the given ranges
+pub fn in_ranges(ranges: &[Range<usize>], offset: usize) -> bool {
+    ranges.iter().any(|r| r.contains(&offset))
+}
+
+/// 1-based character column of a byte offset within a line
+pub fn column_of(text: &str, offset: usize) -> usize {
+    text[..offset].chars().count() + 1
+}
+
+/// Trimmed line text used as a context snippet, clipped around the given range
+pub fn snippet(text: &str, range: Range<usize>, max_chars: usize) -> String {
+    let trimmed = text.trim();
+    if trimmed.chars().count() <= max_chars {
+        return trimmed.to_string();
+    }
+
+    // Center the window on the range, in characters
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let focus = chars
+        .iter()
+        .position(|(i, _)| *i >= range.start)
+        .unwrap_or(0);
+    let half = max_chars / 2;
+    let begin = focus.saturating_sub(half);
+    let end = (begin + max_chars).min(chars.len());
+    let begin = end.saturating_sub(max_chars);
+
+    let mut out = String::new();
+    if begin > 0 {
+        out.push('…');
+    }
+    out.extend(chars[begin..end].iter().map(|(_, c)| *c));
+    if end < chars.len() {
+        out.push('…');
+    }
+    out.trim().to_string()
+}
+
+/// Decode `%XX` escapes, leaving malformed sequences untouched
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            if let Ok(byte) = u8::from_str_radix(&input[i + 1..i + 3], 16) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(out).unwrap_or_else(|_| input.to_string())
+}
+
+/// Encode characters that would break a markdown link destination
+pub fn percent_encode_path(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for ch in input.chars() {
+        match ch {
+            ' ' => out.push_str("%20"),
+            '(' => out.push_str("%28"),
+            ')' => out.push_str("%29"),
+            '<' => out.push_str("%3C"),
+            '>' => out.push_str("%3E"),
+            '#' => out.push_str("%23"),
+            '%' => out.push_str("%25"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use super::links::parse_links;
+use super::markdown::{code_spans, column_of, in_ranges, prose_lines, snippet};
+use super::rewrite::{apply_edits, RewrittenNote, TextEdit};
+use super::VaultIndex;
+
+/// Longest context snippet stored per mention, in characters
+const SNIPPET_CHARS: usize = 160;
+
+/// Plain-text occurrence of a note's title or alias in another note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mention {
+    /// Note containing the mention
+    pub source: String,
+    /// Matched text, as written
+    pub text: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based character column of the match
+    pub column: usize,
+    /// Trimmed text of the line the mention is on
+    pub context: String,
+    /// Byte range of the match in the note
+    #[serde(skip)]
+    pub span: Range<usize>,
+}
+
+impl VaultIndex {
+    /// Unlinked mentions of `target` across the vault
+    ///
+    /// Matches the note's file name (without `.md`) and its aliases as whole words,
+    /// ignoring case, outside links and code. Notes are read from disk so the
+    /// reported positions match what is there now.
+    pub fn unlinked_mentions(&self, target: &Path) -> Vec<Mention> {
+        let terms = self.mention_terms(target);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut sources: Vec<&_> = self.notes.keys().filter(|p| *p != target).collect();
+        sources.sort();
+
+        let mut mentions = Vec::new();
+        for source in sources {
+            let content = match fs::read_to_string(source) {
+                Ok(content) => content,
+                Err(e) => {
+                    log::warn!("Skipping mentions in '{}': {}", source.display(), e);
+                    continue;
+                }
+            };
+            mentions.extend(find_mentions(&self.shown(source), &content, &terms));
+        }
+        mentions
+    }
+
+    /// Turn the selected mentions in one note into wiki links to `target`
+    ///
+    /// Mentions are looked up again in `content`, so ones that moved or were
+    /// linked in the meantime are returned as not found instead of being edited.
+    pub fn link_mentions<'a>(
+        &self,
+        target: &Path,
+        source: &Path,
+        content: &str,
+        selected: &'a [Mention],
+    ) -> (Option<RewrittenNote>, Vec<&'a Mention>) {
+        let current = find_mentions(&self.shown(source), content, &self.mention_terms(target));
+        let name = self.wiki_target(source, target, false, false);
+
+        let mut edits = Vec::new();
+        let mut missing = Vec::new();
+        for mention in selected {
+            let found = current.iter().find(|m| {
+                m.line == mention.line && m.column == mention.column && m.text == mention.text
+            });
+            match (found, &name) {
+                (Some(found), Some(name)) => edits.push(TextEdit {
+                    range: found.span.clone(),
+                    text: wiki_link(name, &found.text),
+                }),
+                _ => missing.push(mention),
+            }
+        }
+
+        if edits.is_empty() {
+            return (None, missing);
+        }
+        let note = RewrittenNote {
+            path: source.to_path_buf(),
+            links: edits.len(),
+            content: apply_edits(content, edits),
+            original: content.to_string(),
+        };
+        (Some(note), missing)
+    }
+
+    /// Title and aliases of a note, longest first so longer names win
+    fn mention_terms(&self, target: &Path) -> Vec<String> {
+        let mut terms: Vec<String> = target
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .into_iter()
+            .chain(
+                self.notes
+                    .get(target)
+                    .map(|entry| entry.aliases.clone())
+                    .unwrap_or_default(),
+            )
+            .map(|term| term.trim().to_string())
+            .filter(|term| !term.is_empty())
+            .collect();
+        terms.sort_by(|a, b| b.chars().count().cmp(&a.chars().count()).then(a.cmp(b)));
+        terms.dedup_by(|a, b| a.to_lowercase() == b.to_lowercase());
+        terms
+    }
+}
+
+/// Whole-word, case-insensitive matches of any term outside links and code
+fn find_mentions(source: &str, content: &str, terms: &[String]) -> Vec<Mention> {
+    let links: Vec<Range<usize>> = parse_links(content)
+        .into_iter()
+        .map(|link| link.span)
+        .collect();
+    let mut mentions = Vec::new();
+
+    for line in prose_lines(content) {
+        let code = code_spans(line.text);
+        let mut i = 0;
+        while i < line.text.len() {
+            let at_boundary = line.text[..i]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !is_word_char(c));
+            let matched = at_boundary && !in_ranges(&code, i) && !in_ranges(&links, line.start + i);
+            let end = matched
+                .then(|| terms.iter().find_map(|term| match_at(line.text, i, term)))
+                .flatten();
+
+            match end {
+                Some(end) if !in_ranges(&links, line.start + end - 1) => {
+                    mentions.push(Mention {
+                        source: source.to_string(),
+                        text: line.text[i..end].to_string(),
+                        line: line.number,
+                        column: column_of(line.text, i),
+                        context: snippet(line.text, i..end, SNIPPET_CHARS),
+                        span: line.start + i..line.start + end,
+                    });
+                    i = end;
+                }
+                _ => i += line.text[i..].chars().next().map_or(1, char::len_utf8),
+            }
+        }
+    }
+
+    mentions
+}
+
+/// End of `term` if it occurs at `start`, ignoring case and followed by a word boundary
+fn match_at(text: &str, start: usize, term: &str) -> Option<usize> {
+    let mut rest = text[start..].char_indices();
+    for expected in term.chars() {
+        let (_, actual) = rest.next()?;
+        if !actual.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    let end = rest.next().map_or(text.len(), |(offset, _)| start + offset);
+    let at_boundary = text[end..].chars().next().is_none_or(|c| !is_word_char(c));
+    at_boundary.then_some(end)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// `[[name]]`, or `[[name|text]]` when the mention is written differently
+fn wiki_link(name: &str, text: &str) -> String {
+    if name == text {
+        format!("[[{}]]", name)
+    } else {
+        format!("[[{}|{}]]", name, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn texts(content: &str, terms: &[&str]) -> Vec<(String, usize, usize)> {
+        let terms: Vec<String> = terms.iter().map(|term| term.to_string()).collect();
+        find_mentions("Source.md", content, &terms)
+            .into_iter()
+            .map(|mention| (mention.text, mention.line, mention.column))
+            .collect()
+    }
+
+    #[test]
+    fn whole_words_in_any_case() {
+        assert_eq!(
+            texts(
+                "project plan, Project Plans and myProject Plan\nthe PROJECT PLAN_ or PROJECT PLAN.",
+                &["Project Plan"]
+            ),
+            [
+                ("project plan".to_string(), 1, 1),
+                ("PROJECT PLAN".to_string(), 2, 22),
+            ]
+        );
+    }
+
+    #[test]
+    fn linked_and_code_text_is_skipped() {
+        let content = "---\ntitle: Plan\n---\n\
+            [[Plan]] and [the Plan](Plan.md) and ![[Plan]]\n\
+            `Plan` but Plan\n\
+            ```\nPlan\n```\n";
+        assert_eq!(texts(content, &["Plan"]), [("Plan".to_string(), 5, 12)]);
+    }
+
+    #[test]
+    fn multi_byte_titles() {
+        // Columns count characters, spans count bytes
+        let content = "Ein Überblick: überblick, Überblicke, ÜBERBLICK";
+        let terms = ["Überblick".to_string()];
+        let mentions = find_mentions("Source.md", content, &terms);
+        let found: Vec<(&str, usize)> = mentions
+            .iter()
+            .map(|mention| (&content[mention.span.clone()], mention.column))
+            .collect();
+        assert_eq!(
+            found,
+            [("Überblick", 5), ("überblick", 16), ("ÜBERBLICK", 39)]
+        );
+    }
+
+    fn vault(name: &str, notes: &[(&str, &str)]) -> (PathBuf, VaultIndex) {
+        let dir =
+            std::env::temp_dir().join(format!("yana-mentions-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (path, content) in notes {
+            fs::write(dir.join(path), content).unwrap();
+        }
+        let dir = fs::canonicalize(dir).unwrap();
+        let mut index = VaultIndex::default();
+        index.rebuild(&dir);
+        (dir, index)
+    }
+
+    #[test]
+    fn linking_mentions_checks_they_are_still_there() {
+        let content = "See the plan and the road map.\n";
+        let (dir, index) = vault(
+            "link",
+            &[
+                ("Plan.md", "---\naliases: [Road Map]\n---\n"),
+                ("Other.md", content),
+            ],
+        );
+        let (target, source) = (dir.join("Plan.md"), dir.join("Other.md"));
+        let mentions = index.unlinked_mentions(&target);
+        assert_eq!(mentions.len(), 2);
+
+        let (note, missing) = index.link_mentions(&target, &source, content, &mentions);
+        assert!(missing.is_empty());
+        let note = note.unwrap();
+        assert_eq!(note.links, 2);
+        assert_eq!(
+            note.content,
+            "See the [[Plan|plan]] and the [[Plan|road map]].\n"
+        );
+
+        // The alias was linked by hand since the scan
+        let edited = "See the plan and the [[Plan|road map]].\n";
+        let (note, missing) = index.link_mentions(&target, &source, edited, &mentions);
+        assert_eq!(
+            note.unwrap().content,
+            "See the [[Plan|plan]] and the [[Plan|road map]].\n"
+        );
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].text, "road map");
+
+        // A line added above moves both
+        let edited = "Intro\nSee the plan and the road map.\n";
+        let (note, missing) = index.link_mentions(&target, &source, edited, &mentions);
+        assert!(note.is_none());
+        assert_eq!(missing.len(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,543 @@
+pub mod attachments;
+pub mod blocks;
+pub mod convert;
+pub mod embeds;
+pub mod frontmatter;
+pub mod graph;
+pub mod heading_links;
+pub mod links;
+pub mod lint;
+pub mod markdown;
+pub mod mentions;
+pub mod outline;
+pub mod preview;
+pub mod properties;
+pub mod query;
+pub mod rewrite;
+pub mod sections;
+pub mod tags;
+pub mod tasks;
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use blocks::{parse_block_ids, BlockId};
+use frontmatter::{parse_frontmatter, Properties};
+use links::{parse_html_refs, parse_links, Link, LinkKind};
+use sections::{parse_headings, Heading};
+use tags::{parse_frontmatter_tags, parse_inline_tags, TagRef};
+use tasks::{parse_tasks, Task};
+
+/// Everything the index knows about a single note
+#[derive(Debug, Clone, Default)]
+pub struct NoteEntry {
+    pub links: Vec<Link>,
+    /// Targets of HTML `<img src>` and `<a href>` tags, resolved like markdown links
+    pub html_refs: Vec<String>,
+    /// Frontmatter tags, then inline tags
+    pub tags: Vec<TagRef>,
+    /// Frontmatter properties, in the order they are written
+    pub properties: Properties,
+    /// Alternative names from the `aliases` frontmatter property
+    pub aliases: Vec<String>,
+    /// `^block-id` markers, for block references
+    pub blocks: Vec<BlockId>,
+    /// ATX headings in document order
+    pub headings: Vec<Heading>,
+    /// Checkbox list items in document order
+    pub tasks: Vec<Task>,
+}
+
+impl NoteEntry {
+    /// Analyze the content of a note
+    pub fn analyze(content: &str) -> Self {
+        let links = parse_links(content);
+        let properties = parse_frontmatter(content).unwrap_or_default();
+        let mut tags = parse_frontmatter_tags(content, &properties);
+        tags.extend(parse_inline_tags(content, &links));
+        NoteEntry {
+            html_refs: parse_html_refs(content),
+            links,
+            tags,
+            aliases: frontmatter::aliases(&properties),
+            properties,
+            blocks: parse_block_ids(content),
+            headings: parse_headings(content),
+            tasks: parse_tasks(content),
+        }
+    }
+}
+
+/// In-memory index of the open vault
+///
+/// Holds every visible file of the vault and the parsed contents of its notes.
+/// Paths are stored under the canonical vault root, the same form the sandbox
+/// resolves command arguments to. The index is rebuilt when another vault is
+/// scanned and kept current by the watcher and the file commands.
+#[derive(Debug, Default)]
+pub struct VaultIndex {
+    root: Option<PathBuf>,
+    /// The root as the frontend names it, for the paths in results
+    shown_root: Option<PathBuf>,
+    notes: HashMap<PathBuf, NoteEntry>,
+    /// Every visible file in the vault, notes included
+    files: BTreeSet<PathBuf>,
+    /// Lowercase file name to the files carrying it, for wiki link lookups
+    by_name: HashMap<String, Vec<PathBuf>>,
+    /// Headings renamed by the editor, as (text links use, current text) per note
+    renamed_headings: HashMap<PathBuf, Vec<(String, String)>>,
+}
+
+impl VaultIndex {
+    /// Canonical root of the indexed vault
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Report paths under `root` rather than the canonical root from now on
+    pub fn set_shown_root(&mut self, root: PathBuf) {
+        self.shown_root = Some(root);
+    }
+
+    /// A path of the vault as the frontend names it, for results
+    ///
+    /// Paths are stored canonical, which on Windows are `\\?\` paths and
+    /// elsewhere skip symlinks in the vault path; results use the vault path
+    /// the file tree was built from so the frontend can compare them.
+    pub fn shown(&self, path: &Path) -> String {
+        let shown = match (&self.root, &self.shown_root) {
+            (Some(root), Some(shown_root)) => match path.strip_prefix(root) {
+                Ok(relative) if relative.as_os_str().is_empty() => shown_root.clone(),
+                Ok(relative) => shown_root.join(relative),
+                Err(_) => path.to_path_buf(),
+            },
+            _ => path.to_path_buf(),
+        };
+        shown.to_string_lossy().to_string()
+    }
+
+    /// Index a vault from scratch
+    pub fn rebuild(&mut self, root: &Path) {
+        let start = std::time::Instant::now();
+
+        self.root = Some(root.to_path_buf());
+        self.shown_root = None;
+        self.notes.clear();
+        self.files.clear();
+        self.by_name.clear();
+        self.renamed_headings.clear();
+        self.add_tree(root);
+
+        log::info!(
+            "Indexed vault '{}': {} note(s), {} file(s) in {:.2}ms",
+            root.display(),
+            self.notes.len(),
+            self.files.len(),
+            start.elapsed().as_secs_f64() * 1000.0
+        );
+    }
+
+    /// Bring the index in line with whatever is now on disk at a path
+    ///
+    /// Handles created, modified, deleted and moved files and folders alike, so
+    /// callers don't need to know what happened to the path.
+    pub fn refresh_path(&mut self, path: &Path) {
+        if !self.is_indexable(path) {
+            return;
+        }
+
+        match fs::symlink_metadata(path) {
+            Ok(meta) if meta.is_dir() => self.add_tree(path),
+            Ok(_) if path.is_file() => self.add_file(path),
+            _ => self.remove_tree(path),
+        }
+    }
+
+    /// Re-analyze a note from content that was just written
+    pub fn update_note(&mut self, path: &Path, content: &str) {
+        if !self.is_indexable(path) || !is_note(path) {
+            return;
+        }
+        self.insert_file(path);
+        self.notes
+            .insert(path.to_path_buf(), NoteEntry::analyze(content));
+    }
+
+    /// Entry of a single note
+    pub fn note(&self, path: &Path) -> Option<&NoteEntry> {
+        self.notes.get(path)
+    }
+
+    /// Paths of every indexed note, in no particular order
+    pub fn note_paths(&self) -> impl Iterator<Item = &Path> {
+        self.notes.keys().map(|path| path.as_path())
+    }
+
+    /// Resolve a link written in `source` to the file it points at
+    ///
+    /// Markdown links are relative to the note (or to the vault root when they
+    /// start with `/`), with `.md` implied when the target has no extension. Wiki
+    /// links name a file anywhere in the vault, by file name or by the end of its
+    /// path, again with `.md` implied. An empty target is the note itself.
+    pub fn resolve(&self, source: &Path, link: &Link) -> Option<PathBuf> {
+        self.resolve_target(source, link.kind, &link.target)
+    }
+
+    /// Resolve a bare link target; see [`VaultIndex::resolve`]
+    pub fn resolve_target(&self, source: &Path, kind: LinkKind, target: &str) -> Option<PathBuf> {
+        let root = self.root.as_deref()?;
+        let target = target.trim();
+        if target.is_empty() {
+            return Some(source.to_path_buf());
+        }
+
+        match kind {
+            LinkKind::Markdown => {
+                let base = match target.strip_prefix('/') {
+                    Some(rooted) => root.join(rooted),
+                    None => source.parent()?.join(target),
+                };
+                let path = normalize_path(&base);
+                if !path.starts_with(root) {
+                    return None;
+                }
+                if self.files.contains(&path) {
+                    return Some(path);
+                }
+                let with_md = append_extension(&path, "md");
+                self.files.contains(&with_md).then_some(with_md)
+            }
+            LinkKind::Wiki => {
+                let target = target.trim_matches('/');
+                let name = target.rsplit('/').next()?.to_lowercase();
+                let suffix: Vec<String> =
+                    target.split('/').map(|part| part.to_lowercase()).collect();
+
+                // An exact file name wins over the same name with `.md` appended
+                for key in [name.clone(), format!("{}.md", name)] {
+                    let Some(candidates) = self.by_name.get(&key) else {
+                        continue;
+                    };
+                    let matching = candidates
+                        .iter()
+                        .filter(|candidate| ends_with_parts(candidate, root, &suffix, &key));
+                    if let Some(best) = closest(source, matching) {
+                        return Some(best.clone());
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// Links in other notes (and self-references) that resolve to `target`
+    pub fn backlinks(&self, target: &Path) -> Vec<(&PathBuf, &Link)> {
+        let mut found: Vec<(&PathBuf, &Link)> = self
+            .notes
+            .iter()
+            .flat_map(|(source, entry)| entry.links.iter().map(move |link| (source, link)))
+            .filter(|(source, link)| self.resolve(source, link).as_deref() == Some(target))
+            .collect();
+        found.sort_by(|a, b| {
+            a.0.cmp(b.0)
+                .then(a.1.line.cmp(&b.1.line))
+                .then(a.1.column.cmp(&b.1.column))
+        });
+        found
+    }
+
+    /// Links written in `source`, each with the file it resolves to
+    pub fn outgoing(&self, source: &Path) -> Vec<(&Link, Option<PathBuf>)> {
+        self.notes
+            .get(source)
+            .map(|entry| {
+                entry
+                    .links
+                    .iter()
+                    .map(|link| (link, self.resolve(source, link)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether a path belongs in the index: inside the root and not hidden
+    fn is_indexable(&self, path: &Path) -> bool {
+        let Some(root) = self.root.as_deref() else {
+            return false;
+        };
+        match path.strip_prefix(root) {
+            Ok(relative) => !relative
+                .components()
+                .any(|c| c.as_os_str().to_string_lossy().starts_with('.')),
+            Err(_) => false,
+        }
+    }
+
+    fn add_tree(&mut self, dir: &Path) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("Failed to index directory '{}': {}", dir.display(), e);
+                return;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // Symlinked folders are not followed, they could loop back into the vault
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => self.add_tree(&path),
+                Ok(kind) if kind.is_symlink() && path.is_dir() => {}
+                Ok(_) if path.is_file() => self.add_file(&path),
+                _ => {}
+            }
+        }
+    }
+
+    fn add_file(&mut self, path: &Path) {
+        self.insert_file(path);
+        if !is_note(path) {
+            return;
+        }
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                self.notes
+                    .insert(path.to_path_buf(), NoteEntry::analyze(&content));
+            }
+            Err(e) => {
+                log::warn!("Failed to index note '{}': {}", path.display(), e);
+                self.notes.insert(path.to_path_buf(), NoteEntry::default());
+            }
+        }
+    }
+
+    fn insert_file(&mut self, path: &Path) {
+        if self.files.insert(path.to_path_buf()) {
+            if let Some(key) = name_key(path) {
+                self.by_name
+                    .entry(key)
+                    .or_default()
+                    .push(path.to_path_buf());
+            }
+        }
+    }
+
+    fn remove_tree(&mut self, path: &Path) {
+        let removed: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|file| file.starts_with(path))
+            .cloned()
+            .collect();
+
+        for file in removed {
+            self.files.remove(&file);
+            self.notes.remove(&file);
+            self.renamed_headings.remove(&file);
+            if let Some(key) = name_key(&file) {
+                if let Some(paths) = self.by_name.get_mut(&key) {
+                    paths.retain(|p| p != &file);
+                    if paths.is_empty() {
+                        self.by_name.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether a file is a markdown note
+pub fn is_note(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("md"))
+        .unwrap_or(false)
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut raw = path.as_os_str().to_owned();
+    raw.push(".");
+    raw.push(extension);
+    PathBuf::from(raw)
+}
+
+fn name_key(path: &Path) -> Option<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+}
+
+/// Whether the vault-relative path of `candidate` ends with the given parts, the
+/// last of which is compared against the candidate's file name `key`
+fn ends_with_parts(candidate: &Path, root: &Path, parts: &[String], key: &str) -> bool {
+    let Ok(relative) = candidate.strip_prefix(root) else {
+        return false;
+    };
+    let folders: Vec<String> = relative
+        .parent()
+        .map(|parent| {
+            parent
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let wanted = &parts[..parts.len().saturating_sub(1)];
+    name_key(candidate).as_deref() == Some(key) && folders.ends_with(wanted)
+}
+
+/// Pick the candidate closest to the linking note: same folder first, then the
+/// shallowest path, then alphabetical
+fn closest<'a>(
+    source: &Path,
+    candidates: impl Iterator<Item = &'a PathBuf>,
+) -> Option<&'a PathBuf> {
+    let folder = source.parent();
+    candidates.min_by(|a, b| {
+        let a_away = a.parent() != folder;
+        let b_away = b.parent() != folder;
+        a_away
+            .cmp(&b_away)
+            .then(a.components().count().cmp(&b.components().count()))
+            .then(a.cmp(b))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault(name: &str, files: &[(&str, &str)]) -> (PathBuf, VaultIndex) {
+        let dir = std::env::temp_dir().join(format!("yana-index-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let dir = fs::canonicalize(dir).unwrap();
+        let mut index = VaultIndex::default();
+        index.rebuild(&dir);
+        (dir, index)
+    }
+
+    #[test]
+    fn wiki_links_resolve_by_name_and_path() {
+        let (root, index) = vault(
+            "wiki",
+            &[
+                ("Plan.md", ""),
+                ("Work/Plan.md", ""),
+                ("Work/Deep/Plan.md", ""),
+                ("Work/Source.md", ""),
+                ("Other/Source.md", ""),
+                ("Image.png", ""),
+                ("Image.png.md", ""),
+            ],
+        );
+        let resolve = |source: &str, target: &str| {
+            index
+                .resolve_target(&root.join(source), LinkKind::Wiki, target)
+                .map(|path| path.strip_prefix(&root).unwrap().to_path_buf())
+        };
+
+        // A note in the same folder wins, then the shallowest one
+        assert_eq!(
+            resolve("Work/Source.md", "Plan"),
+            Some("Work/Plan.md".into())
+        );
+        assert_eq!(resolve("Other/Source.md", "plan"), Some("Plan.md".into()));
+        // The end of a path narrows it down
+        assert_eq!(
+            resolve("Other/Source.md", "Deep/Plan"),
+            Some("Work/Deep/Plan.md".into())
+        );
+        assert_eq!(resolve("Other/Source.md", "Missing/Plan"), None);
+        // An exact file name wins over the same name as a note
+        assert_eq!(resolve("Plan.md", "Image.png"), Some("Image.png".into()));
+        assert_eq!(resolve("Plan.md", ""), Some("Plan.md".into()));
+        assert_eq!(resolve("Plan.md", "Nothing"), None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn markdown_links_resolve_relative_to_the_note() {
+        let (root, index) = vault(
+            "markdown",
+            &[("Work/Plan.md", ""), ("Top.md", ""), ("Work/img.png", "")],
+        );
+        let source = root.join("Work/Source.md");
+        let resolve = |target: &str| index.resolve_target(&source, LinkKind::Markdown, target);
+
+        assert_eq!(resolve("Plan.md"), Some(root.join("Work/Plan.md")));
+        assert_eq!(resolve("./Plan"), Some(root.join("Work/Plan.md")));
+        assert_eq!(resolve("../Top.md"), Some(root.join("Top.md")));
+        assert_eq!(resolve("/Work/img.png"), Some(root.join("Work/img.png")));
+        assert_eq!(resolve("../../Outside.md"), None);
+        assert_eq!(resolve("Missing.md"), None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn backlinks_follow_changes() {
+        let (root, mut index) = vault(
+            "backlinks",
+            &[
+                ("Target.md", "# Target\n"),
+                ("A.md", "See [[Target#Target|it]]\nand [again](Target.md)\n"),
+                ("B.md", "`[[Target]]` in code\n"),
+            ],
+        );
+        let target = root.join("Target.md");
+        let sources = |index: &VaultIndex| -> Vec<(String, usize)> {
+            index
+                .backlinks(&target)
+                .into_iter()
+                .map(|(source, link)| {
+                    let name = source.file_name().unwrap().to_string_lossy().to_string();
+                    (name, link.line)
+                })
+                .collect()
+        };
+        assert_eq!(sources(&index), [("A.md".into(), 1), ("A.md".into(), 2)]);
+
+        let outgoing = index.outgoing(&root.join("A.md"));
+        assert_eq!(outgoing.len(), 2);
+        assert_eq!(outgoing[0].0.context, "See [[Target#Target|it]]");
+        assert_eq!(outgoing[0].1.as_deref(), Some(target.as_path()));
+
+        // Edits seen by the watcher or written by the app update the index
+        fs::write(root.join("B.md"), "[[Target]]\n").unwrap();
+        index.refresh_path(&root.join("B.md"));
+        index.update_note(&root.join("A.md"), "No links\n");
+        assert_eq!(sources(&index), [("B.md".into(), 1)]);
+
+        fs::remove_file(&target).unwrap();
+        index.refresh_path(&target);
+        assert!(sources(&index).is_empty());
+        assert_eq!(index.outgoing(&root.join("B.md"))[0].1, None);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,243 @@
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+use super::markdown::frontmatter_range;
+
+/// Words read per minute for the reading time estimate
+const WORDS_PER_MINUTE: usize = 200;
+
+/// A heading and the headings nested below it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineHeading {
+    /// Heading text without markup
+    pub text: String,
+    /// 1 to 6
+    pub level: u8,
+    /// 1-based line of the heading
+    pub line: usize,
+    /// Last line of the section, before the next heading of the same or a higher level
+    pub end_line: usize,
+    pub children: Vec<OutlineHeading>,
+}
+
+/// Counts over the prose of a note, leaving out frontmatter, headings and code
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteStats {
+    pub words: usize,
+    /// Characters of text, spaces included but markup left out
+    pub characters: usize,
+    /// Markdown, wiki and autolinks; embeds are not counted
+    pub links: usize,
+    pub tasks: usize,
+    pub completed_tasks: usize,
+    /// Estimated reading time in whole minutes, rounded up
+    pub reading_minutes: usize,
+}
+
+/// Heading tree and statistics of a note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteOutline {
+    pub headings: Vec<OutlineHeading>,
+    #[serde(flatten)]
+    pub stats: NoteStats,
+}
+
+/// Parse a note as CommonMark, with tables, task lists and wiki links
+pub fn note_outline(content: &str) -> NoteOutline {
+    let body_start = frontmatter_range(content).map_or(0, |range| range.end);
+    let body = &content[body_start..];
+    let line_of = |offset: usize| {
+        content[..body_start + offset]
+            .bytes()
+            .filter(|b| *b == b'\n')
+            .count()
+            + 1
+    };
+
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_WIKILINKS;
+
+    let mut stats = NoteStats::default();
+    let mut text = String::new();
+    // Headings in document order as (level, line, text)
+    let mut headings: Vec<(u8, usize, String)> = Vec::new();
+    let mut heading: Option<(u8, usize, String)> = None;
+    let mut in_code = false;
+    let mut in_image = 0usize;
+
+    for (event, range) in Parser::new_ext(body, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading = Some((heading_level(level), line_of(range.start), String::new()));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, line, title)) = heading.take() {
+                    headings.push((level, line, title.trim().to_string()));
+                }
+            }
+            Event::Start(Tag::CodeBlock(_)) => in_code = true,
+            Event::End(TagEnd::CodeBlock) => in_code = false,
+            Event::Start(Tag::Image { .. }) => in_image += 1,
+            Event::End(TagEnd::Image) => in_image = in_image.saturating_sub(1),
+            Event::Start(Tag::Link { .. }) if in_image == 0 => stats.links += 1,
+            Event::TaskListMarker(checked) => {
+                stats.tasks += 1;
+                if checked {
+                    stats.completed_tasks += 1;
+                }
+            }
+            // Headings keep their inline code in the outline but count as neither
+            Event::Text(fragment) | Event::Code(fragment) if heading.is_some() => {
+                if let Some((_, _, title)) = heading.as_mut() {
+                    title.push_str(&fragment);
+                }
+            }
+            Event::Text(fragment) if !in_code && in_image == 0 => text.push_str(&fragment),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::End(
+                TagEnd::Paragraph | TagEnd::Item | TagEnd::TableCell | TagEnd::BlockQuote(_),
+            ) => text.push('\n'),
+            _ => {}
+        }
+    }
+
+    stats.words = text.split_whitespace().count();
+    stats.characters = text.lines().map(|line| line.chars().count()).sum();
+    stats.reading_minutes = stats.words.div_ceil(WORDS_PER_MINUTE);
+
+    let last_line = content.lines().count().max(1);
+    NoteOutline {
+        headings: heading_tree(&headings, last_line),
+        stats,
+    }
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// Nest headings below the closest preceding heading of a lower level
+fn heading_tree(headings: &[(u8, usize, String)], last_line: usize) -> Vec<OutlineHeading> {
+    let mut roots: Vec<OutlineHeading> = Vec::new();
+    // Path from a root to the heading most recently added
+    let mut open: Vec<OutlineHeading> = Vec::new();
+
+    for (index, (level, line, text)) in headings.iter().enumerate() {
+        let end_line = headings[index + 1..]
+            .iter()
+            .find(|(next, _, _)| next <= level)
+            .map_or(last_line, |(_, next_line, _)| next_line - 1);
+
+        while open.last().is_some_and(|parent| parent.level >= *level) {
+            close(&mut open, &mut roots);
+        }
+        open.push(OutlineHeading {
+            text: text.clone(),
+            level: *level,
+            line: *line,
+            end_line,
+            children: Vec::new(),
+        });
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut roots);
+    }
+    roots
+}
+
+fn close(open: &mut Vec<OutlineHeading>, roots: &mut Vec<OutlineHeading>) {
+    if let Some(heading) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.children.push(heading),
+            None => roots.push(heading),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_leave_out_headings_code_and_frontmatter() {
+        let content = "---\ntitle: Not counted\n---\n# The `main` heading\n\nOne two `three` four.\nFive\n\n```\nlet code = 1;\n```\n\n- [x] done item\n- [ ] open item\n";
+        let outline = note_outline(content);
+
+        assert_eq!(outline.stats.words, 8);
+        assert_eq!(
+            outline.stats.characters,
+            "One two  four. Five".len() + "done item".len() + "open item".len()
+        );
+        assert_eq!(outline.stats.tasks, 2);
+        assert_eq!(outline.stats.completed_tasks, 1);
+        assert_eq!(outline.stats.reading_minutes, 1);
+        assert_eq!(outline.headings[0].text, "The main heading");
+    }
+
+    #[test]
+    fn links_but_not_embeds_are_counted() {
+        let outline = note_outline(
+            "See [[Other]], [site](https://example.com) and <https://a.b>.\n![img](a.png) ![[Embed]]\n",
+        );
+        assert_eq!(outline.stats.links, 3);
+        // Link text is prose, image alt text is not
+        assert_eq!(outline.stats.words, 5);
+    }
+
+    #[test]
+    fn reading_time_rounds_up() {
+        assert_eq!(note_outline("").stats.reading_minutes, 0);
+        let words = "word ".repeat(WORDS_PER_MINUTE + 1);
+        assert_eq!(note_outline(&words).stats.reading_minutes, 2);
+    }
+
+    #[test]
+    fn heading_tree_with_line_ranges() {
+        let content = "intro\n# A\ntext\n## A1\n### A1a\n## A2\n# B\n#### B deep\nend\n";
+        let outline = note_outline(content);
+
+        let summary = |heading: &OutlineHeading| {
+            (
+                heading.text.clone(),
+                heading.level,
+                heading.line,
+                heading.end_line,
+            )
+        };
+        assert_eq!(outline.headings.len(), 2);
+        let a = &outline.headings[0];
+        assert_eq!(summary(a), ("A".to_string(), 1, 2, 6));
+        assert_eq!(
+            a.children.iter().map(summary).collect::<Vec<_>>(),
+            [("A1".to_string(), 2, 4, 5), ("A2".to_string(), 2, 6, 6)]
+        );
+        assert_eq!(
+            summary(&a.children[0].children[0]),
+            ("A1a".to_string(), 3, 5, 5)
+        );
+
+        // A skipped level still nests below the closest heading above it
+        let b = &outline.headings[1];
+        assert_eq!(summary(b), ("B".to_string(), 1, 7, 9));
+        assert_eq!(summary(&b.children[0]), ("B deep".to_string(), 4, 8, 9));
+    }
+
+    #[test]
+    fn lines_count_from_the_top_of_the_file() {
+        let outline = note_outline("---\na: 1\n---\n# Title\n");
+        assert_eq!(outline.headings[0].line, 4);
+        assert_eq!(outline.headings[0].end_line, 4);
+    }
+}
@@ -0,0 +1,104 @@
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use super::frontmatter::{parse_frontmatter, property};
+use super::markdown::frontmatter_range;
+
+/// Longest excerpt kept for a note, in characters
+pub const MAX_EXCERPT: usize = 1000;
+
+/// Title and plain text of a note, for hover cards and search results
+#[derive(Debug, Clone)]
+pub struct Preview {
+    /// The `title` property, else the first H1, else the file name
+    pub title: String,
+    /// Text without markup, frontmatter, code blocks or the H1 used as title,
+    /// whitespace collapsed, at most [`MAX_EXCERPT`] characters
+    pub text: String,
+}
+
+/// Build the preview of a note; `name` is its file name without `.md`
+pub fn note_preview(content: &str, name: &str) -> Preview {
+    let mut title = parse_frontmatter(content).and_then(|properties| {
+        let title = property(&properties, "title")?.as_str()?.trim().to_string();
+        (!title.is_empty()).then_some(title)
+    });
+
+    let body_start = frontmatter_range(content).map_or(0, |range| range.end);
+    let options = Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS | Options::ENABLE_WIKILINKS;
+
+    let mut text = String::new();
+    // Characters in `text`, counted as they are added
+    let mut length = 0;
+    // Text of the first H1 while it is being read, when it becomes the title
+    let mut h1: Option<String> = None;
+    let mut in_code = false;
+    let mut in_image = 0usize;
+
+    for event in Parser::new_ext(&content[body_start..], options) {
+        let full = length > 2 * MAX_EXCERPT;
+        if full && title.is_some() {
+            break;
+        }
+        match event {
+            Event::Start(Tag::Heading {
+                level: HeadingLevel::H1,
+                ..
+            }) if title.is_none() => h1 = Some(String::new()),
+            Event::End(TagEnd::Heading(HeadingLevel::H1)) if h1.is_some() => {
+                title = h1.take().map(|h1| h1.trim().to_string());
+            }
+            Event::Start(Tag::CodeBlock(_)) => in_code = true,
+            Event::End(TagEnd::CodeBlock) => in_code = false,
+            Event::Start(Tag::Image { .. }) => in_image += 1,
+            Event::End(TagEnd::Image) => in_image = in_image.saturating_sub(1),
+            Event::Text(fragment) | Event::Code(fragment) if !in_code && in_image == 0 => {
+                match h1.as_mut() {
+                    Some(h1) => h1.push_str(&fragment),
+                    None if !full => {
+                        text.push_str(&fragment);
+                        length += fragment.chars().count();
+                    }
+                    None => {}
+                }
+            }
+            // Breaks and block ends separate words
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => match h1.as_mut() {
+                Some(h1) => h1.push(' '),
+                None => {
+                    text.push(' ');
+                    length += 1;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    let title = title
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| name.to_string());
+    let text: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_EXCERPT)
+        .collect();
+    Preview { title, text }
+}
+
+/// The first `length` characters of a preview, cut at a word boundary
+pub fn excerpt(text: &str, length: usize) -> String {
+    if text.chars().count() <= length {
+        return text.to_string();
+    }
+    let cut = text
+        .char_indices()
+        .nth(length)
+        .map_or(text.len(), |(index, _)| index);
+    let head = &text[..cut];
+    let head = match head.rfind(' ') {
+        Some(space) if space > 0 => &head[..space],
+        _ => head,
+    };
+    format!("{}…", head.trim_end())
+}
@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::VaultIndex;
+
+/// Type of a frontmatter property value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyType {
+    Text,
+    Number,
+    Checkbox,
+    /// `YYYY-MM-DD`
+    Date,
+    /// `YYYY-MM-DDTHH:MM`, optionally with seconds and an offset
+    DateTime,
+    List,
+    Object,
+    /// A key without a value
+    Empty,
+}
+
+/// A property key used anywhere in the vault
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertyKey {
+    /// Key as first written, keys differing only in case are merged
+    pub key: String,
+    /// Value types seen for the key, most common first
+    pub types: Vec<PropertyType>,
+    /// Number of notes with the key
+    pub notes: usize,
+}
+
+/// A property of a single note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteProperty {
+    pub key: String,
+    pub value: Value,
+    #[serde(rename = "type")]
+    pub kind: PropertyType,
+}
+
+/// What a property value has to match
+///
+/// With a value, the property must equal it (for lists, contain it). With `min`
+/// and/or `max`, it must lie in the inclusive range. With neither, the note
+/// only needs to have the property.
+#[derive(Debug, Clone, Default)]
+pub struct PropertyFilter {
+    pub value: Option<Value>,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+}
+
+/// A note whose property matched a filter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertyMatch {
+    pub path: String,
+    /// Key as written in the note
+    pub key: String,
+    pub value: Value,
+}
+
+impl VaultIndex {
+    /// Every property key in the vault with its value types, ordered by key
+    pub fn property_keys(&self) -> Vec<PropertyKey> {
+        let mut paths: Vec<&Path> = self.notes.keys().map(|p| p.as_path()).collect();
+        paths.sort();
+
+        // Lowercase key to (spelling, notes, count per type)
+        let mut keys: BTreeMap<String, (String, usize, BTreeMap<PropertyType, usize>)> =
+            BTreeMap::new();
+        for path in paths {
+            for (key, value) in &self.notes[path].properties {
+                let (_, notes, types) = keys
+                    .entry(key.to_lowercase())
+                    .or_insert_with(|| (key.clone(), 0, BTreeMap::new()));
+                *notes += 1;
+                *types.entry(property_type(value)).or_default() += 1;
+            }
+        }
+
+        keys.into_values()
+            .map(|(key, notes, types)| {
+                let mut types: Vec<(PropertyType, usize)> = types.into_iter().collect();
+                types.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+                PropertyKey {
+                    key,
+                    types: types.into_iter().map(|(kind, _)| kind).collect(),
+                    notes,
+                }
+            })
+            .collect()
+    }
+
+    /// Properties of a note in the order they are written
+    pub fn note_properties(&self, path: &Path) -> Option<Vec<NoteProperty>> {
+        let entry = self.notes.get(path)?;
+        Some(
+            entry
+                .properties
+                .iter()
+                .map(|(key, value)| NoteProperty {
+                    key: key.clone(),
+                    value: value.clone(),
+                    kind: property_type(value),
+                })
+                .collect(),
+        )
+    }
+
+    /// Notes under `scope` whose property `key` matches a filter, ordered by path
+    ///
+    /// Keys match ignoring case. Text compares ignoring case, numbers numerically
+    /// and dates chronologically; a list matches when any of its items does.
+    pub fn find_by_property(
+        &self,
+        scope: &Path,
+        key: &str,
+        filter: &PropertyFilter,
+    ) -> Vec<PropertyMatch> {
+        let mut matches: Vec<PropertyMatch> = self
+            .notes
+            .iter()
+            .filter(|(path, _)| path.starts_with(scope))
+            .filter_map(|(path, entry)| {
+                let (key, value) = entry
+                    .properties
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(key))?;
+                value_matches(value, filter).then(|| PropertyMatch {
+                    path: self.shown(path),
+                    key: key.clone(),
+                    value: value.clone(),
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| a.path.cmp(&b.path));
+        matches
+    }
+}
+
+/// Type of a property value, telling dates apart from other text
+pub fn property_type(value: &Value) -> PropertyType {
+    match value {
+        Value::Null => PropertyType::Empty,
+        Value::Bool(_) => PropertyType::Checkbox,
+        Value::Number(_) => PropertyType::Number,
+        Value::String(text) if is_date(text) => PropertyType::Date,
+        Value::String(text) if is_date_time(text) => PropertyType::DateTime,
+        Value::String(_) => PropertyType::Text,
+        Value::Array(_) => PropertyType::List,
+        Value::Object(_) => PropertyType::Object,
+    }
+}
+
+fn value_matches(value: &Value, filter: &PropertyFilter) -> bool {
+    if let Value::Array(items) = value {
+        return items.iter().any(|item| value_matches(item, filter));
+    }
+
+    if let Some(wanted) = &filter.value {
+        if compare(value, wanted) != Some(Ordering::Equal) {
+            return false;
+        }
+    }
+    if let Some(min) = &filter.min {
+        if !matches!(
+            compare(value, min),
+            Some(Ordering::Greater | Ordering::Equal)
+        ) {
+            return false;
+        }
+    }
+    if let Some(max) = &filter.max {
+        if !matches!(compare(value, max), Some(Ordering::Less | Ordering::Equal)) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Order two values of the same type; `None` when they can't be compared
+///
+/// Text compares ignoring case, numbers numerically and dates chronologically.
+pub fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => {
+            let dated = |text: &str| is_date(text) || is_date_time(text);
+            if dated(a) && dated(b) {
+                // ISO dates sort chronologically as text; a bare date is the start of its day
+                let a = a.replace(' ', "T");
+                let b = b.replace(' ', "T");
+                Some(a.cmp(&b))
+            } else {
+                Some(a.to_lowercase().cmp(&b.to_lowercase()))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Order any two values, for sorting
+///
+/// Unlike [`compare`] this is a total order: values rank by type first
+/// (booleans, numbers, dates, other text, lists, objects, null), then compare
+/// within the type. Text that differs only in case falls back to a plain
+/// comparison, and lists compare item by item.
+pub fn total_order(a: &Value, b: &Value) -> Ordering {
+    let rank = |value: &Value| match value {
+        Value::Bool(_) => 0,
+        Value::Number(_) => 1,
+        Value::String(text) if is_date(text) || is_date_time(text) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+        Value::Null => 6,
+    };
+    let (a_rank, b_rank) = (rank(a), rank(b));
+    a_rank.cmp(&b_rank).then_with(|| match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => {
+            let number = |n: &serde_json::Number| n.as_f64().unwrap_or(f64::NAN);
+            number(a).total_cmp(&number(b))
+        }
+        // ISO dates sort chronologically as text; a bare date is the start of its day
+        (Value::String(a), Value::String(b)) if a_rank == 2 => {
+            a.replace(' ', "T").cmp(&b.replace(' ', "T"))
+        }
+        (Value::String(a), Value::String(b)) => a
+            .to_lowercase()
+            .cmp(&b.to_lowercase())
+            .then_with(|| a.cmp(b)),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| total_order(a, b))
+            .find(|order| order.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Object(_), Value::Object(_)) => a.to_string().cmp(&b.to_string()),
+        _ => Ordering::Equal,
+    })
+}
+
+/// `YYYY-MM-DD`
+pub fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+/// `YYYY-MM-DDTHH:MM` or `YYYY-MM-DD HH:MM`, followed by anything (seconds, offset)
+pub fn is_date_time(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 16
+        && text.get(..10).is_some_and(is_date)
+        && matches!(bytes[10], b'T' | b' ')
+        && bytes[11..16].iter().enumerate().all(|(i, b)| match i {
+            2 => *b == b':',
+            _ => b.is_ascii_digit(),
+        })
+}
@@ -0,0 +1,1011 @@
+//! A small query language over note metadata
+//!
+//! ```text
+//! TABLE status, due AS "Due date" FROM "Projects" WHERE status = "active" SORT due DESC LIMIT 20
+//! LIST FROM #project/alpha AND -"Archive"
+//! ```
+//!
+//! Every clause is optional and they come in this order:
+//!
+//! - `LIST` (the default) returns matching notes; `TABLE field [AS "label"], ...`
+//!   adds a column per field.
+//! - `FROM` narrows the notes by folder (`"Projects"`) and tag (`#tag`, nested
+//!   tags included), combined with `AND`, `OR`, `NOT` (or `-`) and parentheses.
+//! - `WHERE` filters by an expression: comparisons (`=`, `!=`, `<`, `<=`, `>`,
+//!   `>=`) joined with `AND`, `OR`, `NOT`; a bare `#tag` is true for tagged notes;
+//!   `contains(field, value)` tests lists and text.
+//! - `SORT field [ASC|DESC], ...` orders the rows; missing values go last.
+//! - `LIMIT n` keeps the first rows.
+//!
+//! Fields are frontmatter properties by name (ignoring case), or one of
+//! `file.name`, `file.path`, `file.folder`, `file.tags`, `file.size`,
+//! `file.ctime`, `file.mtime`, `file.inlinks` and `file.outlinks`. Literals are
+//! quoted text, numbers, `true`, `false`, `null` and `today`. Dates are written
+//! as `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM[:SS]`, bare or quoted, so
+//! `due < 2024-01-01` compares dates; file times and `today` are local time.
+//! Keywords ignore case.
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::frontmatter::property;
+use super::properties::{compare, is_date, is_date_time, total_order};
+use super::tags::{is_tag_char, tag_matches};
+use super::{NoteEntry, VaultIndex};
+
+/// Rows a query produced
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+    /// Column labels of a `TABLE` query, empty for `LIST`
+    pub columns: Vec<String>,
+    pub rows: Vec<QueryRow>,
+}
+
+/// One matching note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryRow {
+    pub path: String,
+    /// Value per column, `null` where the note has none
+    pub values: Vec<Value>,
+}
+
+/// A query that could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    /// 1-based character column in the query
+    pub column: usize,
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (column {})", self.message, self.column)
+    }
+}
+
+/// A parsed query
+#[derive(Debug, Clone)]
+pub struct Query {
+    columns: Vec<(String, String)>,
+    from: Option<Expr>,
+    filter: Option<Expr>,
+    sort: Vec<(String, bool)>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Box<Expr>, Op, Box<Expr>),
+    Contains(Box<Expr>, Box<Expr>),
+    Field(String),
+    Literal(Value),
+    Tag(String),
+    Folder(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl VaultIndex {
+    /// Run a query over every note of the vault
+    pub fn query(&self, query: &Query) -> QueryResult {
+        let Some(root) = self.root() else {
+            return QueryResult::default();
+        };
+        let mut context = Context {
+            index: self,
+            root,
+            inlinks: None,
+        };
+
+        let mut paths: Vec<&PathBuf> = self.notes.keys().collect();
+        paths.sort();
+
+        let mut rows: Vec<(&PathBuf, Vec<Value>, Vec<Value>)> = Vec::new();
+        for path in paths {
+            let mut note = Note {
+                path,
+                entry: &self.notes[path],
+                metadata: None,
+            };
+            let included = [&query.from, &query.filter]
+                .into_iter()
+                .flatten()
+                .all(|expr| truthy(&context.eval(expr, &mut note)));
+            if !included {
+                continue;
+            }
+
+            let values = query
+                .columns
+                .iter()
+                .map(|(field, _)| context.field(field, &mut note))
+                .collect();
+            let keys = query
+                .sort
+                .iter()
+                .map(|(field, _)| context.field(field, &mut note))
+                .collect();
+            rows.push((path, values, keys));
+        }
+
+        rows.sort_by(|a, b| {
+            query
+                .sort
+                .iter()
+                .zip(a.2.iter().zip(&b.2))
+                .map(|((_, descending), (a, b))| sort_order(a, b, *descending))
+                .find(|order| order.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        if let Some(limit) = query.limit {
+            rows.truncate(limit);
+        }
+
+        QueryResult {
+            columns: query
+                .columns
+                .iter()
+                .map(|(_, label)| label.clone())
+                .collect(),
+            rows: rows
+                .into_iter()
+                .map(|(path, values, _)| QueryRow {
+                    path: self.shown(path),
+                    values,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Missing values last in either direction, mixed types ordered by type
+fn sort_order(a: &Value, b: &Value, descending: bool) -> Ordering {
+    match (a.is_null(), b.is_null()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        _ => {
+            let order = total_order(a, b);
+            if descending {
+                order.reverse()
+            } else {
+                order
+            }
+        }
+    }
+}
+
+struct Context<'a> {
+    index: &'a VaultIndex,
+    root: &'a Path,
+    /// Links from other notes per note, counted on first use
+    inlinks: Option<HashMap<PathBuf, usize>>,
+}
+
+struct Note<'a> {
+    path: &'a Path,
+    entry: &'a NoteEntry,
+    /// File metadata, read on first use; `None` inside if it can't be read
+    metadata: Option<Option<fs::Metadata>>,
+}
+
+impl Context<'_> {
+    fn eval(&mut self, expr: &Expr, note: &mut Note<'_>) -> Value {
+        match expr {
+            Expr::And(a, b) => {
+                Value::Bool(truthy(&self.eval(a, note)) && truthy(&self.eval(b, note)))
+            }
+            Expr::Or(a, b) => {
+                Value::Bool(truthy(&self.eval(a, note)) || truthy(&self.eval(b, note)))
+            }
+            Expr::Not(a) => Value::Bool(!truthy(&self.eval(a, note))),
+            Expr::Compare(a, op, b) => {
+                let a = self.eval(a, note);
+                let b = self.eval(b, note);
+                Value::Bool(compare_op(&a, *op, &b))
+            }
+            Expr::Contains(a, b) => {
+                let haystack = self.eval(a, note);
+                let needle = self.eval(b, note);
+                Value::Bool(contains(&haystack, &needle))
+            }
+            Expr::Field(name) => self.field(name, note),
+            Expr::Literal(value) => value.clone(),
+            Expr::Tag(tag) => {
+                Value::Bool(note.entry.tags.iter().any(|t| tag_matches(&t.name, tag)))
+            }
+            Expr::Folder(folder) => {
+                let folder = folder.trim_matches('/');
+                Value::Bool(folder.is_empty() || note.path.starts_with(self.root.join(folder)))
+            }
+        }
+    }
+
+    fn field(&mut self, name: &str, note: &mut Note<'_>) -> Value {
+        let relative = note.path.strip_prefix(self.root).unwrap_or(note.path);
+        match name.to_lowercase().as_str() {
+            "file.name" => text(note.path.file_stem()),
+            "file.path" => Value::String(slash_path(relative)),
+            "file.folder" => Value::String(relative.parent().map(slash_path).unwrap_or_default()),
+            "file.tags" => {
+                let mut tags: Vec<String> = Vec::new();
+                for tag in &note.entry.tags {
+                    if !tags.iter().any(|t| t.eq_ignore_ascii_case(&tag.name)) {
+                        tags.push(tag.name.clone());
+                    }
+                }
+                Value::from(tags)
+            }
+            "file.size" => note
+                .metadata()
+                .map_or(Value::Null, |meta| Value::from(meta.len())),
+            "file.ctime" => note
+                .metadata()
+                .and_then(|meta| meta.created().ok())
+                .map_or(Value::Null, |time| Value::String(iso_datetime(time))),
+            "file.mtime" => note
+                .metadata()
+                .and_then(|meta| meta.modified().ok())
+                .map_or(Value::Null, |time| Value::String(iso_datetime(time))),
+            "file.outlinks" => Value::from(note.entry.links.len()),
+            "file.inlinks" => Value::from(self.inlinks(note.path)),
+            _ => property(&note.entry.properties, name)
+                .cloned()
+                .unwrap_or(Value::Null),
+        }
+    }
+
+    fn inlinks(&mut self, path: &Path) -> usize {
+        let index = self.index;
+        let counts = self.inlinks.get_or_insert_with(|| {
+            let mut counts = HashMap::new();
+            for (source, entry) in &index.notes {
+                for link in &entry.links {
+                    match index.resolve(source, link) {
+                        Some(target) if target != *source => {
+                            *counts.entry(target).or_default() += 1
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            counts
+        });
+        counts.get(path).copied().unwrap_or(0)
+    }
+}
+
+impl Note<'_> {
+    fn metadata(&mut self) -> Option<&fs::Metadata> {
+        let path = self.path;
+        self.metadata
+            .get_or_insert_with(|| fs::metadata(path).ok())
+            .as_ref()
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// Comparisons against a list hold when they hold for any item
+fn compare_op(a: &Value, op: Op, b: &Value) -> bool {
+    if let (Value::Array(items), false) = (a, b.is_array() || b.is_null()) {
+        // `!=` holds when no item is equal
+        if op == Op::Ne {
+            return !items.iter().any(|item| compare_op(item, Op::Eq, b));
+        }
+        return items.iter().any(|item| compare_op(item, op, b));
+    }
+
+    let order = compare(a, b);
+    match op {
+        Op::Eq => order == Some(Ordering::Equal),
+        Op::Ne => order != Some(Ordering::Equal),
+        Op::Lt => order == Some(Ordering::Less),
+        Op::Le => matches!(order, Some(Ordering::Less | Ordering::Equal)),
+        Op::Gt => order == Some(Ordering::Greater),
+        Op::Ge => matches!(order, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+fn contains(haystack: &Value, needle: &Value) -> bool {
+    match (haystack, needle) {
+        (Value::Array(items), _) => items.iter().any(|item| compare_op(item, Op::Eq, needle)),
+        (Value::String(text), Value::String(part)) => {
+            text.to_lowercase().contains(&part.to_lowercase())
+        }
+        _ => false,
+    }
+}
+
+fn text(value: Option<&std::ffi::OsStr>) -> Value {
+    value.map_or(Value::Null, |v| {
+        Value::String(v.to_string_lossy().to_string())
+    })
+}
+
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `YYYY-MM-DDTHH:MM:SS` in local time, like dates in periodic note names
+fn iso_datetime(time: SystemTime) -> String {
+    DateTime::<Local>::from(time)
+        .format("%Y-%m-%dT%H:%M:%S")
+        .to_string()
+}
+
+/// Today's date as `YYYY-MM-DD`, in local time
+fn today() -> String {
+    Local::now().date_naive().format("%Y-%m-%d").to_string()
+}
+
+// Parsing
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Number(f64),
+    Tag(String),
+    Op(Op),
+    Open,
+    Close,
+    Comma,
+    Minus,
+}
+
+/// Parse a query; see the module documentation for the syntax
+pub fn parse_query(source: &str) -> Result<Query, QueryError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        end: source.chars().count() + 1,
+    };
+    parser.query()
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        // Bare dates are text, so they compare like quoted ones
+        if let Some(len) = c.is_ascii_digit().then(|| date_len(&chars[i..])).flatten() {
+            tokens.push((Token::Text(chars[i..i + len].iter().collect()), column));
+            i += len;
+            continue;
+        }
+
+        let token = match c {
+            '"' | '\'' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(QueryError {
+                                message: "Unterminated text".to_string(),
+                                column,
+                            })
+                        }
+                        Some('\\') if i + 1 < chars.len() => {
+                            value.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(ch) if *ch == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(ch) => {
+                            value.push(*ch);
+                            i += 1;
+                        }
+                    }
+                }
+                Token::Text(value)
+            }
+            '#' => {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && is_tag_char(chars[i]) {
+                    i += 1;
+                }
+                if i == start {
+                    return Err(QueryError {
+                        message: "Expected a tag name after '#'".to_string(),
+                        column,
+                    });
+                }
+                Token::Tag(chars[start..i].iter().collect::<String>())
+            }
+            '(' | ')' | ',' => {
+                i += 1;
+                match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Comma,
+                }
+            }
+            '=' | '!' | '<' | '>' => {
+                let next = chars.get(i + 1).copied();
+                let (op, len) = match (c, next) {
+                    ('=', Some('=')) => (Op::Eq, 2),
+                    ('=', _) => (Op::Eq, 1),
+                    ('!', Some('=')) => (Op::Ne, 2),
+                    ('<', Some('=')) => (Op::Le, 2),
+                    ('<', _) => (Op::Lt, 1),
+                    ('>', Some('=')) => (Op::Ge, 2),
+                    ('>', _) => (Op::Gt, 1),
+                    _ => {
+                        return Err(QueryError {
+                            message: "Expected '!='".to_string(),
+                            column,
+                        })
+                    }
+                };
+                i += len;
+                Token::Op(op)
+            }
+            '-' if !chars.get(i + 1).is_some_and(char::is_ascii_digit) => {
+                i += 1;
+                Token::Minus
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                Token::Number(text.parse().map_err(|_| QueryError {
+                    message: format!("Invalid number '{}'", text),
+                    column,
+                })?)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '-' | '.'))
+                {
+                    i += 1;
+                }
+                Token::Word(chars[start..i].iter().collect())
+            }
+            c => {
+                return Err(QueryError {
+                    message: format!("Unexpected '{}'", c),
+                    column,
+                })
+            }
+        };
+        tokens.push((token, column));
+    }
+    Ok(tokens)
+}
+
+/// Length of a bare `YYYY-MM-DD`, `YYYY-MM-DDTHH:MM` or `YYYY-MM-DDTHH:MM:SS`
+/// at the start of `chars`
+fn date_len(chars: &[char]) -> Option<usize> {
+    [19, 16, 10].into_iter().find(|&len| {
+        let Some(candidate) = chars.get(..len) else {
+            return false;
+        };
+        let ended = !chars
+            .get(len)
+            .is_some_and(|c| c.is_alphanumeric() || matches!(c, '-' | ':' | '.'));
+        let text: String = candidate.iter().collect();
+        let bytes = text.as_bytes();
+        ended
+            && match len {
+                10 => is_date(&text),
+                16 => bytes[10] == b'T' && is_date_time(&text),
+                _ => {
+                    bytes[10] == b'T'
+                        && is_date_time(&text)
+                        && bytes[16] == b':'
+                        && text[17..].bytes().all(|b| b.is_ascii_digit())
+                }
+            }
+    })
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// Column just past the end of the query
+    end: usize,
+}
+
+impl Parser {
+    fn query(&mut self) -> Result<Query, QueryError> {
+        let mut query = Query {
+            columns: Vec::new(),
+            from: None,
+            filter: None,
+            sort: Vec::new(),
+            limit: None,
+        };
+
+        if self.keyword("table") {
+            loop {
+                let field = self.field_name()?;
+                let label = if self.keyword("as") {
+                    match self.next() {
+                        Some(Token::Text(label)) | Some(Token::Word(label)) => label,
+                        _ => return Err(self.error_at_previous("Expected a column label")),
+                    }
+                } else {
+                    field.clone()
+                };
+                query.columns.push((field, label));
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+        } else {
+            self.keyword("list");
+        }
+
+        if self.keyword("from") {
+            query.from = Some(self.or(true)?);
+        }
+        if self.keyword("where") {
+            query.filter = Some(self.or(false)?);
+        }
+        if self.keyword("sort") {
+            loop {
+                let field = self.field_name()?;
+                let descending = if self.keyword("desc") {
+                    true
+                } else {
+                    self.keyword("asc");
+                    false
+                };
+                query.sort.push((field, descending));
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+        if self.keyword("limit") {
+            match self.next() {
+                Some(Token::Number(n)) if n >= 0.0 && n.fract() == 0.0 => {
+                    query.limit = Some(n as usize)
+                }
+                _ => return Err(self.error_at_previous("Expected a whole number after LIMIT")),
+            }
+        }
+
+        match self.tokens.get(self.position) {
+            None => Ok(query),
+            Some((token, column)) => Err(QueryError {
+                message: format!("Unexpected {}", describe(token)),
+                column: *column,
+            }),
+        }
+    }
+
+    /// `FROM` expressions take folders and tags, `WHERE` ones take anything
+    fn or(&mut self, source: bool) -> Result<Expr, QueryError> {
+        let mut expr = self.and(source)?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and(source)?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self, source: bool) -> Result<Expr, QueryError> {
+        let mut expr = self.not(source)?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not(source)?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self, source: bool) -> Result<Expr, QueryError> {
+        if self.keyword("not") || self.eat(&Token::Minus) {
+            return Ok(Expr::Not(Box::new(self.not(source)?)));
+        }
+        if source {
+            return self.source();
+        }
+
+        let left = self.primary()?;
+        match self.tokens.get(self.position) {
+            Some((Token::Op(op), _)) => {
+                let op = *op;
+                self.position += 1;
+                let right = self.primary()?;
+                Ok(Expr::Compare(Box::new(left), op, Box::new(right)))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn source(&mut self) -> Result<Expr, QueryError> {
+        match self.next() {
+            Some(Token::Text(folder)) => Ok(Expr::Folder(folder)),
+            Some(Token::Tag(tag)) => Ok(Expr::Tag(tag)),
+            Some(Token::Open) => {
+                let expr = self.or(true)?;
+                self.expect(&Token::Close, "Expected ')'")?;
+                Ok(expr)
+            }
+            _ => Err(self.error_at_previous("Expected a \"folder\" or #tag")),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        match self.next() {
+            Some(Token::Text(text)) => Ok(Expr::Literal(Value::String(text))),
+            Some(Token::Number(n)) => Ok(Expr::Literal(
+                serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number),
+            )),
+            Some(Token::Tag(tag)) => Ok(Expr::Tag(tag)),
+            Some(Token::Open) => {
+                let expr = self.or(false)?;
+                self.expect(&Token::Close, "Expected ')'")?;
+                Ok(expr)
+            }
+            Some(Token::Word(word)) => match word.to_lowercase().as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                "today" => Ok(Expr::Literal(Value::String(today()))),
+                "contains" if self.eat(&Token::Open) => {
+                    let haystack = self.or(false)?;
+                    self.expect(&Token::Comma, "Expected ','")?;
+                    let needle = self.or(false)?;
+                    self.expect(&Token::Close, "Expected ')'")?;
+                    Ok(Expr::Contains(Box::new(haystack), Box::new(needle)))
+                }
+                _ if is_keyword(&word) => Err(self.error_at_previous("Expected a value or field")),
+                _ => Ok(Expr::Field(word)),
+            },
+            _ => Err(self.error_at_previous("Expected a value or field")),
+        }
+    }
+
+    fn field_name(&mut self) -> Result<String, QueryError> {
+        match self.next() {
+            Some(Token::Word(word)) if !is_keyword(&word) => Ok(word),
+            _ => Err(self.error_at_previous("Expected a field name")),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).map(|(t, _)| t.clone());
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let matched = self
+            .tokens
+            .get(self.position)
+            .is_some_and(|(t, _)| t == token);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let matched = matches!(
+            self.tokens.get(self.position),
+            Some((Token::Word(word), _)) if word.eq_ignore_ascii_case(keyword)
+        );
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn expect(&mut self, token: &Token, message: &str) -> Result<(), QueryError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            self.position += 1;
+            Err(self.error_at_previous(message))
+        }
+    }
+
+    /// Error pointing at the token just consumed, or past the end
+    fn error_at_previous(&self, message: &str) -> QueryError {
+        QueryError {
+            message: message.to_string(),
+            column: self
+                .tokens
+                .get(self.position.saturating_sub(1))
+                .map_or(self.end, |(_, column)| *column),
+        }
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    [
+        "table", "list", "from", "where", "sort", "limit", "and", "or", "not", "asc", "desc", "as",
+    ]
+    .iter()
+    .any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("'{}'", word),
+        Token::Text(text) => format!("\"{}\"", text),
+        Token::Number(n) => n.to_string(),
+        Token::Tag(tag) => format!("#{}", tag),
+        Token::Op(_) => "operator".to_string(),
+        Token::Open => "'('".to_string(),
+        Token::Close => "')'".to_string(),
+        Token::Comma => "','".to_string(),
+        Token::Minus => "'-'".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault(name: &str, notes: &[(&str, &str)]) -> (PathBuf, VaultIndex) {
+        let dir = std::env::temp_dir().join(format!("yana-query-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in notes {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let dir = fs::canonicalize(dir).unwrap();
+        let mut index = VaultIndex::default();
+        index.rebuild(&dir);
+        (dir, index)
+    }
+
+    fn names(result: &QueryResult) -> Vec<String> {
+        result
+            .rows
+            .iter()
+            .map(|row| {
+                Path::new(&row.path)
+                    .file_stem()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    fn run(index: &VaultIndex, query: &str) -> QueryResult {
+        index.query(&parse_query(query).unwrap())
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_problem() {
+        let error = |query: &str| parse_query(query).unwrap_err();
+
+        assert_eq!(error("WHERE status = \"open").column, 16);
+        assert_eq!(error("LIST WHERE").message, "Expected a value or field");
+        assert_eq!(error("LIST WHERE").column, 11);
+        assert_eq!(
+            error("LIST LIMIT 2.5").message,
+            "Expected a whole number after LIMIT"
+        );
+        assert_eq!(error("LIST SORT due LIMIT 1 extra").column, 23);
+        assert_eq!(
+            error("FROM status").message,
+            "Expected a \"folder\" or #tag"
+        );
+        assert_eq!(error("WHERE a ! b").message, "Expected '!='");
+    }
+
+    #[test]
+    fn bare_dates_are_date_literals() {
+        let tokens = |query: &str| -> Vec<Token> {
+            tokenize(query)
+                .unwrap()
+                .into_iter()
+                .map(|(token, _)| token)
+                .collect()
+        };
+        assert_eq!(
+            tokens("due < 2024-01-01"),
+            [
+                Token::Word("due".into()),
+                Token::Op(Op::Lt),
+                Token::Text("2024-01-01".into())
+            ]
+        );
+        assert_eq!(
+            tokens("2024-01-01T09:30 2024-01-01T09:30:15"),
+            [
+                Token::Text("2024-01-01T09:30".into()),
+                Token::Text("2024-01-01T09:30:15".into())
+            ]
+        );
+        // Not a date: plain arithmetic-looking numbers stay numbers
+        assert_eq!(
+            tokens("2024 -1"),
+            [Token::Number(2024.0), Token::Number(-1.0)]
+        );
+
+        let (dir, index) = vault(
+            "dates",
+            &[
+                ("Early.md", "---\ndue: 2023-12-31\n---\n"),
+                ("Late.md", "---\ndue: 2024-01-01T10:00\n---\n"),
+                ("Undated.md", "No due date\n"),
+            ],
+        );
+        assert_eq!(names(&run(&index, "WHERE due < 2024-01-01")), ["Early"]);
+        assert_eq!(names(&run(&index, "WHERE due >= \"2024-01-01\"")), ["Late"]);
+        assert_eq!(
+            names(&run(&index, "WHERE due > 2023-12-31T23:59")),
+            ["Late"]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn where_sort_and_limit() {
+        let (dir, index) = vault(
+            "filter",
+            &[
+                (
+                    "Projects/Alpha.md",
+                    "---\nstatus: active\npriority: 2\n---\n#work\n",
+                ),
+                (
+                    "Projects/Beta.md",
+                    "---\nstatus: Active\npriority: 1\n---\n",
+                ),
+                (
+                    "Projects/Gamma.md",
+                    "---\nstatus: done\npriority: 3\n---\n#work\n",
+                ),
+                ("Inbox.md", "---\nstatus: active\n---\n#work\n"),
+            ],
+        );
+
+        let result = run(
+            &index,
+            "TABLE priority AS \"P\" FROM \"Projects\" WHERE status = \"active\" SORT priority",
+        );
+        assert_eq!(result.columns, ["P"]);
+        assert_eq!(names(&result), ["Beta", "Alpha"]);
+        assert_eq!(result.rows[0].values, [Value::from(1)]);
+
+        assert_eq!(
+            names(&run(&index, "FROM #work AND -\"Projects\"")),
+            ["Inbox"]
+        );
+        assert_eq!(
+            names(&run(
+                &index,
+                "WHERE #work AND NOT status = \"done\" SORT file.name DESC"
+            )),
+            ["Inbox", "Alpha"]
+        );
+        // Missing values sort last in either direction
+        assert_eq!(
+            names(&run(&index, "SORT priority DESC LIMIT 3")),
+            ["Gamma", "Alpha", "Beta"]
+        );
+        assert_eq!(
+            names(&run(&index, "SORT priority DESC")).last().unwrap(),
+            "Inbox"
+        );
+        assert_eq!(run(&index, "LIST LIMIT 0").rows.len(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mixed_value_types() {
+        let (dir, index) = vault(
+            "mixed",
+            &[
+                ("Flag.md", "---\nrank: true\n---\n"),
+                ("Number.md", "---\nrank: 10\n---\n"),
+                ("Date.md", "---\nrank: 2024-05-01\n---\n"),
+                ("Text.md", "---\nrank: high\n---\n"),
+                ("List.md", "---\nrank: [1, 2]\n---\n"),
+                ("None.md", "No rank\n"),
+            ],
+        );
+
+        // Sorting never fails on mixed types: it orders by type, then value
+        assert_eq!(
+            names(&run(&index, "SORT rank")),
+            ["Flag", "Number", "Date", "Text", "List", "None"]
+        );
+        assert_eq!(
+            names(&run(&index, "SORT rank DESC")),
+            ["List", "Text", "Date", "Number", "Flag", "None"]
+        );
+
+        // Comparisons across types never match, lists match on any item
+        assert_eq!(names(&run(&index, "WHERE rank > 1")), ["List", "Number"]);
+        assert_eq!(names(&run(&index, "WHERE rank = \"HIGH\"")), ["Text"]);
+        assert_eq!(names(&run(&index, "WHERE rank = null")), ["None"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn contains_lists_and_text() {
+        let (dir, index) = vault(
+            "contains",
+            &[
+                ("Listed.md", "---\naliases: [Alpha, Beta]\n---\n"),
+                ("Titled.md", "---\ntitle: The Alphabet\n---\n"),
+                ("Tagged.md", "#project/alpha\n"),
+            ],
+        );
+
+        assert_eq!(
+            names(&run(&index, "WHERE contains(aliases, \"beta\")")),
+            ["Listed"]
+        );
+        assert_eq!(
+            names(&run(&index, "WHERE contains(title, \"alpha\")")),
+            ["Titled"]
+        );
+        // A list only contains whole items
+        assert!(run(&index, "WHERE contains(aliases, \"alp\")")
+            .rows
+            .is_empty());
+        assert_eq!(
+            names(&run(&index, "WHERE contains(file.tags, \"project/alpha\")")),
+            ["Tagged"]
+        );
+        assert_eq!(
+            names(&run(
+                &index,
+                "WHERE NOT contains(aliases, \"Alpha\") SORT file.name"
+            )),
+            ["Tagged", "Titled"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,506 @@
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::links::{parse_links, Link, LinkKind};
+use super::markdown::percent_encode_path;
+use super::{is_note, VaultIndex};
+
+/// Replacement of a byte range in a note
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// Apply edits to content; an edit overlapping an earlier one is skipped
+pub fn apply_edits(content: &str, mut edits: Vec<TextEdit>) -> String {
+    edits.sort_by_key(|edit| edit.range.start);
+
+    let mut out = String::with_capacity(content.len());
+    let mut last = 0;
+    for edit in edits {
+        if edit.range.start < last {
+            continue;
+        }
+        out.push_str(&content[last..edit.range.start]);
+        out.push_str(&edit.text);
+        last = edit.range.end;
+    }
+    out.push_str(&content[last..]);
+    out
+}
+
+/// A note with links that a move will break, captured before the move
+#[derive(Debug, Clone)]
+pub struct MoveRewrite {
+    /// Path of the note before the move
+    pub path: PathBuf,
+    pub content: String,
+    /// Links to rewrite, each with the file it resolved to before the move
+    pub links: Vec<(Link, PathBuf)>,
+}
+
+/// A note rewritten by a link (or tag) update, ready to be written
+#[derive(Debug, Clone)]
+pub struct RewrittenNote {
+    pub path: PathBuf,
+    pub content: String,
+    /// Content the rewrite was computed from, to notice saves made since
+    pub original: String,
+    /// Number of links (or tags) changed
+    pub links: usize,
+}
+
+impl VaultIndex {
+    /// Collect the links that moving `from` (a file or folder) will break
+    ///
+    /// Covers links pointing into `from` from anywhere in the vault, as well as
+    /// relative markdown links inside the moved notes themselves. Notes are re-read
+    /// so the captured spans match the files on disk.
+    pub fn plan_move(&self, from: &Path) -> Vec<MoveRewrite> {
+        let mut plans = Vec::new();
+
+        for (source, entry) in &self.notes {
+            let source_moved = source.starts_with(from);
+            let affected = entry.links.iter().any(|link| {
+                let target_moved = self
+                    .resolve(source, link)
+                    .is_some_and(|target| target.starts_with(from));
+                needs_rewrite(link, source_moved, target_moved)
+            });
+            if !affected {
+                continue;
+            }
+
+            let content = match fs::read_to_string(source) {
+                Ok(content) => content,
+                Err(e) => {
+                    log::warn!("Skipping link update in '{}': {}", source.display(), e);
+                    continue;
+                }
+            };
+            let links = parse_links(&content)
+                .into_iter()
+                .filter_map(|link| {
+                    let target = self.resolve(source, &link)?;
+                    needs_rewrite(&link, source_moved, target.starts_with(from))
+                        .then_some((link, target))
+                })
+                .collect();
+
+            plans.push(MoveRewrite {
+                path: source.clone(),
+                content,
+                links,
+            });
+        }
+
+        plans
+    }
+
+    /// Rewrite a planned note so its links point at the moved files again
+    ///
+    /// Must be called once the index reflects the move. Returns `None` when no
+    /// link text actually changes.
+    pub fn rewrite_moved(
+        &self,
+        plan: &MoveRewrite,
+        from: &Path,
+        to: &Path,
+    ) -> Option<RewrittenNote> {
+        let source = moved_path(&plan.path, from, to);
+
+        let edits: Vec<TextEdit> = plan
+            .links
+            .iter()
+            .filter_map(|(link, target)| {
+                let target = moved_path(target, from, to);
+                let text = self.link_target_text(&source, link, &plan.content, &target)?;
+                (text != plan.content[link.target_span.clone()]).then(|| TextEdit {
+                    range: link.target_span.clone(),
+                    text,
+                })
+            })
+            .collect();
+
+        if edits.is_empty() {
+            return None;
+        }
+        Some(RewrittenNote {
+            path: source,
+            links: edits.len(),
+            content: apply_edits(&plan.content, edits),
+            original: plan.content.clone(),
+        })
+    }
+
+    /// Target text for `link`, written in `source`, so that it points at `target`
+    ///
+    /// Keeps the style of the original: path or bare name for wiki links, rooted
+    /// or relative for markdown links, and whether the `.md` extension was spelled out.
+    pub fn link_target_text(
+        &self,
+        source: &Path,
+        link: &Link,
+        content: &str,
+        target: &Path,
+    ) -> Option<String> {
+        let keep_md = link.target.to_lowercase().ends_with(".md");
+        match link.kind {
+            LinkKind::Wiki => self.wiki_target(source, target, link.target.contains('/'), keep_md),
+            LinkKind::Markdown => {
+                // `<...>` destinations may contain spaces, bare ones must be encoded
+                let angled = link.target_span.start > 0
+                    && content.as_bytes()[link.target_span.start - 1] == b'<';
+                self.markdown_target(
+                    source,
+                    target,
+                    link.target.starts_with('/'),
+                    keep_md,
+                    !angled,
+                )
+            }
+        }
+    }
+
+    /// Wiki link target naming `target` from `source`
+    ///
+    /// A bare file name is used unless `prefer_path` is set or the name would
+    /// resolve to a different file, in which case the vault-relative path is used.
+    pub fn wiki_target(
+        &self,
+        source: &Path,
+        target: &Path,
+        prefer_path: bool,
+        keep_md: bool,
+    ) -> Option<String> {
+        let relative = target.strip_prefix(self.root()?).ok()?;
+        let path = strip_note_extension(&slash_path(relative), target, keep_md);
+
+        if !prefer_path {
+            let name = target.file_name()?.to_string_lossy().to_string();
+            let name = strip_note_extension(&name, target, keep_md);
+            if self
+                .resolve_target(source, LinkKind::Wiki, &name)
+                .as_deref()
+                == Some(target)
+            {
+                return Some(name);
+            }
+        }
+        Some(path)
+    }
+
+    /// Markdown link destination pointing at `target` from `source`
+    pub fn markdown_target(
+        &self,
+        source: &Path,
+        target: &Path,
+        rooted: bool,
+        keep_md: bool,
+        encode: bool,
+    ) -> Option<String> {
+        let path = if rooted {
+            format!("/{}", slash_path(target.strip_prefix(self.root()?).ok()?))
+        } else {
+            relative_path(source.parent()?, target)
+        };
+        let path = strip_note_extension(&path, target, keep_md);
+        Some(if encode {
+            percent_encode_path(&path)
+        } else {
+            path
+        })
+    }
+}
+
+/// Whether a link has to change when its note or its target moves
+fn needs_rewrite(link: &Link, source_moved: bool, target_moved: bool) -> bool {
+    if link.target.is_empty() {
+        return false;
+    }
+    match link.kind {
+        // Wiki links don't depend on where the linking note lives
+        LinkKind::Wiki => target_moved,
+        LinkKind::Markdown => target_moved || (source_moved && !link.target.starts_with('/')),
+    }
+}
+
+/// Where a path ends up when `from` is moved to `to`
+pub fn moved_path(path: &Path, from: &Path, to: &Path) -> PathBuf {
+    match path.strip_prefix(from) {
+        Ok(rest) if rest.as_os_str().is_empty() => to.to_path_buf(),
+        Ok(rest) => to.join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Relative path from a folder to a file, with `/` separators
+pub fn relative_path(from_dir: &Path, to: &Path) -> String {
+    let from: Vec<_> = from_dir.components().collect();
+    let target: Vec<_> = to.components().collect();
+    let common = from
+        .iter()
+        .zip(target.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(
+        target[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().to_string()),
+    );
+    parts.join("/")
+}
+
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn strip_note_extension(text: &str, target: &Path, keep_md: bool) -> String {
+    if keep_md || !is_note(target) {
+        return text.to_string();
+    }
+    // is_note guarantees a three byte `.md` suffix in some casing
+    text[..text.len() - 3].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault(name: &str, files: &[(&str, &str)]) -> (PathBuf, VaultIndex) {
+        let dir =
+            std::env::temp_dir().join(format!("yana-rewrite-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let dir = fs::canonicalize(dir).unwrap();
+        let mut index = VaultIndex::default();
+        index.rebuild(&dir);
+        (dir, index)
+    }
+
+    /// Move `from` to `to` as the rename command does, returning the rewritten
+    /// notes by path relative to the root
+    fn move_and_rewrite(
+        root: &Path,
+        index: &mut VaultIndex,
+        from: &str,
+        to: &str,
+    ) -> Vec<(String, String)> {
+        let (from, to) = (root.join(from), root.join(to));
+        let plans = index.plan_move(&from);
+
+        fs::create_dir_all(to.parent().unwrap()).unwrap();
+        fs::rename(&from, &to).unwrap();
+        index.refresh_path(&from);
+        index.refresh_path(&to);
+
+        let mut notes: Vec<(String, String)> = plans
+            .iter()
+            .filter_map(|plan| index.rewrite_moved(plan, &from, &to))
+            .map(|note| {
+                let path = slash_path(note.path.strip_prefix(root).unwrap());
+                (path, note.content)
+            })
+            .collect();
+        notes.sort();
+        notes
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn renaming_under_a_symlinked_root_reports_vault_paths() {
+        use crate::sandbox::VaultSandbox;
+
+        let (real, _) = vault("symlinked", &[("Plan.md", ""), ("Links.md", "[[Plan]]\n")]);
+        let link = real.with_file_name(format!("yana-rewrite-link-{}", std::process::id()));
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        // The frontend registered the vault, and names its files, through the symlink
+        let mut sandbox = VaultSandbox::default();
+        let roots = sandbox.set_roots(&[link.to_string_lossy().to_string()]);
+        assert_eq!(roots, std::slice::from_ref(&real));
+        let resolve = |name: &str| {
+            sandbox
+                .resolve_entry(&link.join(name).to_string_lossy())
+                .unwrap()
+        };
+        let (from, to) = (resolve("Plan.md"), resolve("Roadmap.md"));
+        assert_eq!(from, real.join("Plan.md"));
+
+        let mut index = VaultIndex::default();
+        index.rebuild(&real);
+        index.set_shown_root(sandbox.shown(&real));
+        let plans = index.plan_move(&from);
+        fs::rename(&from, &to).unwrap();
+        index.refresh_path(&from);
+        index.refresh_path(&to);
+        let notes: Vec<RewrittenNote> = plans
+            .iter()
+            .filter_map(|plan| index.rewrite_moved(plan, &from, &to))
+            .collect();
+
+        // The rewritten note is reported as the path the editor has open
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].content, "[[Roadmap]]\n");
+        assert_eq!(sandbox.shown(&notes[0].path), link.join("Links.md"));
+        fs::write(&notes[0].path, &notes[0].content).unwrap();
+        index.refresh_path(&notes[0].path);
+        let backlinks: Vec<String> = index
+            .backlinks(&to)
+            .into_iter()
+            .map(|(source, _)| index.shown(source))
+            .collect();
+        assert_eq!(backlinks, [link.join("Links.md").to_string_lossy()]);
+
+        fs::remove_file(&link).unwrap();
+        fs::remove_dir_all(&real).unwrap();
+    }
+
+    #[test]
+    fn renaming_a_note_updates_links_to_it() {
+        let (root, mut index) = vault(
+            "rename",
+            &[
+                ("Plan.md", "# Plan\n"),
+                (
+                    "Work/Links.md",
+                    "[[Plan]] [[Plan#Plan|alias]] [[Plan.md]] [md](../Plan.md) [root](/Plan)\n",
+                ),
+                ("Other.md", "[[Other]] and `[[Plan]]`\n"),
+            ],
+        );
+
+        let notes = move_and_rewrite(&root, &mut index, "Plan.md", "Roadmap 2024.md");
+        assert_eq!(
+            notes,
+            [(
+                "Work/Links.md".to_string(),
+                "[[Roadmap 2024]] [[Roadmap 2024#Plan|alias]] [[Roadmap 2024.md]] [md](../Roadmap%202024.md) [root](/Roadmap%202024)\n"
+                    .to_string()
+            )]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn moving_a_note_fixes_its_own_relative_links() {
+        let (root, mut index) = vault(
+            "move",
+            &[
+                (
+                    "Note.md",
+                    "![shot](attachments/Screen Shot.png) [[Sibling]] [s](Sibling.md) [r](/Sibling.md)\n",
+                ),
+                ("Sibling.md", "[back](Note.md)\n"),
+                ("attachments/Screen Shot.png", ""),
+            ],
+        );
+
+        let notes = move_and_rewrite(&root, &mut index, "Note.md", "Archive/2024/Note.md");
+        assert_eq!(
+            notes,
+            [
+                (
+                    "Archive/2024/Note.md".to_string(),
+                    "![shot](../../attachments/Screen%20Shot.png) [[Sibling]] [s](../../Sibling.md) [r](/Sibling.md)\n"
+                        .to_string()
+                ),
+                (
+                    "Sibling.md".to_string(),
+                    "[back](Archive/2024/Note.md)\n".to_string()
+                ),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn moving_a_folder_keeps_links_inside_it() {
+        let (root, mut index) = vault(
+            "folder",
+            &[
+                ("Projects/A.md", "[b](B.md) [[B]] [out](../Index.md)\n"),
+                ("Projects/B.md", ""),
+                ("Index.md", "[[Projects/A]] [a](Projects/A.md)\n"),
+            ],
+        );
+
+        let notes = move_and_rewrite(&root, &mut index, "Projects", "Archive/Projects");
+        assert_eq!(
+            notes,
+            [
+                (
+                    "Archive/Projects/A.md".to_string(),
+                    "[b](B.md) [[B]] [out](../../Index.md)\n".to_string()
+                ),
+                (
+                    "Index.md".to_string(),
+                    "[[Archive/Projects/A]] [a](Archive/Projects/A.md)\n".to_string()
+                ),
+            ]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn ambiguous_names_get_a_path() {
+        let (root, mut index) = vault(
+            "ambiguous",
+            &[
+                ("Inbox/Draft.md", ""),
+                ("Zeta/Draft.md", ""),
+                ("Inbox/Links.md", "[[Draft]]\n"),
+            ],
+        );
+
+        // After the move the bare name would find the shallower Zeta/Draft
+        let notes = move_and_rewrite(&root, &mut index, "Inbox/Draft.md", "Old/Deep/Draft.md");
+        assert_eq!(
+            notes,
+            [(
+                "Inbox/Links.md".to_string(),
+                "[[Old/Deep/Draft]]\n".to_string()
+            )]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn overlapping_edits_keep_the_first() {
+        let edits = vec![
+            TextEdit {
+                range: 4..7,
+                text: "two".to_string(),
+            },
+            TextEdit {
+                range: 0..3,
+                text: "ONE".to_string(),
+            },
+            TextEdit {
+                range: 5..6,
+                text: "x".to_string(),
+            },
+        ];
+        assert_eq!(apply_edits("one 222 three", edits), "ONE two three");
+        assert_eq!(
+            relative_path(Path::new("/v/a/b"), Path::new("/v/c/d.md")),
+            "../../c/d.md"
+        );
+    }
+}
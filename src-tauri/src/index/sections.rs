@@ -0,0 +1,99 @@
+use std::ops::Range;
+
+use super::blocks::strip_block_id;
+use super::markdown::prose_lines;
+
+/// An ATX heading (`## Title`) in a note
+#[derive(Debug, Clone)]
+pub struct Heading {
+    /// 1 to 6
+    pub level: usize,
+    /// Heading text without the `#` markers
+    pub text: String,
+    /// Byte range of the heading line in the note
+    pub span: Range<usize>,
+}
+
+/// Every ATX heading outside frontmatter and code blocks, in document order
+pub fn parse_headings(content: &str) -> Vec<Heading> {
+    prose_lines(content)
+        .into_iter()
+        .filter_map(|line| {
+            let (level, text) = heading_of(line.text)?;
+            Some(Heading {
+                level,
+                text,
+                span: line.start..line.start + line.text.len(),
+            })
+        })
+        .collect()
+}
+
+/// Byte range of the section under a heading, heading line included
+///
+/// `path` lists the heading and optionally its ancestors (`[[Note#Parent#Child]]`),
+/// each matched ignoring case and surrounding whitespace. The section ends at the
+/// next heading of the same or a higher level.
+pub fn section_range(content: &str, path: &[&str]) -> Option<Range<usize>> {
+    let headings = parse_headings(content);
+    let mut scope = 0..headings.len();
+    let mut found: Option<usize> = None;
+
+    for wanted in path {
+        let wanted = normalize_heading(wanted);
+        let index = scope
+            .clone()
+            .find(|i| normalize_heading(&headings[*i].text) == wanted)?;
+        let end = section_end(&headings, index);
+        scope = index + 1..end;
+        found = Some(index);
+    }
+
+    let index = found?;
+    let start = headings[index].span.start;
+    let end = headings
+        .get(section_end(&headings, index))
+        .map_or(content.len(), |next| next.span.start);
+    Some(start..end)
+}
+
+/// Level and text of an ATX heading line
+pub fn heading_of(text: &str) -> Option<(usize, String)> {
+    let trimmed = text.trim_start();
+    if text.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    // A closing run of `#` is not part of the text, and neither is a block id
+    let rest = strip_block_id(rest).trim();
+    let rest = match rest.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with([' ', '\t']) => stripped.trim_end(),
+        _ => rest,
+    };
+    Some((level, rest.to_string()))
+}
+
+/// Index of the first heading after `index` that closes its section
+fn section_end(headings: &[Heading], index: usize) -> usize {
+    let level = headings[index].level;
+    headings[index + 1..]
+        .iter()
+        .position(|h| h.level <= level)
+        .map_or(headings.len(), |offset| index + 1 + offset)
+}
+
+/// Heading text as compared against link fragments
+pub fn normalize_heading(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
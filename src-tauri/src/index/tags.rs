@@ -0,0 +1,454 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use super::frontmatter::{property, property_range};
+use super::links::Link;
+use super::markdown::{code_spans, column_of, in_ranges, prose_lines};
+use super::rewrite::{apply_edits, RewrittenNote, TextEdit};
+use super::{NoteEntry, VaultIndex};
+
+/// A `#tag` in the body of a note, or an entry of its `tags` property
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagRef {
+    /// Tag name without the `#`, as written
+    pub name: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based character column of the `#`, or of the name in frontmatter
+    pub column: usize,
+    /// Whether the tag comes from the frontmatter rather than the body
+    pub frontmatter: bool,
+    /// Byte range of the name in the note
+    #[serde(skip)]
+    pub span: Range<usize>,
+}
+
+/// A level of the tag hierarchy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagNode {
+    /// Last segment of the tag, e.g. `alpha`
+    pub name: String,
+    /// Full tag without the `#`, e.g. `project/alpha`
+    pub tag: String,
+    /// Notes carrying exactly this tag
+    pub notes: usize,
+    /// Notes carrying this tag or one nested below it
+    pub total: usize,
+    pub children: Vec<TagNode>,
+}
+
+/// A note carrying a tag, with where the tag occurs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaggedNote {
+    pub path: String,
+    pub tags: Vec<TagRef>,
+}
+
+/// Extract inline `#tags` from a note
+///
+/// A tag starts with `#` at the beginning of a line or after whitespace, and
+/// consists of letters, digits, `_`, `-` and `/` for nesting. Purely numeric
+/// tags (`#123`) are ignored, as are tags inside code and inside links.
+pub fn parse_inline_tags(content: &str, links: &[Link]) -> Vec<TagRef> {
+    let link_spans: Vec<Range<usize>> = links.iter().map(|link| link.span.clone()).collect();
+    let mut tags = Vec::new();
+
+    for line in prose_lines(content) {
+        let code = code_spans(line.text);
+        let bytes = line.text.as_bytes();
+
+        for (i, _) in line.text.match_indices('#') {
+            if i > 0 && !(bytes[i - 1] as char).is_ascii_whitespace() {
+                continue;
+            }
+            if in_ranges(&code, i) || in_ranges(&link_spans, line.start + i) {
+                continue;
+            }
+
+            let rest = &line.text[i + 1..];
+            let len: usize = rest
+                .chars()
+                .take_while(|c| is_tag_char(*c))
+                .map(char::len_utf8)
+                .sum();
+            let name = rest[..len].trim_end_matches('/');
+            if name.is_empty() || name.chars().all(|c| c.is_ascii_digit() || c == '/') {
+                continue;
+            }
+
+            tags.push(TagRef {
+                name: name.to_string(),
+                line: line.number,
+                column: column_of(line.text, i),
+                frontmatter: false,
+                span: line.start + i + 1..line.start + i + 1 + name.len(),
+            });
+        }
+    }
+
+    tags
+}
+
+/// Tags listed in the `tags` (or `tag`) frontmatter property
+///
+/// The property may be a list or a string of comma or space separated tags, each
+/// with or without a leading `#`. Every tag is located in the property's lines so
+/// it can be renamed in place.
+pub fn parse_frontmatter_tags(content: &str, properties: &[(String, Value)]) -> Vec<TagRef> {
+    let Some((key, value)) = ["tags", "tag"]
+        .into_iter()
+        .find_map(|key| Some((key, property(properties, key)?)))
+    else {
+        return Vec::new();
+    };
+    let Some(range) = property_range(content, key) else {
+        return Vec::new();
+    };
+
+    let items: Vec<&Value> = match value {
+        Value::Array(items) => items.iter().collect(),
+        value => vec![value],
+    };
+    let names = items.into_iter().flat_map(|item| match item {
+        Value::String(text) => text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(|name| name.trim_start_matches('#').trim_end_matches('/'))
+            .filter(|name| is_tag_name(name))
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    });
+
+    // Skip past the key itself before looking for the names
+    let mut cursor = content[range.clone()]
+        .find([':', '='])
+        .map_or(range.start, |i| range.start + i + 1);
+    let mut tags = Vec::new();
+    for name in names {
+        let Some(start) = find_tag(content, cursor..range.end, &name) else {
+            continue;
+        };
+        cursor = start + name.len();
+
+        let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+        let column_at = if content[..start].ends_with('#') {
+            start - 1
+        } else {
+            start
+        };
+        tags.push(TagRef {
+            line: content[..start].matches('\n').count() + 1,
+            column: column_of(&content[line_start..], column_at - line_start),
+            name,
+            frontmatter: true,
+            span: start..cursor,
+        });
+    }
+    tags
+}
+
+/// Start of `name` as a whole tag within `range`
+fn find_tag(content: &str, range: Range<usize>, name: &str) -> Option<usize> {
+    let text = &content[range.clone()];
+    text.match_indices(name)
+        .map(|(i, _)| range.start + i)
+        .find(|start| {
+            let before = content[..*start].chars().next_back();
+            let after = content[start + name.len()..].chars().next();
+            !before.is_some_and(|c| is_tag_char(c) && c != '/') && !after.is_some_and(is_tag_char)
+        })
+}
+
+/// Whether text is a usable tag name: tag characters only, and not just digits
+pub fn is_tag_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(is_tag_char)
+        && !name.chars().all(|c| c.is_ascii_digit() || c == '/')
+}
+
+/// Characters allowed in a tag name
+pub fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// Whether `tag` is `parent` or nested below it, ignoring case
+pub fn tag_matches(tag: &str, parent: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let parent = parent.trim_start_matches('#').to_lowercase();
+    tag == parent || tag.starts_with(&format!("{}/", parent))
+}
+
+impl VaultIndex {
+    /// Every tag in the vault as a hierarchy, with note counts at each level
+    ///
+    /// Tags differing only in case are merged and shown as first written.
+    pub fn tag_tree(&self) -> Vec<TagNode> {
+        let mut paths: Vec<&PathBuf> = self.notes.keys().collect();
+        paths.sort();
+
+        // Lowercase tag to (spelling, notes, total)
+        let mut counts: BTreeMap<String, (String, usize, usize)> = BTreeMap::new();
+        for path in paths {
+            let mut exact = BTreeSet::new();
+            let mut nested = BTreeSet::new();
+            for tag in &self.notes[path].tags {
+                let segments: Vec<&str> = tag.name.split('/').collect();
+                for depth in 1..=segments.len() {
+                    let spelling = segments[..depth].join("/");
+                    let key = spelling.to_lowercase();
+                    counts.entry(key.clone()).or_insert((spelling, 0, 0));
+                    if depth == segments.len() {
+                        exact.insert(key.clone());
+                    }
+                    nested.insert(key);
+                }
+            }
+            for key in exact {
+                if let Some(count) = counts.get_mut(&key) {
+                    count.1 += 1;
+                }
+            }
+            for key in nested {
+                if let Some(count) = counts.get_mut(&key) {
+                    count.2 += 1;
+                }
+            }
+        }
+
+        let mut roots: Vec<TagNode> = Vec::new();
+        for (spelling, notes, total) in counts.into_values() {
+            // Parents sort before their children, so the parent is already in place
+            let mut level = &mut roots;
+            let segments: Vec<&str> = spelling.split('/').collect();
+            for segment in &segments[..segments.len() - 1] {
+                let index = level
+                    .iter()
+                    .position(|node| node.name.eq_ignore_ascii_case(segment))
+                    .unwrap_or_else(|| {
+                        level.push(TagNode {
+                            name: segment.to_string(),
+                            tag: String::new(),
+                            notes: 0,
+                            total: 0,
+                            children: Vec::new(),
+                        });
+                        level.len() - 1
+                    });
+                level = &mut level[index].children;
+            }
+            level.push(TagNode {
+                name: segments[segments.len() - 1].to_string(),
+                tag: spelling.clone(),
+                notes,
+                total,
+                children: Vec::new(),
+            });
+        }
+        roots
+    }
+
+    /// Notes carrying a tag, ordered by path
+    ///
+    /// With `nested`, tags below it count too (`#project` finds `#project/alpha`).
+    pub fn notes_with_tag(&self, tag: &str, nested: bool) -> Vec<TaggedNote> {
+        let tag = tag.trim().trim_start_matches('#');
+        let mut notes: Vec<TaggedNote> = self
+            .notes
+            .iter()
+            .filter_map(|(path, entry)| {
+                let tags: Vec<TagRef> = entry
+                    .tags
+                    .iter()
+                    .filter(|t| {
+                        if nested {
+                            tag_matches(&t.name, tag)
+                        } else {
+                            t.name.to_lowercase() == tag.to_lowercase()
+                        }
+                    })
+                    .cloned()
+                    .collect();
+                (!tags.is_empty()).then(|| TaggedNote {
+                    path: self.shown(path),
+                    tags,
+                })
+            })
+            .collect();
+        notes.sort_by(|a, b| a.path.cmp(&b.path));
+        notes
+    }
+
+    /// Notes rewritten so that `from`, and every tag nested below it, becomes `to`
+    ///
+    /// Renaming onto an existing tag merges the two. Nested tags keep their place
+    /// below the renamed part (`#project/alpha` becomes `#work/alpha`). Notes are
+    /// re-read so the spans match the files on disk; nothing is written.
+    pub fn rename_tag(&self, from: &str, to: &str) -> Vec<RewrittenNote> {
+        let depth = from.split('/').count();
+        let mut sources: Vec<&PathBuf> = self
+            .notes
+            .iter()
+            .filter(|(_, entry)| entry.tags.iter().any(|t| tag_matches(&t.name, from)))
+            .map(|(path, _)| path)
+            .collect();
+        sources.sort();
+
+        let mut notes = Vec::new();
+        for source in sources {
+            let content = match fs::read_to_string(source) {
+                Ok(content) => content,
+                Err(e) => {
+                    log::warn!("Skipping tag rename in '{}': {}", source.display(), e);
+                    continue;
+                }
+            };
+
+            let edits: Vec<TextEdit> = NoteEntry::analyze(&content)
+                .tags
+                .into_iter()
+                .filter(|tag| tag_matches(&tag.name, from))
+                .map(|tag| {
+                    let rest: Vec<&str> = tag.name.split('/').skip(depth).collect();
+                    let mut text = to.to_string();
+                    if !rest.is_empty() {
+                        text.push('/');
+                        text.push_str(&rest.join("/"));
+                    }
+                    TextEdit {
+                        range: tag.span,
+                        text,
+                    }
+                })
+                .filter(|edit| content[edit.range.clone()] != edit.text)
+                .collect();
+
+            if !edits.is_empty() {
+                notes.push(RewrittenNote {
+                    path: source.to_path_buf(),
+                    links: edits.len(),
+                    content: apply_edits(&content, edits),
+                    original: content,
+                });
+            }
+        }
+        notes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn rename(name: &str, notes: &[(&str, &str)], from: &str, to: &str) -> Vec<(String, String)> {
+        let dir = std::env::temp_dir().join(format!("yana-tags-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (path, content) in notes {
+            fs::write(dir.join(path), content).unwrap();
+        }
+        let mut index = VaultIndex::default();
+        index.rebuild(&dir);
+
+        let renamed = index
+            .rename_tag(from, to)
+            .into_iter()
+            .map(|note| {
+                let name = Path::new(&note.path).file_name().unwrap();
+                (name.to_string_lossy().to_string(), note.content)
+            })
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        renamed
+    }
+
+    #[test]
+    fn nested_tags_move_with_their_parent() {
+        let renamed = rename(
+            "nested",
+            &[
+                (
+                    "A.md",
+                    "#project and #project/alpha/x, not #projects or #other/project\n",
+                ),
+                ("B.md", "#unrelated\n"),
+            ],
+            "project",
+            "work",
+        );
+        assert_eq!(
+            renamed,
+            [(
+                "A.md".to_string(),
+                "#work and #work/alpha/x, not #projects or #other/project\n".to_string()
+            )]
+        );
+
+        // Renaming a nested tag leaves its parent and siblings alone
+        let renamed = rename(
+            "nested-child",
+            &[("A.md", "#project/alpha #project/beta #project\n")],
+            "project/alpha",
+            "archive",
+        );
+        assert_eq!(renamed[0].1, "#archive #project/beta #project\n");
+    }
+
+    #[test]
+    fn frontmatter_lists_and_strings() {
+        let renamed = rename(
+            "frontmatter",
+            &[
+                (
+                    "List.md",
+                    "---\ntags:\n  - project\n  - '#project/alpha'\n  - projector\n---\nproject\n",
+                ),
+                ("Inline.md", "---\ntags: [project, other]\n---\n"),
+                (
+                    "String.md",
+                    "---\ntags: \"other, #project project/beta\"\n---\n",
+                ),
+            ],
+            "project",
+            "work",
+        );
+        assert_eq!(
+            renamed,
+            [
+                (
+                    "Inline.md".to_string(),
+                    "---\ntags: [work, other]\n---\n".to_string()
+                ),
+                (
+                    "List.md".to_string(),
+                    "---\ntags:\n  - work\n  - '#work/alpha'\n  - projector\n---\nproject\n"
+                        .to_string()
+                ),
+                (
+                    "String.md".to_string(),
+                    "---\ntags: \"other, #work work/beta\"\n---\n".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tags_in_code_and_links_stay() {
+        let content = "#project\n`#project` and [#project](#project)\n```\n#project\n```\n";
+        let renamed = rename("code", &[("A.md", content)], "project", "work");
+        assert_eq!(
+            renamed[0].1,
+            "#work\n`#project` and [#project](#project)\n```\n#project\n```\n"
+        );
+
+        let renamed = rename("code-only", &[("A.md", "`#project`\n")], "project", "work");
+        assert!(renamed.is_empty());
+    }
+}
@@ -0,0 +1,426 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::markdown::prose_lines;
+use super::properties::is_date;
+use super::sections::heading_of;
+use super::VaultIndex;
+
+/// State of a checkbox, from the character between the brackets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// `[ ]`
+    Todo,
+    /// `[/]`
+    InProgress,
+    /// `[-]`
+    Cancelled,
+    /// `[x]`, or any other character
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskPriority {
+    Highest,
+    High,
+    Medium,
+    Low,
+    Lowest,
+}
+
+/// A checkbox list item (`- [ ] text`) in a note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    /// Text after the checkbox, as written
+    pub text: String,
+    pub status: TaskStatus,
+    /// 1-based line number
+    pub line: usize,
+    /// Nearest heading above the task
+    pub heading: Option<String>,
+    /// Due date as `YYYY-MM-DD`
+    pub due: Option<String>,
+    pub priority: Option<TaskPriority>,
+    /// Byte range of the character between the brackets
+    #[serde(skip)]
+    pub checkbox: std::ops::Range<usize>,
+}
+
+/// Which tasks to return
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    /// Only tasks in one of these states; all states if empty
+    pub status: Vec<TaskStatus>,
+    /// Only tasks in notes below this folder
+    pub folder: Option<PathBuf>,
+    /// Only tasks due on or after this date
+    pub due_from: Option<String>,
+    /// Only tasks due on or before this date
+    pub due_to: Option<String>,
+}
+
+/// A task with the note it is in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskItem {
+    pub path: String,
+    #[serde(flatten)]
+    pub task: Task,
+}
+
+/// Every checkbox list item in a note, outside frontmatter and code
+///
+/// Due dates are read from `📅 2024-05-01`, `due: 2024-05-01` and
+/// `[due:: 2024-05-01]`; priorities from the emoji markers (`🔺⏫🔼🔽⏬`) and
+/// `[priority:: high]`.
+pub fn parse_tasks(content: &str) -> Vec<Task> {
+    let mut heading = None;
+    let mut tasks = Vec::new();
+
+    for line in prose_lines(content) {
+        if let Some((_, text)) = heading_of(line.text) {
+            heading = Some(text);
+            continue;
+        }
+        let Some((checkbox, text)) = checkbox_of(line.text) else {
+            continue;
+        };
+        let mark = line.text[checkbox.clone()].chars().next().unwrap_or(' ');
+
+        tasks.push(Task {
+            text: text.trim().to_string(),
+            status: match mark {
+                ' ' => TaskStatus::Todo,
+                '/' => TaskStatus::InProgress,
+                '-' => TaskStatus::Cancelled,
+                _ => TaskStatus::Done,
+            },
+            line: line.number,
+            heading: heading.clone(),
+            due: due_date(text),
+            priority: priority(text),
+            checkbox: line.start + checkbox.start..line.start + checkbox.end,
+        });
+    }
+    tasks
+}
+
+/// Why a task could not be toggled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToggleError {
+    /// There is no task on the line
+    NotATask,
+    /// The task on the line is not the one that was shown, e.g. the note was
+    /// edited or toggled elsewhere since it was read
+    Changed,
+}
+
+/// Flip the checkbox of the task on a line: done when open or in progress, open
+/// otherwise
+///
+/// The task must still have the text and status it was shown with. Only the
+/// character between the brackets changes. Returns the new content and the
+/// task as it is afterwards.
+pub fn toggle_task(
+    content: &str,
+    line: usize,
+    text: &str,
+    status: TaskStatus,
+) -> Result<(String, Task), ToggleError> {
+    let task = parse_tasks(content)
+        .into_iter()
+        .find(|t| t.line == line)
+        .ok_or(ToggleError::NotATask)?;
+    if task.text != text.trim() || task.status != status {
+        return Err(ToggleError::Changed);
+    }
+    let mark = match task.status {
+        TaskStatus::Todo | TaskStatus::InProgress => "x",
+        TaskStatus::Done | TaskStatus::Cancelled => " ",
+    };
+
+    let mut updated = String::with_capacity(content.len());
+    updated.push_str(&content[..task.checkbox.start]);
+    updated.push_str(mark);
+    updated.push_str(&content[task.checkbox.end..]);
+
+    let toggled = parse_tasks(&updated)
+        .into_iter()
+        .find(|t| t.line == line)
+        .ok_or(ToggleError::NotATask)?;
+    Ok((updated, toggled))
+}
+
+impl VaultIndex {
+    /// Tasks matching a filter, ordered by note and line
+    ///
+    /// A date range leaves out tasks without a due date.
+    pub fn tasks(&self, filter: &TaskFilter) -> Vec<TaskItem> {
+        let mut paths: Vec<&PathBuf> = self
+            .notes
+            .keys()
+            .filter(|path| {
+                filter
+                    .folder
+                    .as_deref()
+                    .is_none_or(|folder| path.starts_with(folder))
+            })
+            .collect();
+        paths.sort();
+
+        let mut items = Vec::new();
+        for path in paths {
+            items.extend(
+                self.notes[path]
+                    .tasks
+                    .iter()
+                    .filter(|task| task_matches(task, filter))
+                    .map(|task| TaskItem {
+                        path: self.shown(path),
+                        task: task.clone(),
+                    }),
+            );
+        }
+        items
+    }
+}
+
+fn task_matches(task: &Task, filter: &TaskFilter) -> bool {
+    if !filter.status.is_empty() && !filter.status.contains(&task.status) {
+        return false;
+    }
+    if filter.due_from.is_none() && filter.due_to.is_none() {
+        return true;
+    }
+    let Some(due) = task.due.as_deref() else {
+        return false;
+    };
+    filter.due_from.as_deref().is_none_or(|from| due >= from)
+        && filter.due_to.as_deref().is_none_or(|to| due <= to)
+}
+
+/// Byte range of the checkbox mark and the text after it, for `- [ ] text`
+fn checkbox_of(text: &str) -> Option<(std::ops::Range<usize>, &str)> {
+    let indent = text.len() - text.trim_start().len();
+    let rest = &text[indent..];
+
+    let marker = if rest.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        if digits == 0 || !rest[digits..].starts_with(['.', ')']) {
+            return None;
+        }
+        digits + 1
+    };
+    let after = rest[marker..].strip_prefix(' ')?.trim_start_matches(' ');
+    let open = text.len() - after.len();
+
+    let mut chars = after.chars();
+    let (Some('['), Some(mark), Some(']')) = (chars.next(), chars.next(), chars.next()) else {
+        return None;
+    };
+    let body = chars.as_str();
+    if !(body.is_empty() || body.starts_with([' ', '\t'])) {
+        return None;
+    }
+    Some((open + 1..open + 1 + mark.len_utf8(), body))
+}
+
+fn due_date(text: &str) -> Option<String> {
+    for marker in ["📅", "due::", "due:", "@due("] {
+        let mut rest = text;
+        while let Some(at) = rest.to_ascii_lowercase().find(marker) {
+            let after = rest[at + marker.len()..].trim_start();
+            if let Some(date) = after.get(..10).filter(|date| is_date(date)) {
+                return Some(date.to_string());
+            }
+            rest = &rest[at + marker.len()..];
+        }
+    }
+    None
+}
+
+fn priority(text: &str) -> Option<TaskPriority> {
+    const MARKERS: [(&str, TaskPriority); 5] = [
+        ("🔺", TaskPriority::Highest),
+        ("⏫", TaskPriority::High),
+        ("🔼", TaskPriority::Medium),
+        ("🔽", TaskPriority::Low),
+        ("⏬", TaskPriority::Lowest),
+    ];
+    if let Some((_, priority)) = MARKERS.iter().find(|(marker, _)| text.contains(marker)) {
+        return Some(*priority);
+    }
+
+    let lower = text.to_ascii_lowercase();
+    let at = lower.find("priority::")? + "priority::".len();
+    let word: String = lower[at..]
+        .trim_start()
+        .chars()
+        .take_while(char::is_ascii_alphabetic)
+        .collect();
+    match word.as_str() {
+        "highest" => Some(TaskPriority::Highest),
+        "high" => Some(TaskPriority::High),
+        "medium" => Some(TaskPriority::Medium),
+        "low" => Some(TaskPriority::Low),
+        "lowest" => Some(TaskPriority::Lowest),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggle_finishes_open_and_started_tasks() {
+        let content = "- [ ] open\n- [/] started\n- [x] done\n- [-] dropped\n";
+
+        let (updated, task) = toggle_task(content, 1, "open", TaskStatus::Todo).unwrap();
+        assert!(updated.starts_with("- [x] open\n"));
+        assert_eq!(task.status, TaskStatus::Done);
+
+        let (updated, task) = toggle_task(content, 2, "started", TaskStatus::InProgress).unwrap();
+        assert_eq!(updated.lines().nth(1), Some("- [x] started"));
+        assert_eq!(task.status, TaskStatus::Done);
+
+        let (updated, task) = toggle_task(content, 3, "done", TaskStatus::Done).unwrap();
+        assert_eq!(updated.lines().nth(2), Some("- [ ] done"));
+        assert_eq!(task.status, TaskStatus::Todo);
+
+        let (updated, task) = toggle_task(content, 4, "dropped", TaskStatus::Cancelled).unwrap();
+        assert_eq!(updated.lines().nth(3), Some("- [ ] dropped"));
+        assert_eq!(task.status, TaskStatus::Todo);
+
+        assert_eq!(
+            toggle_task("Just text\n", 1, "Just text", TaskStatus::Todo).unwrap_err(),
+            ToggleError::NotATask
+        );
+    }
+
+    #[test]
+    fn toggle_refuses_a_task_that_changed() {
+        // Toggled elsewhere since the list was loaded
+        let content = "- [x] open\n- [ ] moved up\n";
+        assert_eq!(
+            toggle_task(content, 1, "open", TaskStatus::Todo).unwrap_err(),
+            ToggleError::Changed
+        );
+        // A line was added above, so another task is on the line now
+        assert_eq!(
+            toggle_task(content, 2, "open", TaskStatus::Todo).unwrap_err(),
+            ToggleError::Changed
+        );
+    }
+
+    #[test]
+    fn due_dates_priorities_and_headings() {
+        let content = "---\ntasks: \"- [ ] frontmatter\"\n---\n\
+            - [ ] no heading yet\n\
+            # Week\n\
+            - [ ] report 📅 2024-05-01 ⏫\n\
+            * [/] review [due:: 2024-05-02] [priority:: low]\n\
+            ## Later\n\
+            1. [-] call due: 2024-05-03 🔺\n\
+            + [x] not a date 📅 2024-5-4\n\
+            ```\n- [ ] in a fence\n```\n\
+            -[ ] no space\n- [ ]text\n";
+
+        let tasks: Vec<_> = parse_tasks(content)
+            .into_iter()
+            .map(|t| (t.line, t.text, t.heading, t.due, t.priority))
+            .collect();
+        let owned = |text: &str| Some(text.to_string());
+        assert_eq!(
+            tasks,
+            [
+                (4, "no heading yet".to_string(), None, None, None),
+                (
+                    6,
+                    "report 📅 2024-05-01 ⏫".to_string(),
+                    owned("Week"),
+                    owned("2024-05-01"),
+                    Some(TaskPriority::High)
+                ),
+                (
+                    7,
+                    "review [due:: 2024-05-02] [priority:: low]".to_string(),
+                    owned("Week"),
+                    owned("2024-05-02"),
+                    Some(TaskPriority::Low)
+                ),
+                (
+                    9,
+                    "call due: 2024-05-03 🔺".to_string(),
+                    owned("Later"),
+                    owned("2024-05-03"),
+                    Some(TaskPriority::Highest)
+                ),
+                (
+                    10,
+                    "not a date 📅 2024-5-4".to_string(),
+                    owned("Later"),
+                    None,
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn filters_by_status_and_due_date() {
+        let task = |status: TaskStatus, due: Option<&str>| Task {
+            text: String::new(),
+            status,
+            line: 1,
+            heading: None,
+            due: due.map(str::to_string),
+            priority: None,
+            checkbox: 0..0,
+        };
+        let filter = |status: &[TaskStatus], from: Option<&str>, to: Option<&str>| TaskFilter {
+            status: status.to_vec(),
+            folder: None,
+            due_from: from.map(str::to_string),
+            due_to: to.map(str::to_string),
+        };
+        let open = task(TaskStatus::Todo, Some("2024-05-01"));
+        let undated = task(TaskStatus::Done, None);
+
+        assert!(task_matches(&open, &TaskFilter::default()));
+        assert!(task_matches(&undated, &TaskFilter::default()));
+        assert!(task_matches(
+            &open,
+            &filter(&[TaskStatus::Todo, TaskStatus::InProgress], None, None)
+        ));
+        assert!(!task_matches(
+            &undated,
+            &filter(&[TaskStatus::Todo], None, None)
+        ));
+
+        // Both ends are inclusive
+        assert!(task_matches(&open, &filter(&[], Some("2024-05-01"), None)));
+        assert!(task_matches(&open, &filter(&[], None, Some("2024-05-01"))));
+        assert!(!task_matches(&open, &filter(&[], Some("2024-05-02"), None)));
+        assert!(!task_matches(&open, &filter(&[], None, Some("2024-04-30"))));
+        assert!(task_matches(
+            &open,
+            &filter(&[], Some("2024-04-01"), Some("2024-05-31"))
+        ));
+        // Any date bound leaves out undated tasks
+        assert!(!task_matches(
+            &undated,
+            &filter(&[], None, Some("2099-12-31"))
+        ));
+        // Status and dates must both match
+        assert!(!task_matches(
+            &open,
+            &filter(&[TaskStatus::Done], Some("2024-04-01"), None)
+        ));
+    }
+}
@@ -0,0 +1,349 @@
+use crate::error::{CommandError, CommandResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A buffer checkpoint as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub path: String,
+    pub content: String,
+    /// Milliseconds since the Unix epoch
+    pub saved_at: u64,
+}
+
+/// A journaled buffer that is newer than the note on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoverableBuffer {
+    pub path: String,
+    pub content: String,
+    pub saved_at: u64,
+    /// Modification time of the note on disk, absent if the note was deleted
+    pub file_modified_at: Option<u64>,
+}
+
+/// Crash recovery journal for unsaved editor buffers
+///
+/// Each note gets one small JSON file in the journal directory, replaced atomically
+/// on every checkpoint. Entries are removed once the same content reaches the note.
+///
+/// Checkpoints carry a sequence number per note. Each one is written to its own
+/// temp file, and only renamed over the entry if nothing newer landed first, so
+/// overlapping checkpoints of one note never leave an older buffer behind.
+pub struct Journal {
+    dir: PathBuf,
+    entries: Mutex<HashMap<PathBuf, EntryState>>,
+}
+
+#[derive(Default)]
+struct EntryState {
+    /// Highest sequence number accepted so far
+    latest_seq: u64,
+    /// Sequence number of the checkpoint in the entry file
+    persisted_seq: u64,
+}
+
+impl Journal {
+    pub fn new(dir: PathBuf) -> Self {
+        Journal {
+            dir,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record the current buffer for a note
+    ///
+    /// Without an explicit `seq` the checkpoint is ordered after everything seen
+    /// so far. A checkpoint older than one already accepted is dropped.
+    pub fn checkpoint(&self, path: &Path, content: String, seq: Option<u64>) -> CommandResult<()> {
+        let entry_path = self.entry_path(path);
+        let seq = {
+            let mut entries = self.lock()?;
+            let state = entries.entry(entry_path.clone()).or_default();
+            let seq = seq.unwrap_or(state.latest_seq + 1);
+            if seq <= state.latest_seq {
+                return Ok(());
+            }
+            state.latest_seq = seq;
+            seq
+        };
+
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| CommandError::io("Failed to create recovery journal", &self.dir, e))?;
+
+        let entry = JournalEntry {
+            path: path.to_string_lossy().to_string(),
+            content,
+            saved_at: now_millis(),
+        };
+        let data = serde_json::to_vec(&entry)
+            .map_err(|e| CommandError::internal(format!("Failed to encode checkpoint: {}", e)))?;
+
+        // Write to a temp file of our own, so a crash mid-write leaves the
+        // previous checkpoint intact and concurrent checkpoints can't mix
+        let temp_path = entry_path.with_extension(format!("{}.tmp", seq));
+        let write = || -> std::io::Result<()> {
+            let mut file = fs::File::create(&temp_path)?;
+            file.write_all(&data)?;
+            file.sync_all()
+        };
+        if let Err(e) = write() {
+            let _ = fs::remove_file(&temp_path);
+            return Err(CommandError::io(
+                "Failed to write checkpoint",
+                &entry_path,
+                e,
+            ));
+        }
+
+        // Renaming under the lock keeps the entry moving forward only
+        let mut entries = self.lock()?;
+        let state = entries.entry(entry_path.clone()).or_default();
+        if seq <= state.persisted_seq {
+            let _ = fs::remove_file(&temp_path);
+            return Ok(());
+        }
+        if let Err(e) = fs::rename(&temp_path, &entry_path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(CommandError::io(
+                "Failed to write checkpoint",
+                &entry_path,
+                e,
+            ));
+        }
+        state.persisted_seq = seq;
+        Ok(())
+    }
+
+    /// Drop the checkpoint for a note, along with checkpoints still being written
+    pub fn discard(&self, path: &Path) -> CommandResult<()> {
+        let entry_path = self.entry_path(path);
+        let mut entries = self.lock()?;
+        if let Some(state) = entries.get_mut(&entry_path) {
+            state.persisted_seq = state.latest_seq;
+        }
+        match fs::remove_file(&entry_path) {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(CommandError::io(
+                "Failed to discard checkpoint",
+                &entry_path,
+                e,
+            )),
+        }
+    }
+
+    /// Drop the checkpoint for a note if it holds exactly the content just written
+    ///
+    /// Checkpoints are renamed into place under the journal lock, so holding it
+    /// from the read to the removal keeps a newer checkpoint landing in between
+    /// from being removed with the old one.
+    pub fn discard_if_persisted(&self, path: &Path, content: &str) {
+        let entry_path = self.entry_path(path);
+        let _entries = match self.lock() {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("Keeping checkpoint '{}': {}", entry_path.display(), e);
+                return;
+            }
+        };
+        let Some(entry) = read_entry(&entry_path) else {
+            return;
+        };
+        if entry.content == content {
+            if let Err(e) = fs::remove_file(&entry_path) {
+                log::warn!(
+                    "Failed to remove checkpoint '{}': {}",
+                    entry_path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    /// List checkpoints that are newer than their note and differ from it
+    ///
+    /// Checkpoints that are already reflected on disk are cleaned up along the way.
+    /// `include` decides which notes the caller may see (e.g. only open vaults).
+    pub fn recoverable(&self, include: impl Fn(&Path) -> bool) -> Vec<RecoverableBuffer> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut buffers = Vec::new();
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            match entry_path.extension().and_then(|e| e.to_str()) {
+                Some("json") => {}
+                // Left behind by a crash mid-checkpoint; the entry is still intact
+                Some("tmp") => {
+                    if !self.in_flight(&entry_path) {
+                        let _ = fs::remove_file(&entry_path);
+                    }
+                    continue;
+                }
+                _ => continue,
+            }
+            let Some(journal) = read_entry(&entry_path) else {
+                log::warn!("Skipping unreadable checkpoint: {}", entry_path.display());
+                continue;
+            };
+
+            let note_path = Path::new(&journal.path);
+            if !include(note_path) {
+                continue;
+            }
+
+            let file_modified_at = fs::metadata(note_path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(system_time_millis);
+
+            let is_newer = file_modified_at.is_none_or(|modified| journal.saved_at > modified);
+            let differs = is_newer
+                && fs::read_to_string(note_path)
+                    .map(|disk| disk != journal.content)
+                    .unwrap_or(true);
+
+            if differs {
+                buffers.push(RecoverableBuffer {
+                    path: journal.path,
+                    content: journal.content,
+                    saved_at: journal.saved_at,
+                    file_modified_at,
+                });
+            } else if let Err(e) = fs::remove_file(&entry_path) {
+                log::warn!(
+                    "Failed to prune checkpoint '{}': {}",
+                    entry_path.display(),
+                    e
+                );
+            }
+        }
+
+        buffers.sort_by_key(|buffer| std::cmp::Reverse(buffer.saved_at));
+        buffers
+    }
+
+    /// Whether a checkpoint for the entry a temp file belongs to is being written
+    fn in_flight(&self, temp_path: &Path) -> bool {
+        let name = temp_path.file_name().unwrap_or_default().to_string_lossy();
+        let hash = name.split('.').next().unwrap_or_default();
+        let entry_path = self.dir.join(format!("{}.json", hash));
+        self.lock().is_ok_and(|entries| {
+            entries
+                .get(&entry_path)
+                .is_some_and(|state| state.latest_seq > state.persisted_seq)
+        })
+    }
+
+    fn lock(&self) -> CommandResult<MutexGuard<'_, HashMap<PathBuf, EntryState>>> {
+        self.entries
+            .lock()
+            .map_err(|e| CommandError::internal(format!("Failed to lock recovery journal: {}", e)))
+    }
+
+    fn entry_path(&self, path: &Path) -> PathBuf {
+        self.dir.join(format!(
+            "{:016x}.json",
+            fnv1a(path.to_string_lossy().as_bytes())
+        ))
+    }
+}
+
+fn read_entry(entry_path: &Path) -> Option<JournalEntry> {
+    let data = fs::read(entry_path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// FNV-1a, used for entry file names because it is stable across Rust releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn now_millis() -> u64 {
+    system_time_millis(SystemTime::now())
+}
+
+fn system_time_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn older_checkpoint_never_replaces_newer() {
+        let dir = std::env::temp_dir().join(format!("yana-journal-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let journal = Journal::new(dir.join("recovery"));
+        let note = dir.join("note.md");
+
+        journal.checkpoint(&note, "second".into(), Some(2)).unwrap();
+        // Arrives late, after the newer buffer was checkpointed
+        journal.checkpoint(&note, "first".into(), Some(1)).unwrap();
+
+        let buffers = journal.recoverable(|_| true);
+        assert_eq!(buffers.len(), 1);
+        assert_eq!(buffers[0].content, "second");
+
+        // Each checkpoint wrote its own temp file and none is left behind
+        let leftovers: Vec<_> = fs::read_dir(dir.join("recovery"))
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|e| e == "tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+
+        // Without a sequence number the checkpoint comes after everything seen
+        journal.checkpoint(&note, "third".into(), None).unwrap();
+        assert_eq!(journal.recoverable(|_| true)[0].content, "third");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn saved_and_discarded_buffers_are_not_recoverable() {
+        let dir = std::env::temp_dir().join(format!("yana-journal-saved-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let journal = Journal::new(dir.join("recovery"));
+        let saved = dir.join("saved.md");
+        let discarded = dir.join("discarded.md");
+        let other = dir.join("other.md");
+
+        journal.checkpoint(&saved, "typed".into(), Some(1)).unwrap();
+        fs::write(&saved, "typed").unwrap();
+        journal.discard_if_persisted(&saved, "typed");
+
+        journal
+            .checkpoint(&discarded, "typed".into(), Some(1))
+            .unwrap();
+        journal.discard(&discarded).unwrap();
+        // A checkpoint issued before the discard must not bring the buffer back
+        journal
+            .checkpoint(&discarded, "typed".into(), Some(1))
+            .unwrap();
+
+        journal.checkpoint(&other, "typed".into(), Some(1)).unwrap();
+        assert!(journal.recoverable(|path| path != other).is_empty());
+        assert_eq!(journal.recoverable(|_| true).len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,21 @@
 mod commands;
+mod dates;
+mod error;
+mod index;
+mod journal;
+mod sandbox;
+mod templates;
+mod vault_lock;
+mod write_queue;
 
-use commands::WatcherState;
+use commands::{PreviewCache, WatcherState};
+use index::VaultIndex;
+use journal::Journal;
+use sandbox::VaultSandbox;
 use std::sync::Mutex;
+use tauri::{Manager, RunEvent};
+use vault_lock::VaultLocks;
+use write_queue::WriteQueue;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -15,12 +29,26 @@ pub fn run() {
             watcher: None,
             watching_path: None,
         }))
+        .manage(Mutex::new(VaultSandbox::default()))
+        .manage(WriteQueue::default())
+        .manage(Mutex::new(VaultLocks::default()))
+        .manage(Mutex::new(VaultIndex::default()))
+        .manage(Mutex::new(PreviewCache::default()))
         .plugin(
             tauri_plugin_log::Builder::default()
                 .level(log::LevelFilter::Info)
                 .build(),
         )
-        .setup(|_app| Ok(()))
+        .setup(|app| {
+            let journal_dir = app.path().app_data_dir()?.join(commands::JOURNAL_DIR);
+            app.manage(Journal::new(journal_dir));
+
+            // Vault roots must be known before the frontend issues its first file command
+            if let Err(e) = commands::load_vault_roots(app.handle()) {
+                log::error!("Failed to load vault roots: {}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::scan_directory,
             commands::read_file,
@@ -33,7 +61,58 @@ pub fn run() {
             commands::start_watching,
             commands::stop_watching,
             commands::save_image_to_attachments,
+            commands::choose_vault_folder,
+            commands::sync_vault_roots,
+            commands::checkpoint_buffer,
+            commands::discard_buffer,
+            commands::list_recoverable_buffers,
+            commands::get_vault_lock_status,
+            commands::acquire_vault_lock,
+            commands::release_vault_lock,
+            commands::get_backlinks,
+            commands::get_outgoing_links,
+            commands::find_broken_links,
+            commands::find_unlinked_mentions,
+            commands::link_mentions,
+            commands::get_heading_renames,
+            commands::update_heading_links,
+            commands::dismiss_heading_renames,
+            commands::convert_links,
+            commands::get_property_keys,
+            commands::get_note_properties,
+            commands::find_notes_by_property,
+            commands::set_note_property,
+            commands::remove_note_property,
+            commands::get_tag_tree,
+            commands::get_notes_for_tag,
+            commands::rename_tag,
+            commands::run_query,
+            commands::get_tasks,
+            commands::toggle_task,
+            commands::open_periodic_note,
+            commands::get_previous_periodic_note,
+            commands::get_next_periodic_note,
+            commands::list_templates,
+            commands::create_note_from_template,
+            commands::get_folder_template,
+            commands::set_folder_template,
+            commands::get_note_outline,
+            commands::get_note_previews,
+            commands::find_orphaned_attachments,
+            commands::trash_attachments,
+            commands::get_graph_data,
+            commands::resolve_embed,
+            commands::get_block_ids,
+            commands::insert_block_id,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Don't leave our vault locks behind for the next launch to clean up
+            if let RunEvent::Exit = event {
+                if let Ok(mut locks) = app.state::<Mutex<VaultLocks>>().lock() {
+                    locks.release_all();
+                }
+            }
+        });
 }
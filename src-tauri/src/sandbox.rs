@@ -0,0 +1,297 @@
+use crate::error::{CommandError, CommandResult};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Set of vault roots that file commands are allowed to touch
+///
+/// Roots are stored canonicalized, so a path is inside a vault exactly when its
+/// canonical form starts with one of the roots.
+#[derive(Debug, Default)]
+pub struct VaultSandbox {
+    roots: Vec<PathBuf>,
+    /// Each root as it was registered, which is how the frontend names paths
+    registered: Vec<PathBuf>,
+}
+
+impl VaultSandbox {
+    /// Replace the registered roots, returning the canonical roots that were accepted
+    pub fn set_roots(&mut self, roots: &[String]) -> Vec<PathBuf> {
+        let mut accepted: Vec<PathBuf> = Vec::new();
+        let mut registered: Vec<PathBuf> = Vec::new();
+
+        for root in roots {
+            match fs::canonicalize(root) {
+                Ok(canonical) if canonical.is_dir() => {
+                    // The filesystem root would make the sandbox meaningless
+                    if canonical.parent().is_none() {
+                        log::warn!("Refusing to register filesystem root as a vault: {}", root);
+                        continue;
+                    }
+                    if !accepted.contains(&canonical) {
+                        accepted.push(canonical);
+                        registered.push(PathBuf::from(root));
+                    }
+                }
+                Ok(_) => log::warn!("Vault root is not a directory: {}", root),
+                Err(e) => log::warn!("Failed to resolve vault root '{}': {}", root, e),
+            }
+        }
+
+        self.roots = accepted.clone();
+        self.registered = registered;
+        accepted
+    }
+
+    /// Registered canonical vault roots
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Resolve a path, following every symlink, and ensure it lies inside a vault
+    /// Use this for anything that reads or writes through the path
+    pub fn resolve(&self, path: &str) -> CommandResult<PathBuf> {
+        let resolved = canonicalize_lenient(Path::new(path))
+            .map_err(|e| CommandError::io("Failed to resolve path", path, e))?;
+        self.ensure_inside(path, resolved)
+    }
+
+    /// Resolve a path without following its final component, and ensure it lies
+    /// inside a vault. Use this for operations on the entry itself (delete, rename)
+    /// so a symlink is acted on rather than its target. Vault roots themselves are
+    /// rejected.
+    pub fn resolve_entry(&self, path: &str) -> CommandResult<PathBuf> {
+        let raw = Path::new(path);
+        let file_name = match raw.components().next_back() {
+            Some(Component::Normal(name)) => name.to_owned(),
+            _ => return Err(CommandError::invalid_path(path, "Invalid path")),
+        };
+        let parent = raw
+            .parent()
+            .ok_or_else(|| CommandError::invalid_path(path, "Invalid path"))?;
+
+        let mut resolved = canonicalize_lenient(parent)
+            .map_err(|e| CommandError::io("Failed to resolve path", path, e))?;
+        resolved.push(file_name);
+
+        if self.roots.contains(&resolved) {
+            return Err(CommandError::invalid_path(
+                path,
+                "Refusing to modify vault root",
+            ));
+        }
+        self.ensure_inside(path, resolved)
+    }
+
+    /// A resolved path under its vault root as registered rather than canonical
+    ///
+    /// Canonical paths are `\\?\` paths on Windows and bypass symlinks in the
+    /// vault path, so they don't compare equal to the paths the file tree
+    /// shows. Paths outside every vault are returned as they are.
+    pub fn shown(&self, path: &Path) -> PathBuf {
+        self.roots
+            .iter()
+            .zip(&self.registered)
+            .filter(|(root, _)| path.starts_with(root))
+            .max_by_key(|(root, _)| root.components().count())
+            .and_then(|(root, registered)| {
+                let relative = path.strip_prefix(root).ok()?;
+                Some(if relative.as_os_str().is_empty() {
+                    registered.clone()
+                } else {
+                    registered.join(relative)
+                })
+            })
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// Whether an already-canonical path lies inside a registered vault
+    pub fn contains(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| path.starts_with(root))
+    }
+
+    fn ensure_inside(&self, original: &str, resolved: PathBuf) -> CommandResult<PathBuf> {
+        if self.contains(&resolved) {
+            Ok(resolved)
+        } else {
+            Err(CommandError::outside_vault(original))
+        }
+    }
+}
+
+/// Canonicalize a path that may not exist yet
+///
+/// The longest existing prefix is canonicalized by the OS, which resolves symlinks
+/// and `..` the same way the filesystem would. The remaining, not yet existing
+/// components must be plain names so they cannot climb back out.
+fn canonicalize_lenient(path: &Path) -> io::Result<PathBuf> {
+    if !path.is_absolute() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "path must be absolute",
+        ));
+    }
+
+    let mut existing = path.to_path_buf();
+    let mut missing: Vec<std::ffi::OsString> = Vec::new();
+
+    loop {
+        match fs::canonicalize(&existing) {
+            Ok(mut canonical) => {
+                for name in missing.iter().rev() {
+                    canonical.push(name);
+                }
+                return Ok(canonical);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                // A dangling symlink is missing to canonicalize, but a write
+                // through it would land wherever it points
+                if fs::symlink_metadata(&existing).is_ok() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "path goes through a dangling symlink",
+                    ));
+                }
+                match existing.components().next_back() {
+                    Some(Component::Normal(name)) => missing.push(name.to_owned()),
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "path contains '.' or '..' after a missing component",
+                        ))
+                    }
+                }
+                if !existing.pop() {
+                    return Err(e);
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Read the vault roots the user picked in the folder dialog
+/// `None` when the file does not exist yet
+///
+/// Only lines holding an absolute path count. Nothing in the file marks it as
+/// written by the backend; what the store plugin writes is JSON, and no line of
+/// JSON is an absolute path, so it can't add a root.
+pub fn read_granted_roots(file: &Path) -> io::Result<Option<Vec<PathBuf>>> {
+    let content = match fs::read_to_string(file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    Ok(Some(
+        content
+            .lines()
+            .map(Path::new)
+            .filter(|path| path.is_absolute())
+            .map(Path::to_path_buf)
+            .collect(),
+    ))
+}
+
+/// Persist the vault roots the user picked, one canonical path per line
+pub fn write_granted_roots(file: &Path, roots: &[PathBuf]) -> io::Result<()> {
+    let mut content = String::new();
+    for root in roots {
+        let root = root.to_string_lossy();
+        if root.contains('\n') {
+            log::warn!("Not persisting vault root with a line break: {}", root);
+            continue;
+        }
+        content.push_str(&root);
+        content.push('\n');
+    }
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(file, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yana-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::canonicalize(dir).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dangling_symlink_is_rejected() {
+        let dir = temp_dir("sandbox");
+        let vault = dir.join("vault");
+        fs::create_dir(&vault).unwrap();
+        std::os::unix::fs::symlink(dir.join("outside.md"), vault.join("link.md")).unwrap();
+
+        let mut sandbox = VaultSandbox::default();
+        sandbox.set_roots(&[vault.to_string_lossy().to_string()]);
+        assert!(sandbox
+            .resolve(&vault.join("link.md").to_string_lossy())
+            .is_err());
+        assert!(sandbox
+            .resolve(&vault.join("link.md/child.md").to_string_lossy())
+            .is_err());
+        assert_eq!(
+            sandbox
+                .resolve(&vault.join("new/note.md").to_string_lossy())
+                .unwrap(),
+            vault.join("new/note.md")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolved_paths_are_shown_under_the_registered_root() {
+        let dir = temp_dir("shown");
+        let vault = dir.join("vault");
+        fs::create_dir_all(vault.join("inner")).unwrap();
+        let link = dir.join("link");
+        std::os::unix::fs::symlink(&vault, &link).unwrap();
+
+        let mut sandbox = VaultSandbox::default();
+        sandbox.set_roots(&[
+            link.to_string_lossy().to_string(),
+            vault.join("inner").to_string_lossy().to_string(),
+            // The same vault again by its real path keeps the first registration
+            vault.to_string_lossy().to_string(),
+        ]);
+
+        assert_eq!(sandbox.shown(&vault), link);
+        assert_eq!(sandbox.shown(&vault.join("a/b.md")), link.join("a/b.md"));
+        assert_eq!(
+            sandbox.shown(&vault.join("inner/c.md")),
+            vault.join("inner/c.md")
+        );
+        assert_eq!(sandbox.shown(&dir.join("x.md")), dir.join("x.md"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn granted_roots_are_absolute_paths() {
+        let dir = temp_dir("granted");
+        let file = dir.join("vault-roots");
+        assert!(read_granted_roots(&file).unwrap().is_none());
+
+        let roots = vec![dir.join("a b"), dir.join("c")];
+        write_granted_roots(&file, &roots).unwrap();
+        assert_eq!(read_granted_roots(&file).unwrap(), Some(roots));
+
+        // What the store plugin would write
+        fs::write(&file, "{\n  \"/\": 1\n}").unwrap();
+        assert_eq!(read_granted_roots(&file).unwrap(), Some(Vec::new()));
+        fs::write(&file, "{\"/etc\":\"/\"}").unwrap();
+        assert_eq!(read_granted_roots(&file).unwrap(), Some(Vec::new()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
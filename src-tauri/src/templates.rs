@@ -0,0 +1,94 @@
+//! Variables in note templates
+//!
+//! `{{title}}` is the name of the new note, `{{folder}}` the name of the folder
+//! it is in, `{{date}}` and `{{time}}` the moment it is created as `YYYY-MM-DD`
+//! and `HH:mm`. A format after a colon, as in `{{date:dddd, MMMM Do}}`, replaces
+//! the default; see [`crate::dates`] for the tokens. `{{cursor}}`, which other
+//! editors use to place the caret, expands to nothing. Spaces inside the braces
+//! are ignored and unknown variables are left as written.
+
+use chrono::NaiveDateTime;
+
+use crate::dates::format_date;
+
+/// What the variables of a template expand to
+pub struct TemplateContext<'a> {
+    pub title: &'a str,
+    pub folder: &'a str,
+    pub now: NaiveDateTime,
+}
+
+/// Replace the variables in a template
+pub fn expand_template(template: &str, context: &TemplateContext) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open + 2..].find("}}").map(|close| open + 2 + close) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+
+        let variable = rest[open + 2..close].trim();
+        let (name, format) = match variable.split_once(':') {
+            Some((name, format)) => (name.trim(), Some(format.trim())),
+            None => (variable, None),
+        };
+        match (name, format) {
+            ("title", None) => out.push_str(context.title),
+            ("folder", None) => out.push_str(context.folder),
+            ("cursor", None) => {}
+            ("date", format) => {
+                out.push_str(&format_date(&context.now, format.unwrap_or("YYYY-MM-DD")))
+            }
+            ("time", format) => out.push_str(&format_date(&context.now, format.unwrap_or("HH:mm"))),
+            _ => out.push_str(&rest[open..close + 2]),
+        }
+        rest = &rest[close + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn expand(template: &str) -> String {
+        let context = TemplateContext {
+            title: "Weekly Review",
+            folder: "Journal",
+            now: NaiveDate::from_ymd_opt(2024, 3, 5)
+                .unwrap()
+                .and_hms_opt(9, 7, 0)
+                .unwrap(),
+        };
+        expand_template(template, &context)
+    }
+
+    #[test]
+    fn title_folder_and_cursor() {
+        assert_eq!(
+            expand("# {{title}}\nIn {{ folder }}\n{{cursor}}"),
+            "# Weekly Review\nIn Journal\n"
+        );
+        // Formats only apply to dates and times
+        assert_eq!(expand("{{title:upper}}"), "{{title:upper}}");
+    }
+
+    #[test]
+    fn date_and_time_formats() {
+        assert_eq!(expand("{{date}} {{time}}"), "2024-03-05 09:07");
+        assert_eq!(expand("{{date:dddd, MMMM Do}}"), "Tuesday, March 5th");
+        assert_eq!(expand("{{ date : DD/MM/YY }}"), "05/03/24");
+        assert_eq!(expand("{{time:h:mm A}}"), "9:07 AM");
+    }
+
+    #[test]
+    fn unknown_and_unclosed_variables_stay() {
+        assert_eq!(expand("{{author}} {{title}}"), "{{author}} Weekly Review");
+        assert_eq!(expand("{{title}} {{date"), "Weekly Review {{date");
+        assert_eq!(expand("{}{{}}"), "{}{{}}");
+    }
+}
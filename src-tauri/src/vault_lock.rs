@@ -0,0 +1,512 @@
+use crate::error::{CommandError, CommandResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Hidden folder inside a vault for Yana's own bookkeeping
+/// Hidden entries are skipped by the scanner, so it never shows up in the tree
+pub const VAULT_META_DIR: &str = ".yana";
+
+/// Lock file name inside the meta folder
+const LOCK_FILE: &str = "vault.lock";
+
+/// Contents of a vault lock file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockOwner {
+    pub pid: u32,
+    pub host: String,
+    /// Distinguishes this process from an unrelated one that reused the same PID
+    pub instance_id: String,
+    /// Milliseconds since the Unix epoch
+    pub acquired_at: u64,
+}
+
+/// Who holds a vault's lock, from this instance's point of view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockState {
+    /// Nobody holds the lock
+    Free,
+    /// This instance holds the lock
+    Ours,
+    /// Another live instance holds the lock, or one on another host we can't check
+    Held,
+    /// The lock was left behind by a process that is no longer running
+    Stale,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockStatus {
+    pub state: LockState,
+    pub owner: Option<LockOwner>,
+}
+
+/// Advisory locks this instance holds on vaults
+pub struct VaultLocks {
+    me: LockOwner,
+    held: HashSet<PathBuf>,
+    /// Vaults whose lock file was last read to be ours, with the modification
+    /// time it had then
+    verified: HashMap<PathBuf, SystemTime>,
+}
+
+impl Default for VaultLocks {
+    fn default() -> Self {
+        let pid = std::process::id();
+        let started = now_millis();
+        VaultLocks {
+            me: LockOwner {
+                pid,
+                host: hostname(),
+                instance_id: format!("{:x}-{:x}", pid, started),
+                acquired_at: started,
+            },
+            held: HashSet::new(),
+            verified: HashMap::new(),
+        }
+    }
+}
+
+impl VaultLocks {
+    /// Inspect the lock of a vault without changing it
+    pub fn status(&self, root: &Path) -> LockStatus {
+        let owner = read_lock(&lock_path(root));
+        let state = match &owner {
+            None => LockState::Free,
+            Some(owner) => self.classify(owner),
+        };
+        LockStatus { state, owner }
+    }
+
+    /// Whether this instance holds the lock of a vault
+    ///
+    /// The lock file is only read again when its modification time changed, or
+    /// after [`VaultLocks::lock_changed`] reported a change to it, so this is
+    /// cheap enough to call before every write. Checking the time notices a
+    /// takeover even in vaults the watcher doesn't cover.
+    pub fn owns(&mut self, root: &Path) -> bool {
+        let modified = lock_modified(root);
+        if modified.is_some() && self.verified.get(root) == modified.as_ref() {
+            return true;
+        }
+        self.verified.remove(root);
+        let ours = self.status(root).state == LockState::Ours;
+        if let (true, Some(modified)) = (ours, modified) {
+            self.verified.insert(root.to_path_buf(), modified);
+        }
+        ours
+    }
+
+    /// Note that a file inside a vault's meta folder changed, so its lock is
+    /// read again the next time ownership is checked
+    pub fn lock_changed(&mut self, path: &Path) {
+        self.verified
+            .retain(|root, _| !path.starts_with(root.join(VAULT_META_DIR)));
+    }
+
+    /// Take the lock of a vault
+    ///
+    /// Free and stale locks are taken silently. A lock held by another instance is
+    /// only taken over when `force` is set, otherwise a conflict is returned.
+    pub fn acquire(&mut self, root: &Path, force: bool) -> CommandResult<LockStatus> {
+        let path = lock_path(root);
+        let current = self.status(root);
+        self.verified.remove(root);
+
+        match current.state {
+            LockState::Ours => return Ok(current),
+            LockState::Held if !force => {
+                let owner = current.owner.as_ref().map(describe).unwrap_or_default();
+                return Err(CommandError::conflict(
+                    root,
+                    format!("Vault is already open in {}", owner),
+                ));
+            }
+            LockState::Stale | LockState::Held => {
+                log::warn!(
+                    "Taking over {:?} vault lock '{}' from {}",
+                    current.state,
+                    path.display(),
+                    current.owner.as_ref().map(describe).unwrap_or_default()
+                );
+            }
+            LockState::Free => {}
+        }
+
+        let dir = root.join(VAULT_META_DIR);
+        fs::create_dir_all(&dir)
+            .map_err(|e| CommandError::io("Failed to create vault meta folder", &dir, e))?;
+
+        let owner = LockOwner {
+            acquired_at: now_millis(),
+            ..self.me.clone()
+        };
+        let data = serde_json::to_vec_pretty(&owner)
+            .map_err(|e| CommandError::internal(format!("Failed to encode vault lock: {}", e)))?;
+
+        match current.state {
+            // create_new makes the lock exclusive if two instances race for a free vault
+            LockState::Free => match create_lock(&path, &data) {
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    return Err(CommandError::conflict(
+                        root,
+                        "Vault was locked by another instance at the same time",
+                    ));
+                }
+                Err(e) => return Err(CommandError::io("Failed to write vault lock", &path, e)),
+            },
+            // Replacing the lock in one rename means there is never a moment without
+            // one that a third instance could grab as free
+            _ => {
+                let temp = dir.join(format!("{}.{}.tmp", LOCK_FILE, self.me.instance_id));
+                if let Err(e) = create_lock(&temp, &data).and_then(|_| fs::rename(&temp, &path)) {
+                    let _ = fs::remove_file(&temp);
+                    return Err(CommandError::io("Failed to write vault lock", &path, e));
+                }
+            }
+        }
+
+        // Two instances taking over at once both rename; only the last one keeps it
+        if read_lock(&path).map(|current| current.instance_id) != Some(owner.instance_id.clone()) {
+            return Err(CommandError::conflict(
+                root,
+                "Vault was locked by another instance at the same time",
+            ));
+        }
+
+        self.held.insert(root.to_path_buf());
+        if let Some(modified) = lock_modified(root) {
+            self.verified.insert(root.to_path_buf(), modified);
+        }
+        Ok(LockStatus {
+            state: LockState::Ours,
+            owner: Some(owner),
+        })
+    }
+
+    /// Give up the lock of a vault if this instance holds it
+    pub fn release(&mut self, root: &Path) -> CommandResult<()> {
+        self.held.remove(root);
+        self.verified.remove(root);
+
+        let path = lock_path(root);
+        match read_lock(&path) {
+            Some(owner) if owner.instance_id == self.me.instance_id => fs::remove_file(&path)
+                .map_err(|e| CommandError::io("Failed to remove vault lock", &path, e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Release every lock this instance holds, used on shutdown
+    pub fn release_all(&mut self) {
+        let roots: Vec<PathBuf> = self.held.iter().cloned().collect();
+        for root in roots {
+            if let Err(e) = self.release(&root) {
+                log::warn!(
+                    "Failed to release vault lock for '{}': {}",
+                    root.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    fn classify(&self, owner: &LockOwner) -> LockState {
+        if owner.instance_id == self.me.instance_id {
+            return LockState::Ours;
+        }
+        // Liveness can only be checked for processes on this machine
+        if owner.host != self.me.host {
+            return LockState::Held;
+        }
+        if owner.pid == self.me.pid || !process_alive(owner.pid) {
+            // Our own PID with a different instance id means the PID was reused
+            LockState::Stale
+        } else {
+            LockState::Held
+        }
+    }
+}
+
+fn lock_path(root: &Path) -> PathBuf {
+    root.join(VAULT_META_DIR).join(LOCK_FILE)
+}
+
+/// Modification time of a vault's lock file, `None` if there is none
+fn lock_modified(root: &Path) -> Option<SystemTime> {
+    fs::metadata(lock_path(root))
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+fn create_lock(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+fn read_lock(path: &Path) -> Option<LockOwner> {
+    let data = fs::read(path).ok()?;
+    match serde_json::from_slice(&data) {
+        Ok(owner) => Some(owner),
+        Err(e) => {
+            // A half-written or foreign lock file is treated as owned by nobody we
+            // can check, so it gets reported as held rather than silently replaced
+            log::warn!("Unreadable vault lock '{}': {}", path.display(), e);
+            Some(LockOwner {
+                pid: 0,
+                host: String::new(),
+                instance_id: String::new(),
+                acquired_at: 0,
+            })
+        }
+    }
+}
+
+fn describe(owner: &LockOwner) -> String {
+    if owner.host.is_empty() {
+        "another Yana instance".to_string()
+    } else {
+        format!("Yana (PID {}) on {}", owner.pid, owner.host)
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 performs the permission and existence checks without sending anything
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, STILL_ACTIVE};
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            return false;
+        }
+        let mut code = 0u32;
+        let ok = GetExitCodeProcess(handle, &mut code) != 0;
+        CloseHandle(handle);
+        ok && code == STILL_ACTIVE as u32
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn process_alive(_pid: u32) -> bool {
+    // Without a way to check, never declare a lock stale
+    true
+}
+
+#[cfg(unix)]
+fn hostname() -> String {
+    let mut buf = [0u8; 256];
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if result != 0 {
+        return String::new();
+    }
+    let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).to_string()
+}
+
+#[cfg(not(unix))]
+fn hostname() -> String {
+    std::env::var("COMPUTERNAME").unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_vault(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("yana-lock-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Another instance, on a host whose processes can't be checked
+    fn elsewhere() -> VaultLocks {
+        let mut locks = VaultLocks::default();
+        locks.me.host = format!("{}-elsewhere", locks.me.host);
+        locks.me.instance_id = "elsewhere".to_string();
+        locks
+    }
+
+    /// PID of a process that has exited
+    fn exited_pid() -> u32 {
+        #[cfg(windows)]
+        let mut command = std::process::Command::new("cmd");
+        #[cfg(windows)]
+        command.args(["/C", "exit"]);
+        #[cfg(not(windows))]
+        let mut command = std::process::Command::new("true");
+
+        let mut child = command.spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        pid
+    }
+
+    #[test]
+    fn process_liveness() {
+        assert!(process_alive(std::process::id()));
+        assert!(!process_alive(exited_pid()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_owned_by_someone_else_is_alive() {
+        // PID 1 always exists; unless we run as root, signalling it fails with EPERM
+        assert!(process_alive(1));
+    }
+
+    #[test]
+    fn hostname_is_read() {
+        let host = hostname();
+        assert!(!host.is_empty());
+        assert!(!host.contains('\0'));
+        assert_eq!(host, hostname());
+    }
+
+    #[test]
+    fn lock_held_elsewhere_needs_force() {
+        let root = temp_vault("held");
+        let mut ours = VaultLocks::default();
+        let mut theirs = elsewhere();
+
+        assert_eq!(ours.status(&root).state, LockState::Free);
+        theirs.acquire(&root, false).unwrap();
+        assert_eq!(ours.status(&root).state, LockState::Held);
+
+        let err = ours.acquire(&root, false).unwrap_err();
+        assert!(err.message.contains("already open"));
+        assert!(!ours.owns(&root));
+
+        let status = ours.acquire(&root, true).unwrap();
+        assert_eq!(status.state, LockState::Ours);
+        assert_eq!(ours.status(&root).state, LockState::Ours);
+        assert_eq!(theirs.status(&root).state, LockState::Held);
+
+        // The takeover leaves nothing but the lock itself behind
+        let entries: Vec<_> = fs::read_dir(root.join(VAULT_META_DIR))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, [LOCK_FILE]);
+
+        // Releasing a lock taken over from us leaves the new owner's in place
+        theirs.release(&root).unwrap();
+        assert_eq!(ours.status(&root).state, LockState::Ours);
+        ours.release(&root).unwrap();
+        assert_eq!(ours.status(&root).state, LockState::Free);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn lock_of_exited_process_is_taken_over() {
+        let root = temp_vault("stale");
+        let mut locks = VaultLocks::default();
+
+        let dead = LockOwner {
+            pid: exited_pid(),
+            instance_id: "dead".to_string(),
+            ..locks.me.clone()
+        };
+        fs::create_dir_all(root.join(VAULT_META_DIR)).unwrap();
+        fs::write(lock_path(&root), serde_json::to_vec(&dead).unwrap()).unwrap();
+        assert_eq!(locks.status(&root).state, LockState::Stale);
+
+        let status = locks.acquire(&root, false).unwrap();
+        assert_eq!(status.state, LockState::Ours);
+        assert_eq!(
+            read_lock(&lock_path(&root)).unwrap().instance_id,
+            locks.me.instance_id
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn unreadable_lock_counts_as_held() {
+        let root = temp_vault("garbage");
+        fs::create_dir_all(root.join(VAULT_META_DIR)).unwrap();
+        fs::write(lock_path(&root), "{ half").unwrap();
+
+        let mut locks = VaultLocks::default();
+        assert_eq!(locks.status(&root).state, LockState::Held);
+        assert!(locks.acquire(&root, false).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn ownership_is_read_again_after_a_change() {
+        let root = temp_vault("owns");
+        let mut ours = VaultLocks::default();
+        ours.acquire(&root, false).unwrap();
+        assert!(ours.owns(&root));
+
+        // A takeover the watcher doesn't report, e.g. in a vault it isn't watching
+        let set_modified = |time: SystemTime| {
+            fs::File::options()
+                .write(true)
+                .open(lock_path(&root))
+                .unwrap()
+                .set_modified(time)
+                .unwrap();
+        };
+        let ours_since = lock_modified(&root).unwrap();
+        elsewhere().acquire(&root, true).unwrap();
+        set_modified(ours_since + std::time::Duration::from_secs(10));
+        assert!(!ours.owns(&root));
+
+        // With a timestamp too coarse to tell, the watcher's report still counts
+        ours.acquire(&root, true).unwrap();
+        let ours_since = lock_modified(&root).unwrap();
+        elsewhere().acquire(&root, true).unwrap();
+        set_modified(ours_since);
+        assert!(ours.owns(&root));
+        ours.lock_changed(&root.join("note.md"));
+        assert!(ours.owns(&root));
+        ours.lock_changed(&lock_path(&root));
+        assert!(!ours.owns(&root));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn removed_lock_is_not_owned() {
+        let root = temp_vault("removed");
+        let mut ours = VaultLocks::default();
+        ours.acquire(&root, false).unwrap();
+        assert!(ours.owns(&root));
+
+        fs::remove_file(lock_path(&root)).unwrap();
+        assert!(!ours.owns(&root));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
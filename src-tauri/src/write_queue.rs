@@ -0,0 +1,309 @@
+use crate::error::{CommandError, CommandResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Outcome of a queued write, reported back to the editor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteOutcome {
+    /// Sequence number of the write that was requested
+    pub seq: u64,
+    /// Highest sequence number whose content is now on disk for this path
+    pub persisted_seq: u64,
+    /// Whether this write was dropped because a newer one replaced it
+    pub superseded: bool,
+}
+
+/// Per-path write serialization
+///
+/// Each path has a pending slot and a disk lock. A write first parks its content in
+/// the pending slot, replacing any older content still waiting there, then takes
+/// the disk lock and writes whatever is pending at that moment. Writes that arrive
+/// with a sequence number lower than one already seen are dropped, so the file on
+/// disk can only ever move forward.
+#[derive(Default)]
+pub struct WriteQueue {
+    paths: Mutex<HashMap<PathBuf, Arc<PathQueue>>>,
+}
+
+#[derive(Default)]
+struct PathQueue {
+    state: Mutex<QueueState>,
+    disk: Mutex<()>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// Highest sequence number accepted so far
+    latest_seq: u64,
+    /// Highest sequence number written to disk
+    persisted_seq: u64,
+    /// Newest content waiting for the disk lock
+    pending: Option<PendingWrite>,
+    /// Last failed write, so writes it superseded can report the failure too
+    failed: Option<(u64, CommandError)>,
+}
+
+struct PendingWrite {
+    seq: u64,
+    content: String,
+}
+
+/// What a backend edit run by [`WriteQueue::exclusive`] returns
+pub struct Edit<T> {
+    pub value: T,
+    /// Whether the edit changed any of the files
+    pub written: bool,
+}
+
+impl<T> Edit<T> {
+    /// An edit that changed at least one of the files
+    pub fn written(value: T) -> Self {
+        Edit {
+            value,
+            written: true,
+        }
+    }
+
+    /// An edit that left the files as they were
+    pub fn unchanged(value: T) -> Self {
+        Edit {
+            value,
+            written: false,
+        }
+    }
+}
+
+impl WriteQueue {
+    /// Queue a write and block until it, or a newer write for the same path, is on disk
+    ///
+    /// Without an explicit `seq` the write is ordered after everything seen so far.
+    pub fn write(
+        &self,
+        path: &Path,
+        content: String,
+        seq: Option<u64>,
+    ) -> CommandResult<WriteOutcome> {
+        let queue = self.queue_for(path)?;
+
+        let seq = {
+            let mut state = lock(&queue.state)?;
+            let seq = seq.unwrap_or(state.latest_seq + 1);
+
+            if seq <= state.latest_seq {
+                log::debug!(
+                    "Dropping stale write #{} for '{}' (latest #{})",
+                    seq,
+                    path.display(),
+                    state.latest_seq
+                );
+                return Ok(WriteOutcome {
+                    seq,
+                    persisted_seq: state.persisted_seq,
+                    superseded: true,
+                });
+            }
+
+            state.latest_seq = seq;
+            state.pending = Some(PendingWrite { seq, content });
+            seq
+        };
+
+        let _disk = lock(&queue.disk)?;
+
+        let pending = lock(&queue.state)?.pending.take();
+        let Some(write) = pending else {
+            // A later caller already wrote our content or something newer
+            let state = lock(&queue.state)?;
+            if let Some((failed_seq, err)) = &state.failed {
+                if *failed_seq >= seq && state.persisted_seq < seq {
+                    return Err(err.clone());
+                }
+            }
+            return Ok(WriteOutcome {
+                seq,
+                persisted_seq: state.persisted_seq,
+                superseded: state.persisted_seq > seq,
+            });
+        };
+
+        let result = fs::write(path, &write.content);
+
+        let mut state = lock(&queue.state)?;
+        match result {
+            Ok(_) => {
+                state.persisted_seq = state.persisted_seq.max(write.seq);
+                state.failed = None;
+                Ok(WriteOutcome {
+                    seq,
+                    persisted_seq: state.persisted_seq,
+                    superseded: write.seq != seq,
+                })
+            }
+            Err(e) => {
+                let err = CommandError::io("Failed to write file", path, e);
+                state.failed = Some((write.seq, err.clone()));
+                Err(err)
+            }
+        }
+    }
+
+    /// Run a backend edit of one or more files while no editor write can touch them
+    ///
+    /// Waits for writes in progress and puts content still waiting for the disk on
+    /// disk first, so an edit that reads the file applies on top of the latest
+    /// save. Once `edit` reports that it wrote, each path's sequence moves past
+    /// every write seen so far: editor writes issued before the edit are dropped
+    /// as stale instead of overwriting it, until the editor re-reads the file and
+    /// picks up the new sequence. An edit that wrote nothing leaves the editor's
+    /// writes going.
+    pub fn exclusive<T>(
+        &self,
+        paths: &[&Path],
+        edit: impl FnOnce() -> CommandResult<Edit<T>>,
+    ) -> CommandResult<T> {
+        let mut queues: Vec<(&Path, Arc<PathQueue>)> = paths
+            .iter()
+            .map(|path| Ok((*path, self.queue_for(path)?)))
+            .collect::<CommandResult<_>>()?;
+        // Disk locks are always taken in path order, so two edits can't deadlock
+        queues.sort_by(|a, b| a.0.cmp(b.0));
+        queues.dedup_by(|a, b| a.0 == b.0);
+
+        let _disks = queues
+            .iter()
+            .map(|(_, queue)| lock(&queue.disk))
+            .collect::<CommandResult<Vec<_>>>()?;
+        for (path, queue) in &queues {
+            flush(path, queue)?;
+        }
+
+        let edit = edit()?;
+        if edit.written {
+            for (_, queue) in &queues {
+                let mut state = lock(&queue.state)?;
+                state.latest_seq += 1;
+                state.persisted_seq = state.latest_seq;
+                state.failed = None;
+            }
+        }
+        Ok(edit.value)
+    }
+
+    /// Highest sequence number accepted for a path, which the next write must exceed
+    pub fn latest_seq(&self, path: &Path) -> CommandResult<u64> {
+        let paths = lock(&self.paths)?;
+        match paths.get(path) {
+            Some(queue) => Ok(lock(&queue.state)?.latest_seq),
+            None => Ok(0),
+        }
+    }
+
+    fn queue_for(&self, path: &Path) -> CommandResult<Arc<PathQueue>> {
+        let mut paths = lock(&self.paths)?;
+        Ok(paths.entry(path.to_path_buf()).or_default().clone())
+    }
+}
+
+/// Write content still waiting for the disk; the caller holds the disk lock
+fn flush(path: &Path, queue: &PathQueue) -> CommandResult<()> {
+    let Some(write) = lock(&queue.state)?.pending.take() else {
+        return Ok(());
+    };
+    let result = fs::write(path, &write.content);
+
+    let mut state = lock(&queue.state)?;
+    match result {
+        Ok(_) => {
+            state.persisted_seq = state.persisted_seq.max(write.seq);
+            state.failed = None;
+            Ok(())
+        }
+        Err(e) => {
+            let err = CommandError::io("Failed to write file", path, e);
+            state.failed = Some((write.seq, err.clone()));
+            Err(err)
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> CommandResult<std::sync::MutexGuard<'_, T>> {
+    mutex
+        .lock()
+        .map_err(|e| CommandError::internal(format!("Failed to lock write queue: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_edit_drops_older_editor_writes() {
+        let dir = std::env::temp_dir().join(format!("yana-queue-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("note.md");
+        let queue = WriteQueue::default();
+
+        queue.write(&path, "- [ ] task\n".into(), Some(1)).unwrap();
+        queue
+            .exclusive(&[&path], || {
+                let content = fs::read_to_string(&path).unwrap();
+                fs::write(&path, content.replace("[ ]", "[x]")).unwrap();
+                Ok(Edit::written(()))
+            })
+            .unwrap();
+        let seq = queue.latest_seq(&path).unwrap();
+        assert_eq!(seq, 2);
+
+        // The editor's buffer from before the edit
+        let outcome = queue.write(&path, "- [ ] task\n".into(), Some(2)).unwrap();
+        assert!(outcome.superseded);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "- [x] task\n");
+
+        // After re-reading the note the editor continues past the edit
+        let outcome = queue
+            .write(&path, "- [x] task\nmore\n".into(), Some(seq + 1))
+            .unwrap();
+        assert!(!outcome.superseded);
+
+        // A failed edit leaves the sequence alone
+        let failed: CommandResult<()> =
+            queue.exclusive(&[&path], || Err(CommandError::internal("nope")));
+        assert!(failed.is_err());
+        assert_eq!(queue.latest_seq(&path).unwrap(), seq + 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn edit_that_writes_nothing_keeps_the_editor_current() {
+        let dir = std::env::temp_dir().join(format!("yana-queue-noop-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("note.md");
+        let queue = WriteQueue::default();
+
+        queue.write(&path, "text ^id\n".into(), Some(1)).unwrap();
+        // E.g. asking for the block id of a block that already has one
+        let content = queue
+            .exclusive(&[&path], || {
+                Ok(Edit::unchanged(fs::read_to_string(&path).unwrap()))
+            })
+            .unwrap();
+        assert_eq!(content, "text ^id\n");
+        assert_eq!(queue.latest_seq(&path).unwrap(), 1);
+
+        // The editor's next save still goes through
+        let outcome = queue
+            .write(&path, "text ^id\nmore\n".into(), Some(2))
+            .unwrap();
+        assert!(!outcome.superseded);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "text ^id\nmore\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -4,6 +4,8 @@ import { useEffect, useRef } from 'react';
 import { Crepe } from '@milkdown/crepe';
 import { Milkdown, MilkdownProvider, useEditor } from '@milkdown/react';
 import { convertFileSrc } from '@tauri-apps/api/core';
+import { ask } from '@tauri-apps/plugin-dialog';
+import { toast } from 'sonner';
 import { $prose, $inputRule } from '@milkdown/kit/utils';
 import { schemaCtx } from '@milkdown/kit/core';
 import { Plugin, PluginKey } from '@milkdown/prose/state';
@@ -14,6 +16,11 @@ import { fileTreeService } from "@/services/file-tree-service";
 import { useDebouncedCallback } from "use-debounce";
 import { useFileTree, calculateStats } from "@/contexts/file-tree-context";
 import { performanceTracker } from '@/services/performance-tracker';
+import { recoveryService } from '@/services/recovery-service';
+import { linkService } from '@/services/link-service';
+import { useSettings } from '@/contexts/settings-context';
+import { useVault } from '@/contexts/vault-context';
+import type { HeadingRename } from '@/types/links';
 
 interface MilkdownEditorProps {
   markdown?: string;
@@ -177,15 +184,43 @@ function createImagePastePlugin(
   });
 }
 
+/**
+ * Ask whether links in other notes should follow headings renamed in a note
+ */
+async function promptHeadingLinkUpdate(path: string, renames: HeadingRename[]) {
+  const links = renames.reduce((sum, rename) => sum + rename.links, 0);
+  const list = renames.map((rename) => `"${rename.from}" → "${rename.to}"`).join('\n');
+  const update = await ask(
+    `${list}\n\n${links} link${links === 1 ? '' : 's'} in other notes point at the old heading${renames.length === 1 ? '' : 's'}. Update them?`,
+    { title: 'Heading renamed', kind: 'info', okLabel: 'Update links', cancelLabel: 'Keep' },
+  );
+  if (update) {
+    await linkService.updateHeadingLinks(path);
+  } else {
+    await linkService.dismissHeadingRenames(path);
+  }
+}
+
 const MilkdownEditorInner = ({ markdown, fileId, filePath }: MilkdownEditorProps) => {
   const filePathRef = useRef(filePath);
   const wrapperRef = useRef<HTMLDivElement>(null);
+  const crepeRef = useRef<Crepe | null>(null);
+
+  // The backend refuses changes to a vault locked by another instance
+  const { vaultLock } = useVault();
+  const readOnly = vaultLock?.state === 'held';
+  const readOnlyRef = useRef(readOnly);
 
   // Keep the ref updated with the latest filePath
   useEffect(() => {
     filePathRef.current = filePath;
   }, [filePath]);
 
+  useEffect(() => {
+    readOnlyRef.current = readOnly;
+    crepeRef.current?.setReadonly(readOnly);
+  }, [readOnly]);
+
   // Pre-process markdown: convert relative paths to asset:// URLs for display
   const displayMarkdown = (() => {
     if (!filePath || !markdown) return markdown ?? "";
@@ -201,12 +236,37 @@ const MilkdownEditorInner = ({ markdown, fileId, filePath }: MilkdownEditorProps
     return convertToAssetUrls(markdown, filePath);
   })();
 
-  const saveFile = useDebouncedCallback((path: string, content: string) => {
+  const { settings } = useSettings();
+
+  const saveFile = useDebouncedCallback(async (path: string, content: string) => {
     // Convert asset:// URLs back to relative paths before saving
     const markdownToSave = convertToRelativePaths(content, path);
-    fileTreeService.saveFile(path, markdownToSave);
+    try {
+      const outcome = await fileTreeService.saveFile(
+        path,
+        markdownToSave,
+        settings.autoUpdateHeadingLinks,
+      );
+      if (outcome.headingRenames.length > 0 && !outcome.links) {
+        await promptHeadingLinkUpdate(path, outcome.headingRenames);
+      }
+      if (fileTreeService.isStale(path)) {
+        toast.warning('Note changed outside the editor', {
+          id: `stale-${path}`,
+          description: 'Reopen the note to keep editing; changes since are not saved.',
+          duration: Infinity,
+        });
+      }
+    } catch {
+      // saveFile already told the user what went wrong
+    }
   }, 1000);
 
+  // Checkpoint the buffer more often than we save, so a crash loses at most a moment of typing
+  const checkpointBuffer = useDebouncedCallback((path: string, content: string) => {
+    recoveryService.checkpoint(path, convertToRelativePaths(content, path));
+  }, 200, { maxWait: 500 });
+
   const { updateStats } = useFileTree();
 
   const { loading } = useEditor((root) => {
@@ -259,7 +319,8 @@ const MilkdownEditorInner = ({ markdown, fileId, filePath }: MilkdownEditorProps
 
     crepe.on((listener) => {
       listener.markdownUpdated((ctx, markdown, prevMarkdown) => {
-        if (filePath && markdown !== prevMarkdown) {
+        if (filePath && markdown !== prevMarkdown && !readOnlyRef.current) {
+          checkpointBuffer(filePath, markdown);
           saveFile(filePath, markdown);
 
           // Calculate stats locally and update context
@@ -276,6 +337,8 @@ const MilkdownEditorInner = ({ markdown, fileId, filePath }: MilkdownEditorProps
       performanceTracker.markStart('firstRender');
     }
 
+    crepe.setReadonly(readOnlyRef.current);
+    crepeRef.current = crepe;
     return crepe;
   }, [fileId]);
 
@@ -297,7 +360,7 @@ const MilkdownEditorInner = ({ markdown, fileId, filePath }: MilkdownEditorProps
       const active = document.activeElement;
       const isInput = active instanceof HTMLInputElement || active instanceof HTMLTextAreaElement;
 
-      if (!isInput) {
+      if (!isInput && !readOnlyRef.current) {
         // Small timeout to ensure DOM is ready and selection is stable
         setTimeout(() => {
           const editor = wrapperRef.current?.querySelector('.ProseMirror') as HTMLElement;
@@ -310,12 +373,18 @@ const MilkdownEditorInner = ({ markdown, fileId, filePath }: MilkdownEditorProps
   useEffect(() => {
     if (!filePath) return;
     return () => {
+      checkpointBuffer.flush();
       saveFile.flush();
     };
-  }, [saveFile, fileId, filePath]);
+  }, [saveFile, checkpointBuffer, fileId, filePath]);
 
   return (
     <div ref={wrapperRef} className="milkdown-crepe-wrapper relative mx-auto w-full max-w-[800px] px-12">
+      {readOnly && (
+        <div className="text-muted-foreground border-b py-2 text-sm">
+          Read-only: this vault is open in another Yana instance.
+        </div>
+      )}
       <Milkdown />
     </div>
   );
@@ -10,6 +10,13 @@ import { Input } from "@/components/ui/input";
 import { IconSun, IconMoon, IconDeviceDesktop, IconRefresh, IconLoader, IconCode } from "@tabler/icons-react";
 import { useUpdate } from "@/hooks/use-update";
 import { Button } from "@/components/ui/button";
+import type { Period } from "@/types/periodic";
+
+const PERIODS: { period: Period; label: string }[] = [
+  { period: "daily", label: "Daily" },
+  { period: "weekly", label: "Weekly" },
+  { period: "monthly", label: "Monthly" },
+];
 
 export function GeneralTab() {
   const { theme, setTheme } = useThemeTransition();
@@ -109,6 +116,97 @@ export function GeneralTab() {
 
       <Separator className="opacity-50" />
 
+      {/* Links Section */}
+      <div className="space-y-4">
+        <div className="space-y-1">
+          <h3 className="text-sm font-medium">Links</h3>
+          <p className="text-xs text-muted-foreground">
+            Keep links between notes working as notes change
+          </p>
+        </div>
+
+        <div className="flex items-center justify-between">
+          <div className="space-y-0.5">
+            <Label htmlFor="auto-heading-links" className="text-sm font-normal">
+              Update heading links automatically
+            </Label>
+            <p className="text-xs text-muted-foreground">
+              When you rename a heading, update links to it without asking
+            </p>
+          </div>
+          <Switch
+            id="auto-heading-links"
+            checked={settings.autoUpdateHeadingLinks}
+            onCheckedChange={(checked) => updateSetting('autoUpdateHeadingLinks', checked)}
+          />
+        </div>
+      </div>
+
+      <Separator className="opacity-50" />
+
+      {/* Periodic Notes Section */}
+      <div className="space-y-4">
+        <div className="space-y-1">
+          <h3 className="text-sm font-medium">Periodic Notes</h3>
+          <p className="text-xs text-muted-foreground">
+            Folder relative to the vault, and file name format such as YYYY-MM-DD
+          </p>
+        </div>
+
+        {PERIODS.map(({ period, label }) => (
+          <div key={period} className="grid grid-cols-[5rem_1fr_1fr] items-center gap-2">
+            <Label className="text-sm font-normal">{label}</Label>
+            <Input
+              aria-label={`${label} notes folder`}
+              placeholder="Vault root"
+              value={settings.periodicNotes[period].folder}
+              onChange={(e) =>
+                updateSetting('periodicNotes', {
+                  ...settings.periodicNotes,
+                  [period]: { ...settings.periodicNotes[period], folder: e.target.value },
+                })
+              }
+            />
+            <Input
+              aria-label={`${label} note name format`}
+              value={settings.periodicNotes[period].format}
+              onChange={(e) =>
+                updateSetting('periodicNotes', {
+                  ...settings.periodicNotes,
+                  [period]: { ...settings.periodicNotes[period], format: e.target.value },
+                })
+              }
+            />
+          </div>
+        ))}
+      </div>
+
+      <Separator className="opacity-50" />
+
+      {/* Templates Section */}
+      <div className="space-y-4">
+        <div className="space-y-1">
+          <h3 className="text-sm font-medium">Templates</h3>
+          <p className="text-xs text-muted-foreground">
+            Templates are notes in this vault folder; subfolders hold templates for matching folders
+          </p>
+        </div>
+
+        <div className="grid grid-cols-[5rem_1fr] items-center gap-2">
+          <Label htmlFor="templates-folder" className="text-sm font-normal">
+            Folder
+          </Label>
+          <Input
+            id="templates-folder"
+            placeholder="Templates"
+            value={settings.templatesFolder}
+            onChange={(e) => updateSetting('templatesFolder', e.target.value)}
+          />
+        </div>
+      </div>
+
+      <Separator className="opacity-50" />
+
       {/* Developer Section */}
       <div className="space-y-4">
         <div className="space-y-1">
@@ -6,6 +6,7 @@ import {
   DialogContent,
   DialogHeader,
   DialogTitle,
+  DialogDescription,
   DialogFooter,
 } from "@/components/ui/dialog";
 import { Button } from "@/components/ui/button";
@@ -34,11 +35,15 @@ export function TemplateDialog({
     if (open && folderPath) {
       setIsLoading(true);
       const load = async () => {
-        // Ensure templates are loaded into memory first
-        await templateService.loadTemplates(); 
-        const existing = templateService.getTemplate(folderPath);
-        setContent(existing || "");
-        setIsLoading(false);
+        try {
+          const existing = await templateService.getTemplate(folderPath);
+          setContent(existing || "");
+        } catch (error) {
+          console.error("Failed to load template", error);
+          setContent("");
+        } finally {
+          setIsLoading(false);
+        }
       };
       load();
     }
@@ -71,6 +76,10 @@ export function TemplateDialog({
               {folderName}
             </span>
           </DialogTitle>
+          <DialogDescription>
+            Used for new notes in this folder and its subfolders. {"{{title}}"}, {"{{folder}}"},{" "}
+            {"{{date}}"}, {"{{date:YYYY-MM-DD}}"} and {"{{time}}"} are filled in.
+          </DialogDescription>
         </DialogHeader>
 
         <div className="flex-1 min-h-0 border rounded-md overflow-hidden">
@@ -49,7 +49,7 @@ export function FileTreeProvider({ children }: { children: React.ReactNode }) {
   const [renamingId, setRenamingId] = useState<string | null>(null);
   const [expandedIds, setExpandedIds] = useState<string[]>([]);
   
-  const { currentVault } = useVault();
+  const { currentVault, grantVersion } = useVault();
   const debounceTimerRef = useRef<NodeJS.Timeout | null>(null);
   const selectedFileRef = useRef<FileNode | null>(null);
 
@@ -58,13 +58,6 @@ export function FileTreeProvider({ children }: { children: React.ReactNode }) {
     selectedFileRef.current = selectedFile;
   }, [selectedFile]);
 
-  // Load templates on mount
-  useEffect(() => {
-    templateService.loadTemplates().catch(err => {
-      console.error('Failed to load templates:', err);
-    });
-  }, []);
-
   /**
    * Load file tree from the current vault path.
    * Sets loading state and handles errors.
@@ -239,7 +232,7 @@ export function FileTreeProvider({ children }: { children: React.ReactNode }) {
   /**
    * Create a new markdown note in the specified directory.
    * Automatically selects the new note after creation.
-   * Checks for folder template and applies it if present.
+   * The backend fills the note from the folder's (or a parent folder's) template.
    */
   const createNewNote = useCallback(async (parentPath: string) => {
     try {
@@ -247,12 +240,6 @@ export function FileTreeProvider({ children }: { children: React.ReactNode }) {
       setError(null);
       const newNode = await fileTreeService.createNewNote(parentPath);
 
-      // Check for template
-      const template = templateService.getTemplate(parentPath);
-      if (template) {
-        await fileTreeService.saveFile(newNode.path, template);
-      }
-
       // Set as renaming
       setRenamingId(newNode.id);
       
@@ -270,6 +257,28 @@ export function FileTreeProvider({ children }: { children: React.ReactNode }) {
     }
   }, [selectFile, refresh]);
   
+  /**
+   * Create a new note in the specified directory from one of the templates offered there.
+   * Automatically selects the new note after creation.
+   */
+  const createNoteFromTemplate = useCallback(async (parentPath: string, template: string) => {
+    try {
+      setIsLoading(true);
+      setError(null);
+      const newNode = await templateService.createNoteFromTemplate(parentPath, template);
+
+      setRenamingId(newNode.id);
+      await refresh();
+      await selectFile(newNode);
+    } catch (err) {
+      const error = err instanceof Error ? err : new Error('Failed to create note from template');
+      setError(error);
+      console.error('Failed to create note from template:', error);
+    } finally {
+      setIsLoading(false);
+    }
+  }, [selectFile, refresh]);
+
   /**
    * Create a new folder in the specified directory.
    */
@@ -349,7 +358,16 @@ export function FileTreeProvider({ children }: { children: React.ReactNode }) {
     try {
       setIsLoading(true);
       setError(null);
-      const newNode = await fileTreeService.renamePath(path, newPath);
+      const outcome = await fileTreeService.renamePath(path, newPath);
+      const newNode = outcome.node;
+
+      // Reload the open note if the rename rewrote links inside it
+      const openPath = selectedFile?.path === path ? newNode.path : selectedFile?.path;
+      if (openPath && outcome.links.updated.some((file) => file.path === openPath)) {
+        const response = await fileTreeService.readFile(openPath);
+        setFileContent(response.content);
+        setStats(calculateStats(response.content));
+      }
       
       // If the selected file was renamed (or is inside a renamed folder), update it
       if (selectedFile) {
@@ -389,7 +407,7 @@ export function FileTreeProvider({ children }: { children: React.ReactNode }) {
     }
 
     loadFileTree(currentVault.path);
-  }, [currentVault?.path, loadFileTree]);
+  }, [currentVault?.path, grantVersion, loadFileTree]);
 
   // Set up file watcher when vault loads
   useEffect(() => {
@@ -453,7 +471,7 @@ export function FileTreeProvider({ children }: { children: React.ReactNode }) {
         console.error('Failed to stop file watcher:', err);
       });
     };
-  }, [currentVault?.path, loadFileTree]);
+  }, [currentVault?.path, grantVersion, loadFileTree]);
 
   /**
    * Update the stats of the currently selected file.
@@ -472,6 +490,7 @@ export function FileTreeProvider({ children }: { children: React.ReactNode }) {
     error,
     selectFile,
     createNewNote,
+    createNoteFromTemplate,
     createNewFolder,
     deleteNode,
     duplicateFile,
@@ -492,6 +511,7 @@ export function FileTreeProvider({ children }: { children: React.ReactNode }) {
     error,
     selectFile,
     createNewNote,
+    createNoteFromTemplate,
     createNewFolder,
     deleteNode,
     duplicateFile,
@@ -20,6 +20,13 @@ export function SettingsProvider({ children }: { children: React.ReactNode }) {
     maxTabs: 5,
     autoCheckUpdates: true,
     devMode: false,
+    autoUpdateHeadingLinks: false,
+    periodicNotes: {
+      daily: { folder: '', format: 'YYYY-MM-DD' },
+      weekly: { folder: '', format: 'gggg-[W]ww' },
+      monthly: { folder: '', format: 'YYYY-MM' },
+    },
+    templatesFolder: 'Templates',
   });
   const [isLoading, setIsLoading] = useState(true);
   const [error, setError] = useState<Error | null>(null);
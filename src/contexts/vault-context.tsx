@@ -1,8 +1,11 @@
 'use client';
 
 import React, { createContext, useContext, useState, useEffect, useCallback } from 'react';
-import type { Vault, VaultContextValue } from '@/types/vault';
+import type { Vault, VaultContextValue, VaultLockStatus } from '@/types/vault';
+import { toast } from 'sonner';
 import { vaultService } from '@/services/vault-service';
+import { recoveryService } from '@/services/recovery-service';
+import { isCommandError } from '@/lib/command-error';
 
 /**
  * React context for vault state management.
@@ -16,6 +19,9 @@ const VaultContext = createContext<VaultContextValue | null>(null);
  */
 export function VaultProvider({ children }: { children: React.ReactNode }) {
   const [vaults, setVaults] = useState<Vault[]>([]);
+  const [vaultLock, setVaultLock] = useState<VaultLockStatus | null>(null);
+  // Bumped when the user grants access to the current vault again
+  const [grantVersion, setGrantVersion] = useState(0);
   const [isLoading, setIsLoading] = useState(true);
   const [error, setError] = useState<Error | null>(null);
 
@@ -42,6 +48,48 @@ export function VaultProvider({ children }: { children: React.ReactNode }) {
     loadVaults();
   }, []);
 
+  // Hold the advisory lock of the current vault while it is open
+  useEffect(() => {
+    const vaultPath = currentVault?.path;
+    const vaultName = currentVault?.name ?? '';
+    setVaultLock(null);
+    if (!vaultPath) return;
+
+    let isActive = true;
+    vaultService
+      .acquireVaultLock(vaultPath)
+      .then((status) => {
+        if (!isActive) return;
+        setVaultLock(status);
+        if (status.state === 'ours') {
+          // Buffers left unsaved by a crash can only be restored while we hold the lock
+          recoveryService.promptRecovery();
+        } else {
+          // The backend refuses every change to a vault whose lock is not ours,
+          // so the editor turns read-only
+          toast.warning('Vault is open in another Yana instance', {
+            description: 'Notes are read-only here until the vault is reopened.',
+            duration: Infinity,
+          });
+        }
+      })
+      .catch(async (err) => {
+        // The vault was never picked in the backend's folder dialog
+        if (isActive && isCommandError(err) && err.code === 'outside_vault') {
+          if (await vaultService.regrantVault(vaultPath, vaultName)) {
+            setGrantVersion((version) => version + 1);
+          }
+          return;
+        }
+        console.error('Failed to lock vault:', err);
+      });
+
+    return () => {
+      isActive = false;
+      vaultService.releaseVaultLock(vaultPath);
+    };
+  }, [currentVault?.path, currentVault?.name, grantVersion]);
+
   /**
    * Open folder dialog and add a new vault.
    * Returns the new vault on success, null on cancellation.
@@ -125,6 +173,8 @@ export function VaultProvider({ children }: { children: React.ReactNode }) {
   const value: VaultContextValue = {
     vaults,
     currentVault,
+    vaultLock,
+    grantVersion,
     isLoading,
     error,
     addVault,
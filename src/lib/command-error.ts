@@ -0,0 +1,54 @@
+import type { CommandError, ErrorCode } from '@/types/errors';
+
+/**
+ * Check whether a rejected invoke() value is a structured backend error
+ */
+export function isCommandError(value: unknown): value is CommandError {
+  return (
+    typeof value === 'object' &&
+    value !== null &&
+    typeof (value as CommandError).code === 'string' &&
+    typeof (value as CommandError).message === 'string'
+  );
+}
+
+/**
+ * Extract a human-readable message from anything thrown by invoke()
+ */
+export function getErrorMessage(err: unknown): string {
+  if (isCommandError(err)) return err.message;
+  if (err instanceof Error) return err.message;
+  return String(err);
+}
+
+/**
+ * Error thrown by the services when a backend command fails.
+ * Keeps the backend error code so callers can match on it
+ * instead of on the message text.
+ */
+export class CommandFailure extends Error {
+  /** Backend error code, or "internal" if the failure was not a backend error */
+  readonly code: ErrorCode;
+  /** Path the error relates to, if any */
+  readonly path: string | null;
+  /** Rust io::ErrorKind name, if the error came from the filesystem */
+  readonly ioKind: string | null;
+
+  constructor(context: string, cause: unknown) {
+    super(`${context}: ${getErrorMessage(cause)}`);
+    this.name = 'CommandFailure';
+    if (isCommandError(cause)) {
+      this.code = cause.code;
+      this.path = cause.path;
+      this.ioKind = cause.ioKind;
+    } else if (cause instanceof CommandFailure) {
+      this.code = cause.code;
+      this.path = cause.path;
+      this.ioKind = cause.ioKind;
+    } else {
+      this.code = 'internal';
+      this.path = null;
+      this.ioKind = null;
+    }
+  }
+}
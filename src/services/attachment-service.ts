@@ -0,0 +1,50 @@
+import { invoke } from '@tauri-apps/api/core';
+import { info, error as logError } from '@tauri-apps/plugin-log';
+import type { OrphanedAttachment, TrashReport } from '@/types/attachments';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for cleaning up attachment folders.
+ * Attachments are the images save_image_to_attachments stores next to notes.
+ */
+class AttachmentService {
+  /**
+   * List files in attachment folders that no note references.
+   * @param vaultPath - Absolute path to the vault root
+   * @returns Promise resolving to the orphaned attachments with their sizes
+   * @throws CommandFailure if the vault can't be checked
+   */
+  async findOrphanedAttachments(vaultPath: string): Promise<OrphanedAttachment[]> {
+    try {
+      const attachments = await invoke<OrphanedAttachment[]>('find_orphaned_attachments', {
+        path: vaultPath,
+      });
+      await info(`Found ${attachments.length} orphaned attachments in ${vaultPath}`);
+      return attachments;
+    } catch (err) {
+      await logError(`Failed to find orphaned attachments: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to find orphaned attachments', err);
+    }
+  }
+
+  /**
+   * Move orphaned attachments to the system trash in one batch.
+   * Files that became referenced since they were listed are skipped and reported.
+   * @param paths - Absolute paths of the attachments to trash
+   * @returns Promise resolving to the trashed and skipped paths
+   * @throws CommandFailure if the batch can't be processed
+   */
+  async trashAttachments(paths: string[]): Promise<TrashReport> {
+    try {
+      const report = await invoke<TrashReport>('trash_attachments', { paths });
+      await info(`Moved ${report.trashed.length} attachments to trash, ${report.failed.length} skipped`);
+      return report;
+    } catch (err) {
+      await logError(`Failed to trash attachments: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to trash attachments', err);
+    }
+  }
+}
+
+export const attachmentService = new AttachmentService();
+export { AttachmentService };
@@ -0,0 +1,67 @@
+import { invoke } from '@tauri-apps/api/core';
+import { info, error as logError } from '@tauri-apps/plugin-log';
+import type { BlockId, BlockIdOutcome, ResolvedEmbed } from '@/types/embeds';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for rendering `![[note]]` embeds and managing `^block-id` markers.
+ * Embeds are resolved in the backend with the same rules as the link index.
+ */
+class EmbedService {
+  /**
+   * Resolve an embed to the markdown it stands for.
+   * Missing notes, headings and blocks are reported in `problems`, not thrown.
+   * @param notePath - Absolute path to the note containing the embed
+   * @param target - Text between `![[` and `]]`, e.g. `Other note#Section` or `Other note#^id`
+   * @param maxDepth - How many levels of nested embeds to expand
+   * @returns Promise resolving to the embedded markdown and any problems
+   * @throws CommandFailure if the note is outside the vault
+   */
+  async resolveEmbed(notePath: string, target: string, maxDepth?: number): Promise<ResolvedEmbed> {
+    try {
+      return await invoke<ResolvedEmbed>('resolve_embed', { path: notePath, target, maxDepth });
+    } catch (err) {
+      await logError(`Failed to resolve embed ${target} in ${notePath}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to resolve embed', err);
+    }
+  }
+
+  /**
+   * List the block ids in a note, e.g. for `[[note#^` completion.
+   * @param path - Absolute path to the note
+   * @returns Promise resolving to the block ids in document order
+   * @throws CommandFailure if the note isn't indexed
+   */
+  async getBlockIds(path: string): Promise<BlockId[]> {
+    try {
+      return await invoke<BlockId[]>('get_block_ids', { path });
+    } catch (err) {
+      await logError(`Failed to get block ids for ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to get block ids', err);
+    }
+  }
+
+  /**
+   * Give the block on a line a unique `^id`, or get the one it already has.
+   * The note is written when an id is added, so the editor should reload it.
+   * @param path - Absolute path to the note
+   * @param line - 1-based line number inside the block
+   * @returns Promise resolving to the id and where its marker is
+   * @throws CommandFailure if the line isn't part of a block
+   */
+  async insertBlockId(path: string, line: number): Promise<BlockIdOutcome> {
+    try {
+      const outcome = await invoke<BlockIdOutcome>('insert_block_id', { path, line });
+      if (outcome.created) {
+        await info(`Added block id ^${outcome.id} to ${path}`);
+      }
+      return outcome;
+    } catch (err) {
+      await logError(`Failed to add block id in ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to add block id', err);
+    }
+  }
+}
+
+export const embedService = new EmbedService();
+export { EmbedService };
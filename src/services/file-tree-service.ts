@@ -2,8 +2,9 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { info, error as logError } from '@tauri-apps/plugin-log';
 import { message } from '@tauri-apps/plugin-dialog';
-import type { FileNode, FileEvent } from '@/types/file-tree';
+import type { FileNode, FileEvent, RenameOutcome, SaveOutcome } from '@/types/file-tree';
 import type { ReadFileResponse } from '@/types/performance';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
 
 /**
  * Service layer for file tree operations and file system watching.
@@ -11,6 +12,15 @@ import type { ReadFileResponse } from '@/types/performance';
  */
 class FileTreeService {
   private unlistenFn: UnlistenFn | null = null;
+  /**
+   * Last write sequence per path, so the backend can drop saves that arrive out of
+   * order. The backend orders writes per path and moves a path's sequence on when
+   * it edits the note itself (toggling a task, rewriting links...); the counter
+   * picks that up whenever the note is read.
+   */
+  private writeSeqs = new Map<string, number>();
+  /** Paths edited by the backend since the editor last read them */
+  private stalePaths = new Set<string>();
 
   /**
    * Scan a directory and return hierarchical file tree.
@@ -18,7 +28,7 @@ class FileTreeService {
    * Sorts folders before files, alphabetically within each group.
    * @param path - Absolute path to the directory to scan
    * @returns Promise resolving to array of root FileNode entries
-   * @throws Error if directory doesn't exist or scan fails
+   * @throws CommandFailure if directory doesn't exist or scan fails
    */
   async scanDirectory(path: string): Promise<FileNode[]> {
     try {
@@ -27,7 +37,7 @@ class FileTreeService {
       await info(`Scanned ${nodes.length} root nodes from ${path}`);
       return nodes;
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to scan directory ${path}: ${errorMessage}`);
       
       // Show user-facing error dialog for critical failures
@@ -36,7 +46,7 @@ class FileTreeService {
         kind: 'error',
       });
       
-      throw new Error(`Failed to scan directory: ${errorMessage}`);
+      throw new CommandFailure('Failed to scan directory', err);
     }
   }
 
@@ -44,15 +54,17 @@ class FileTreeService {
    * Read file contents as UTF-8 string.
    * @param path - Absolute path to the file to read
    * @returns Promise resolving to ReadFileResponse with content and duration_ms
-   * @throws Error if file doesn't exist or read fails
+   * @throws CommandFailure if file doesn't exist or read fails
    */
   async readFile(path: string): Promise<ReadFileResponse> {
     try {
       await info(`Reading file: ${path}`);
       const response = await invoke<ReadFileResponse>('read_file', { path });
+      this.writeSeqs.set(path, Math.max(this.writeSeqs.get(path) ?? 0, response.seq));
+      this.stalePaths.delete(path);
       return response;
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to read file ${path}: ${errorMessage}`);
 
       // Show user-facing error dialog
@@ -61,23 +73,55 @@ class FileTreeService {
         kind: 'error',
       });
 
-      throw new Error(`Failed to read file: ${errorMessage}`);
+      throw new CommandFailure('Failed to read file', err);
     }
   }
 
+  /**
+   * Whether the backend edited a file since it was last read, so saving the
+   * editor's buffer would undo that edit.
+   * @param path - Absolute path to the file
+   */
+  isStale(path: string): boolean {
+    return this.stalePaths.has(path);
+  }
+
   /**
    * Write content to a file.
+   * Each call is tagged with an increasing sequence number; the backend serializes
+   * writes per path and drops any that are older than one it has already seen.
+   * Once the backend has edited the file itself, saves are skipped until the
+   * file is read again.
    * @param path - Absolute path to the file to write
    * @param content - Content to write
-   * @throws Error if write fails
+   * @param updateHeadingLinks - Update links in other notes to follow renamed headings
+   * @returns Promise resolving to the SaveOutcome describing what reached the disk
+   *   and which headings the new content renamed
+   * @throws CommandFailure if write fails
    */
-  async saveFile(path: string, content: string): Promise<void> {
+  async saveFile(path: string, content: string, updateHeadingLinks = false): Promise<SaveOutcome> {
+    const seq = (this.writeSeqs.get(path) ?? 0) + 1;
+    if (this.stalePaths.has(path)) {
+      return { seq, persistedSeq: seq, superseded: true, headingRenames: [], links: null };
+    }
+    this.writeSeqs.set(path, seq);
     try {
-      await invoke('write_file', { path, content });
       // We don't log success here to avoid spamming logs on every auto-save
       // The backend logs success anyway
+      const outcome = await invoke<SaveOutcome>('write_file', {
+        path,
+        content,
+        seq,
+        updateHeadingLinks,
+      });
+      // Only a backend edit can supersede our latest save
+      if (outcome.superseded && outcome.seq === this.writeSeqs.get(path)) {
+        await info(`${path} was changed on disk, not saving the editor buffer over it`);
+        this.stalePaths.add(path);
+      }
+      return outcome;
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to save file ${path}: ${errorMessage}`);
       
       // Show user-facing error dialog
@@ -86,15 +130,17 @@ class FileTreeService {
         kind: 'error',
       });
       
-      throw new Error(`Failed to save file: ${errorMessage}`);
+      throw new CommandFailure('Failed to save file', err);
     }
   }
 
   /**
    * Create a new markdown note in the specified directory.
+   * The note starts from the template of the folder or its closest parent, with
+   * `{{title}}`, `{{folder}}`, `{{date}}`, `{{date:FORMAT}}` and `{{time}}` expanded.
    * @param path - Absolute path to the directory where the note should be created
    * @returns Promise resolving to the FileNode of the newly created note
-   * @throws Error if creation fails
+   * @throws CommandFailure if creation fails
    */
   async createNewNote(path: string): Promise<FileNode> {
     try {
@@ -102,7 +148,7 @@ class FileTreeService {
       const node = await invoke<FileNode>('create_new_note', { path });
       return node;
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to create new note in ${path}: ${errorMessage}`);
       
       await message(`Failed to create new note:\n\n${errorMessage}`, {
@@ -110,7 +156,7 @@ class FileTreeService {
         kind: 'error',
       });
       
-      throw new Error(`Failed to create new note: ${errorMessage}`);
+      throw new CommandFailure('Failed to create new note', err);
     }
   }
 
@@ -118,7 +164,7 @@ class FileTreeService {
    * Create a new folder in the specified directory.
    * @param path - Absolute path to the directory where the folder should be created
    * @returns Promise resolving to the FileNode of the newly created folder
-   * @throws Error if creation fails
+   * @throws CommandFailure if creation fails
    */
   async createNewFolder(path: string): Promise<FileNode> {
     try {
@@ -126,7 +172,7 @@ class FileTreeService {
       const node = await invoke<FileNode>('create_new_folder', { path });
       return node;
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to create new folder in ${path}: ${errorMessage}`);
       
       await message(`Failed to create new folder:\n\n${errorMessage}`, {
@@ -134,21 +180,21 @@ class FileTreeService {
         kind: 'error',
       });
       
-      throw new Error(`Failed to create new folder: ${errorMessage}`);
+      throw new CommandFailure('Failed to create new folder', err);
     }
   }
 
   /**
    * Delete a file or directory at the specified path.
    * @param path - Absolute path to the file or directory to delete
-   * @throws Error if deletion fails
+   * @throws CommandFailure if deletion fails
    */
   async deletePath(path: string): Promise<void> {
     try {
       await info(`Deleting: ${path}`);
       await invoke('delete_path', { path });
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to delete ${path}: ${errorMessage}`);
       
       await message(`Failed to delete:\n\n${errorMessage}`, {
@@ -156,7 +202,7 @@ class FileTreeService {
         kind: 'error',
       });
       
-      throw new Error(`Failed to delete: ${errorMessage}`);
+      throw new CommandFailure('Failed to delete', err);
     }
   }
 
@@ -164,7 +210,7 @@ class FileTreeService {
    * Duplicate a file at the specified path.
    * @param path - Absolute path to the file to duplicate
    * @returns Promise resolving to the FileNode of the newly created duplicate
-   * @throws Error if duplication fails
+   * @throws CommandFailure if duplication fails
    */
   async duplicateFile(path: string): Promise<FileNode> {
     try {
@@ -172,7 +218,7 @@ class FileTreeService {
       const node = await invoke<FileNode>('duplicate_file', { path });
       return node;
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to duplicate ${path}: ${errorMessage}`);
       
       await message(`Failed to duplicate:\n\n${errorMessage}`, {
@@ -180,24 +226,35 @@ class FileTreeService {
         kind: 'error',
       });
       
-      throw new Error(`Failed to duplicate: ${errorMessage}`);
+      throw new CommandFailure('Failed to duplicate', err);
     }
   }
 
   /**
-   * Rename a file or directory at the specified path.
+   * Rename or move a file or directory at the specified path.
    * @param path - Current absolute path
    * @param newPath - Target absolute path
-   * @returns Promise resolving to the FileNode representing the renamed path
-   * @throws Error if renaming fails
+   * @param updateLinks - Rewrite links across the vault to follow the rename
+   * @returns Promise resolving to the renamed node and the notes whose links changed
+   * @throws CommandFailure if renaming fails
    */
-  async renamePath(path: string, newPath: string): Promise<FileNode> {
+  async renamePath(path: string, newPath: string, updateLinks = true): Promise<RenameOutcome> {
     try {
       await info(`Renaming: ${path} to ${newPath}`);
-      const node = await invoke<FileNode>('rename_path', { path, newPath });
-      return node;
+      const outcome = await invoke<RenameOutcome>('rename_path', { path, newPath, updateLinks });
+      const { updated, failed } = outcome.links;
+      if (updated.length > 0) {
+        await info(`Updated links in ${updated.length} notes after renaming ${path}`);
+      }
+      if (failed.length > 0) {
+        await message(
+          `Renamed, but links in ${failed.length} notes could not be updated:\n\n${failed.map((e) => e.path ?? e.message).join('\n')}`,
+          { title: 'Rename Warning', kind: 'warning' }
+        );
+      }
+      return outcome;
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to rename ${path}: ${errorMessage}`);
       
       await message(`Failed to rename:\n\n${errorMessage}`, {
@@ -205,16 +262,17 @@ class FileTreeService {
         kind: 'error',
       });
       
-      throw new Error(`Failed to rename: ${errorMessage}`);
+      throw new CommandFailure('Failed to rename', err);
     }
   }
 
+
   /**
    * Start watching a directory for filesystem changes.
    * Emits events when files or folders are created, deleted, renamed, or modified.
    * Events are debounced to prevent excessive updates.
    * @param path - Absolute path to the directory to watch
-   * @throws Error if directory doesn't exist or watcher fails to start
+   * @throws CommandFailure if directory doesn't exist or watcher fails to start
    */
   async startWatching(path: string): Promise<void> {
     try {
@@ -222,7 +280,7 @@ class FileTreeService {
       await invoke('start_watching', { path });
       await info(`File watcher started for: ${path}`);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to start watching ${path}: ${errorMessage}`);
       
       // Show user-facing error dialog
@@ -231,7 +289,7 @@ class FileTreeService {
         kind: 'error',
       });
       
-      throw new Error(`Failed to start file watcher: ${errorMessage}`);
+      throw new CommandFailure('Failed to start file watcher', err);
     }
   }
 
@@ -253,10 +311,10 @@ class FileTreeService {
       
       await info('File watcher stopped');
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to stop watching: ${errorMessage}`);
       // Don't show dialog for stop errors - they're less critical
-      throw new Error(`Failed to stop file watcher: ${errorMessage}`);
+      throw new CommandFailure('Failed to stop file watcher', err);
     }
   }
 
@@ -295,7 +353,7 @@ class FileTreeService {
    * @param mdFilePath - Absolute path to the markdown file
    * @param imageFile - The image File object to save
    * @returns Promise resolving to the relative path to the saved image (e.g., "attachments/image.png")
-   * @throws Error if save fails
+   * @throws CommandFailure if save fails
    */
   async saveImageToAttachments(mdFilePath: string, imageFile: File): Promise<string> {
     try {
@@ -314,7 +372,7 @@ class FileTreeService {
       await info(`Saved image to: ${relativePath}`);
       return relativePath;
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = getErrorMessage(err);
       await logError(`Failed to save image to attachments: ${errorMessage}`);
 
       await message(`Failed to save image:\n\n${errorMessage}`, {
@@ -322,7 +380,7 @@ class FileTreeService {
         kind: 'error',
       });
 
-      throw new Error(`Failed to save image: ${errorMessage}`);
+      throw new CommandFailure('Failed to save image', err);
     }
   }
 }
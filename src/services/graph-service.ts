@@ -0,0 +1,31 @@
+import { invoke } from '@tauri-apps/api/core';
+import { info, error as logError } from '@tauri-apps/plugin-log';
+import type { GraphData, GraphOptions } from '@/types/graph';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for the graph view.
+ * The graph is computed from the link index in the backend.
+ */
+class GraphService {
+  /**
+   * Build the link graph of a vault, or of the neighbourhood of one note.
+   * @param vaultPath - Absolute path to the vault root
+   * @param options - Local mode, filters and optional node kinds
+   * @returns Promise resolving to the nodes and weighted edges
+   * @throws CommandFailure if the graph can't be built
+   */
+  async getGraphData(vaultPath: string, options: GraphOptions = {}): Promise<GraphData> {
+    try {
+      const graph = await invoke<GraphData>('get_graph_data', { path: vaultPath, ...options });
+      await info(`Loaded graph with ${graph.nodes.length} nodes and ${graph.edges.length} edges`);
+      return graph;
+    } catch (err) {
+      await logError(`Failed to load graph: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to load graph', err);
+    }
+  }
+}
+
+export const graphService = new GraphService();
+export { GraphService };
@@ -0,0 +1,178 @@
+import { invoke } from '@tauri-apps/api/core';
+import { info, error as logError } from '@tauri-apps/plugin-log';
+import type {
+  BrokenLink,
+  HeadingRename,
+  LinkConversionReport,
+  LinkKind,
+  LinkReference,
+  LinkUpdateReport,
+  Mention,
+} from '@/types/links';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for the backend link index.
+ * The index covers wiki links and relative markdown links in every note of the
+ * open vault and is kept current by the file watcher.
+ */
+class LinkService {
+  /**
+   * Get the links in other notes that point at a note or file.
+   * @param path - Absolute path to the note or file
+   * @returns Promise resolving to the backlinks, grouped by source note
+   * @throws CommandFailure if the index can't be queried
+   */
+  async getBacklinks(path: string): Promise<LinkReference[]> {
+    try {
+      const links = await invoke<LinkReference[]>('get_backlinks', { path });
+      await info(`Found ${links.length} backlinks to ${path}`);
+      return links;
+    } catch (err) {
+      await logError(`Failed to get backlinks for ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to get backlinks', err);
+    }
+  }
+
+  /**
+   * Get the links written in a note, each with the file it resolves to.
+   * @param path - Absolute path to the note
+   * @returns Promise resolving to the outgoing links in document order
+   * @throws CommandFailure if the note isn't indexed
+   */
+  async getOutgoingLinks(path: string): Promise<LinkReference[]> {
+    try {
+      const links = await invoke<LinkReference[]>('get_outgoing_links', { path });
+      await info(`Found ${links.length} outgoing links in ${path}`);
+      return links;
+    } catch (err) {
+      await logError(`Failed to get outgoing links for ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to get outgoing links', err);
+    }
+  }
+
+  /**
+   * Find links anywhere in a vault that point at missing notes, files or attachments.
+   * @param vaultPath - Absolute path to the vault root
+   * @returns Promise resolving to the broken links, ordered by note, line and column
+   * @throws CommandFailure if the vault can't be checked
+   */
+  async findBrokenLinks(vaultPath: string): Promise<BrokenLink[]> {
+    try {
+      const links = await invoke<BrokenLink[]>('find_broken_links', { path: vaultPath });
+      await info(`Found ${links.length} broken links in ${vaultPath}`);
+      return links;
+    } catch (err) {
+      await logError(`Failed to check links in ${vaultPath}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to check links', err);
+    }
+  }
+
+  /**
+   * Find plain-text mentions of a note's title or aliases in other notes.
+   * Matches are whole words, ignoring case, outside links and code.
+   * @param path - Absolute path to the note
+   * @returns Promise resolving to the mentions, ordered by note, line and column
+   * @throws CommandFailure if the note isn't indexed
+   */
+  async findUnlinkedMentions(path: string): Promise<Mention[]> {
+    try {
+      const mentions = await invoke<Mention[]>('find_unlinked_mentions', { path });
+      await info(`Found ${mentions.length} unlinked mentions of ${path}`);
+      return mentions;
+    } catch (err) {
+      await logError(`Failed to find mentions of ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to find unlinked mentions', err);
+    }
+  }
+
+  /**
+   * Turn selected mentions into wiki links to a note.
+   * Mentions that changed since they were found are skipped and reported as failed.
+   * @param path - Absolute path to the note the mentions refer to
+   * @param mentions - Mentions as returned by findUnlinkedMentions
+   * @returns Promise resolving to the notes that were rewritten
+   * @throws CommandFailure if the note isn't indexed
+   */
+  async linkMentions(path: string, mentions: Mention[]): Promise<LinkUpdateReport> {
+    try {
+      const report = await invoke<LinkUpdateReport>('link_mentions', { path, mentions });
+      await info(`Linked mentions of ${path} in ${report.updated.length} notes`);
+      return report;
+    } catch (err) {
+      await logError(`Failed to link mentions of ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to link mentions', err);
+    }
+  }
+
+  /**
+   * Get the renamed headings of a note that links in other notes still use.
+   * @param path - Absolute path to the note
+   * @returns Promise resolving to the renames with their link counts
+   * @throws CommandFailure if the index can't be queried
+   */
+  async getHeadingRenames(path: string): Promise<HeadingRename[]> {
+    try {
+      return await invoke<HeadingRename[]>('get_heading_renames', { path });
+    } catch (err) {
+      await logError(`Failed to get heading renames for ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to get heading renames', err);
+    }
+  }
+
+  /**
+   * Update `#heading` links in other notes to follow the headings renamed in a note.
+   * @param path - Absolute path to the note whose headings were renamed
+   * @returns Promise resolving to the notes that were rewritten
+   * @throws CommandFailure if the links can't be updated
+   */
+  async updateHeadingLinks(path: string): Promise<LinkUpdateReport> {
+    try {
+      const report = await invoke<LinkUpdateReport>('update_heading_links', { path });
+      await info(`Updated heading links in ${report.updated.length} notes`);
+      return report;
+    } catch (err) {
+      await logError(`Failed to update heading links for ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to update heading links', err);
+    }
+  }
+
+  /**
+   * Leave links to the old headings of a note as they are.
+   * @param path - Absolute path to the note whose headings were renamed
+   * @throws CommandFailure if the index can't be updated
+   */
+  async dismissHeadingRenames(path: string): Promise<void> {
+    try {
+      await invoke('dismiss_heading_renames', { path });
+    } catch (err) {
+      await logError(`Failed to dismiss heading renames for ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to dismiss heading renames', err);
+    }
+  }
+
+  /**
+   * Convert every link in a folder or vault to wiki or markdown syntax.
+   * Links that point at nothing, or can't be written in the other syntax, are skipped.
+   * @param path - Absolute path to the folder, vault root or note
+   * @param to - Syntax to convert the links to
+   * @param dryRun - Only report what would change, without writing anything
+   * @returns Promise resolving to the converted and skipped links
+   * @throws CommandFailure if the folder can't be converted
+   */
+  async convertLinks(path: string, to: LinkKind, dryRun = false): Promise<LinkConversionReport> {
+    try {
+      const report = await invoke<LinkConversionReport>('convert_links', { path, to, dryRun });
+      await info(
+        `${dryRun ? 'Would convert' : 'Converted'} ${report.converted.length} links in ${path}`
+      );
+      return report;
+    } catch (err) {
+      await logError(`Failed to convert links in ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to convert links', err);
+    }
+  }
+}
+
+export const linkService = new LinkService();
+export { LinkService };
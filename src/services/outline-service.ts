@@ -0,0 +1,28 @@
+import { invoke } from '@tauri-apps/api/core';
+import { error as logError } from '@tauri-apps/plugin-log';
+import type { NoteOutline } from '@/types/outline';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for note outlines.
+ * Notes are parsed as CommonMark in the backend, for the table of contents and
+ * the word count.
+ */
+class OutlineService {
+  /**
+   * Get the heading tree and statistics of a note as saved on disk.
+   * @param path - Absolute path to the note
+   * @returns Promise resolving to the outline
+   */
+  async getNoteOutline(path: string): Promise<NoteOutline> {
+    try {
+      return await invoke<NoteOutline>('get_note_outline', { path });
+    } catch (err) {
+      await logError(`Failed to outline note: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to outline note', err);
+    }
+  }
+}
+
+export const outlineService = new OutlineService();
+export { OutlineService };
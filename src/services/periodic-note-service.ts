@@ -0,0 +1,83 @@
+import { invoke } from '@tauri-apps/api/core';
+import { info, error as logError } from '@tauri-apps/plugin-log';
+import type { FileNode } from '@/types/file-tree';
+import type { Period } from '@/types/periodic';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for daily, weekly and monthly notes.
+ * Folders and file name formats come from the `periodicNotes` setting, which
+ * the backend reads from the settings store.
+ */
+class PeriodicNoteService {
+  /**
+   * Open the periodic note for a date, creating it from its folder's template if needed.
+   * @param vaultPath - Absolute path to the vault root
+   * @param period - Daily, weekly or monthly
+   * @param date - Any day in the period as `YYYY-MM-DD`; today when omitted
+   * @returns Promise resolving to the note's file node
+   */
+  async openPeriodicNote(vaultPath: string, period: Period, date?: string): Promise<FileNode> {
+    try {
+      const node = await invoke<FileNode>('open_periodic_note', { path: vaultPath, period, date });
+      await info(`Opened ${period} note: ${node.path}`);
+      return node;
+    } catch (err) {
+      await logError(`Failed to open ${period} note: ${getErrorMessage(err)}`);
+      throw new CommandFailure(`Failed to open ${period} note`, err);
+    }
+  }
+
+  /**
+   * Find the closest existing periodic note before another one.
+   * @param vaultPath - Absolute path to the vault root
+   * @param period - Daily, weekly or monthly
+   * @param from - Absolute path of the periodic note to start from
+   * @returns Promise resolving to the note, or null if there is none
+   * @throws CommandFailure if `from` is not a note of the period
+   */
+  async getPreviousPeriodicNote(
+    vaultPath: string,
+    period: Period,
+    from: string
+  ): Promise<FileNode | null> {
+    try {
+      return await invoke<FileNode | null>('get_previous_periodic_note', {
+        path: vaultPath,
+        period,
+        from,
+      });
+    } catch (err) {
+      await logError(`Failed to find previous ${period} note: ${getErrorMessage(err)}`);
+      throw new CommandFailure(`Failed to find previous ${period} note`, err);
+    }
+  }
+
+  /**
+   * Find the closest existing periodic note after another one.
+   * @param vaultPath - Absolute path to the vault root
+   * @param period - Daily, weekly or monthly
+   * @param from - Absolute path of the periodic note to start from
+   * @returns Promise resolving to the note, or null if there is none
+   * @throws CommandFailure if `from` is not a note of the period
+   */
+  async getNextPeriodicNote(
+    vaultPath: string,
+    period: Period,
+    from: string
+  ): Promise<FileNode | null> {
+    try {
+      return await invoke<FileNode | null>('get_next_periodic_note', {
+        path: vaultPath,
+        period,
+        from,
+      });
+    } catch (err) {
+      await logError(`Failed to find next ${period} note: ${getErrorMessage(err)}`);
+      throw new CommandFailure(`Failed to find next ${period} note`, err);
+    }
+  }
+}
+
+export const periodicNoteService = new PeriodicNoteService();
+export { PeriodicNoteService };
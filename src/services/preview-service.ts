@@ -0,0 +1,30 @@
+import { invoke } from '@tauri-apps/api/core';
+import { error as logError } from '@tauri-apps/plugin-log';
+import type { NotePreview } from '@/types/preview';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for note previews.
+ * Previews are cached in the backend until a note changes on disk, so asking
+ * for many at once is cheap.
+ */
+class PreviewService {
+  /**
+   * Get the title and a plain-text excerpt of several notes.
+   * Notes that cannot be read are left out of the result.
+   * @param paths - Absolute paths to the notes
+   * @param length - Excerpt length in characters, 200 by default and at most 1000
+   * @returns Promise resolving to the previews, in the order of the paths
+   */
+  async getNotePreviews(paths: string[], length?: number): Promise<NotePreview[]> {
+    try {
+      return await invoke<NotePreview[]>('get_note_previews', { paths, length });
+    } catch (err) {
+      await logError(`Failed to get note previews: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to get note previews', err);
+    }
+  }
+}
+
+export const previewService = new PreviewService();
+export { PreviewService };
@@ -0,0 +1,119 @@
+import { invoke } from '@tauri-apps/api/core';
+import { info, error as logError } from '@tauri-apps/plugin-log';
+import type {
+  NoteProperty,
+  PropertyKey,
+  PropertyMatch,
+  PropertyQuery,
+  PropertyValue,
+} from '@/types/properties';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for frontmatter properties.
+ * Properties are parsed from YAML (`---`) and TOML (`+++`) frontmatter by the
+ * backend index and kept current by the file watcher.
+ */
+class PropertyService {
+  /**
+   * Get every property key used in a vault, with the types of its values.
+   * @param vaultPath - Absolute path to the vault root
+   * @returns Promise resolving to the keys, ordered by name
+   * @throws CommandFailure if the index can't be queried
+   */
+  async getPropertyKeys(vaultPath: string): Promise<PropertyKey[]> {
+    try {
+      const keys = await invoke<PropertyKey[]>('get_property_keys', { path: vaultPath });
+      await info(`Found ${keys.length} property keys in ${vaultPath}`);
+      return keys;
+    } catch (err) {
+      await logError(`Failed to get property keys: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to get property keys', err);
+    }
+  }
+
+  /**
+   * Get the frontmatter properties of a note.
+   * @param path - Absolute path to the note
+   * @returns Promise resolving to the properties in the order they are written
+   * @throws CommandFailure if the note isn't indexed
+   */
+  async getNoteProperties(path: string): Promise<NoteProperty[]> {
+    try {
+      return await invoke<NoteProperty[]>('get_note_properties', { path });
+    } catch (err) {
+      await logError(`Failed to get properties of ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to get note properties', err);
+    }
+  }
+
+  /**
+   * Find notes whose property matches a value or lies in a range.
+   * Keys and text match ignoring case.
+   * @param path - Absolute path to the vault root or a folder in it
+   * @param key - Property key
+   * @param query - Value or range to match; empty to find every note with the property
+   * @returns Promise resolving to the matching notes, ordered by path
+   * @throws CommandFailure if the index can't be queried
+   */
+  async findNotesByProperty(
+    path: string,
+    key: string,
+    query: PropertyQuery = {}
+  ): Promise<PropertyMatch[]> {
+    try {
+      const matches = await invoke<PropertyMatch[]>('find_notes_by_property', {
+        path,
+        key,
+        ...query,
+      });
+      await info(`Found ${matches.length} notes with matching ${key}`);
+      return matches;
+    } catch (err) {
+      await logError(`Failed to find notes by ${key}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to find notes by property', err);
+    }
+  }
+
+  /**
+   * Set one frontmatter property of a note.
+   * Only the lines of that property change; other properties, comments and the
+   * body are left as written. Adds a frontmatter block if the note has none.
+   * @param path - Absolute path to the note
+   * @param key - Property key, matched ignoring case
+   * @param value - New value
+   * @returns Promise resolving to the note's properties after the change
+   * @throws CommandFailure if the frontmatter can't be parsed or written
+   */
+  async setNoteProperty(path: string, key: string, value: PropertyValue): Promise<NoteProperty[]> {
+    try {
+      const properties = await invoke<NoteProperty[]>('set_note_property', { path, key, value });
+      await info(`Set ${key} of ${path}`);
+      return properties;
+    } catch (err) {
+      await logError(`Failed to set ${key} of ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to set property', err);
+    }
+  }
+
+  /**
+   * Remove one frontmatter property of a note, leaving the rest of the file as written.
+   * @param path - Absolute path to the note
+   * @param key - Property key, matched ignoring case
+   * @returns Promise resolving to the note's remaining properties
+   * @throws CommandFailure if the frontmatter can't be parsed or written
+   */
+  async removeNoteProperty(path: string, key: string): Promise<NoteProperty[]> {
+    try {
+      const properties = await invoke<NoteProperty[]>('remove_note_property', { path, key });
+      await info(`Removed ${key} from ${path}`);
+      return properties;
+    } catch (err) {
+      await logError(`Failed to remove ${key} from ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to remove property', err);
+    }
+  }
+}
+
+export const propertyService = new PropertyService();
+export { PropertyService };
@@ -0,0 +1,38 @@
+import { invoke } from '@tauri-apps/api/core';
+import { info, error as logError } from '@tauri-apps/plugin-log';
+import type { QueryResult } from '@/types/query';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for note queries.
+ * Queries run in the backend over the metadata of the link index:
+ *
+ * ```
+ * TABLE status, due AS "Due" FROM "Projects" AND #work WHERE status = "active" SORT due DESC LIMIT 20
+ * ```
+ *
+ * Fields are frontmatter properties or `file.name`, `file.path`, `file.folder`,
+ * `file.tags`, `file.size`, `file.ctime`, `file.mtime`, `file.inlinks` and `file.outlinks`.
+ */
+class QueryService {
+  /**
+   * Run a query over the notes of a vault.
+   * @param vaultPath - Absolute path to the vault root
+   * @param query - Query text
+   * @returns Promise resolving to the column labels and one row per matching note
+   * @throws CommandFailure if the query is invalid; the message points at the column
+   */
+  async runQuery(vaultPath: string, query: string): Promise<QueryResult> {
+    try {
+      const result = await invoke<QueryResult>('run_query', { path: vaultPath, query });
+      await info(`Query returned ${result.rows.length} rows`);
+      return result;
+    } catch (err) {
+      await logError(`Failed to run query: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to run query', err);
+    }
+  }
+}
+
+export const queryService = new QueryService();
+export { QueryService };
@@ -0,0 +1,98 @@
+import { invoke } from '@tauri-apps/api/core';
+import { ask } from '@tauri-apps/plugin-dialog';
+import { info, error as logError } from '@tauri-apps/plugin-log';
+import type { RecoverableBuffer } from '@/types/recovery';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+import { fileTreeService } from '@/services/file-tree-service';
+
+/**
+ * Service layer for the crash recovery journal.
+ * The editor checkpoints unsaved buffers here between debounced saves,
+ * so keystrokes survive a crash or force-quit.
+ */
+class RecoveryService {
+  // Checkpoints can overlap, so each is tagged with an increasing sequence
+  // number; starting from the clock keeps it increasing across webview reloads
+  private checkpointSeq = Date.now();
+
+  /**
+   * Record the current buffer of a note.
+   * Failures are logged but never surfaced, since this runs on every edit.
+   * @param path - Absolute path to the note
+   * @param content - Current buffer content
+   */
+  async checkpoint(path: string, content: string): Promise<void> {
+    const seq = ++this.checkpointSeq;
+    try {
+      await invoke('checkpoint_buffer', { path, content, seq });
+    } catch (err) {
+      await logError(`Failed to checkpoint buffer ${path}: ${getErrorMessage(err)}`);
+    }
+  }
+
+  /**
+   * List notes whose checkpoint is newer than the file on disk.
+   * Should be called on startup, after the vault roots are registered.
+   * @returns Promise resolving to the recoverable buffers, newest first
+   * @throws CommandFailure if the journal can't be read
+   */
+  async listRecoverableBuffers(): Promise<RecoverableBuffer[]> {
+    try {
+      const buffers = await invoke<RecoverableBuffer[]>('list_recoverable_buffers');
+      await info(`Found ${buffers.length} recoverable buffers`);
+      return buffers;
+    } catch (err) {
+      await logError(`Failed to list recoverable buffers: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to list recoverable buffers', err);
+    }
+  }
+
+  /**
+   * Offer to restore each buffer that was not saved before the app closed.
+   * Restored buffers are saved over their note, declined ones are discarded.
+   * Should be called once the vault lock is ours, so a restore can be saved.
+   */
+  async promptRecovery(): Promise<void> {
+    let buffers: RecoverableBuffer[];
+    try {
+      buffers = await this.listRecoverableBuffers();
+    } catch {
+      return;
+    }
+
+    for (const buffer of buffers) {
+      const name = buffer.path.split(/[\\/]/).pop() ?? buffer.path;
+      const restore = await ask(
+        `"${name}" has changes from ${new Date(buffer.savedAt).toLocaleString()} that were not saved before Yana closed.\n\nRestore them?`,
+        { title: 'Recover Unsaved Changes', kind: 'warning', okLabel: 'Restore', cancelLabel: 'Discard' }
+      );
+      try {
+        if (restore) {
+          await fileTreeService.saveFile(buffer.path, buffer.content);
+          await info(`Restored unsaved changes to ${buffer.path}`);
+        } else {
+          await this.discardBuffer(buffer.path);
+        }
+      } catch {
+        // saveFile and discardBuffer already reported the failure
+      }
+    }
+  }
+
+  /**
+   * Drop the checkpoint for a note, e.g. when the user declines to recover it.
+   * @param path - Absolute path to the note
+   * @throws CommandFailure if the checkpoint can't be removed
+   */
+  async discardBuffer(path: string): Promise<void> {
+    try {
+      await invoke('discard_buffer', { path });
+    } catch (err) {
+      await logError(`Failed to discard buffer ${path}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to discard buffer', err);
+    }
+  }
+}
+
+export const recoveryService = new RecoveryService();
+export { RecoveryService };
@@ -10,6 +10,13 @@ const DEFAULT_SETTINGS: Settings = {
   maxTabs: 5,
   autoCheckUpdates: true,
   devMode: false,
+  autoUpdateHeadingLinks: false,
+  periodicNotes: {
+    daily: { folder: '', format: 'YYYY-MM-DD' },
+    weekly: { folder: '', format: 'gggg-[W]ww' },
+    monthly: { folder: '', format: 'YYYY-MM' },
+  },
+  templatesFolder: 'Templates',
 };
 
 /**
@@ -0,0 +1,72 @@
+import { invoke } from '@tauri-apps/api/core';
+import { info, error as logError } from '@tauri-apps/plugin-log';
+import type { TagNode, TaggedNote, TagUpdate } from '@/types/tags';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for tags.
+ * The backend index collects inline `#tags` outside code and the frontmatter
+ * `tags` property of every note.
+ */
+class TagService {
+  /**
+   * Get every tag in a vault as a hierarchy, with note counts at each level.
+   * @param vaultPath - Absolute path to the vault root
+   * @returns Promise resolving to the top-level tags
+   * @throws CommandFailure if the index can't be queried
+   */
+  async getTagTree(vaultPath: string): Promise<TagNode[]> {
+    try {
+      return await invoke<TagNode[]>('get_tag_tree', { path: vaultPath });
+    } catch (err) {
+      await logError(`Failed to get tags of ${vaultPath}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to get tags', err);
+    }
+  }
+
+  /**
+   * Get the notes carrying a tag.
+   * @param vaultPath - Absolute path to the vault root
+   * @param tag - Tag with or without the `#`
+   * @param includeNested - Also match tags nested below it
+   * @returns Promise resolving to the notes, ordered by path
+   * @throws CommandFailure if the tag is invalid or the index can't be queried
+   */
+  async getNotesForTag(vaultPath: string, tag: string, includeNested = true): Promise<TaggedNote[]> {
+    try {
+      const notes = await invoke<TaggedNote[]>('get_notes_for_tag', {
+        path: vaultPath,
+        tag,
+        includeNested,
+      });
+      await info(`Found ${notes.length} notes tagged ${tag}`);
+      return notes;
+    } catch (err) {
+      await logError(`Failed to get notes for ${tag}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to get notes for tag', err);
+    }
+  }
+
+  /**
+   * Rename a tag, and the tags nested below it, in every note of a vault.
+   * Renaming onto an existing tag merges the two. Either every note is updated or none is.
+   * @param vaultPath - Absolute path to the vault root
+   * @param from - Tag to rename
+   * @param to - New tag
+   * @returns Promise resolving to the notes that were rewritten
+   * @throws CommandFailure if a tag is invalid or a note can't be written
+   */
+  async renameTag(vaultPath: string, from: string, to: string): Promise<TagUpdate[]> {
+    try {
+      const updated = await invoke<TagUpdate[]>('rename_tag', { path: vaultPath, from, to });
+      await info(`Renamed ${from} to ${to} in ${updated.length} notes`);
+      return updated;
+    } catch (err) {
+      await logError(`Failed to rename ${from} to ${to}: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to rename tag', err);
+    }
+  }
+}
+
+export const tagService = new TagService();
+export { TagService };
@@ -0,0 +1,52 @@
+import { invoke } from '@tauri-apps/api/core';
+import { info, error as logError } from '@tauri-apps/plugin-log';
+import type { TaskItem, TaskQuery } from '@/types/tasks';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
+
+/**
+ * Service layer for tasks.
+ * Checkbox list items of every note are kept in the link index, with their
+ * heading, due date (`📅 2024-05-01`, `[due:: 2024-05-01]`) and priority.
+ */
+class TaskService {
+  /**
+   * Get the tasks of a vault, ordered by note and line.
+   * @param vaultPath - Absolute path to the vault root
+   * @param query - Optional status, folder and due date filters
+   * @returns Promise resolving to the matching tasks
+   * @throws CommandFailure if a date is not `YYYY-MM-DD`
+   */
+  async getTasks(vaultPath: string, query: TaskQuery = {}): Promise<TaskItem[]> {
+    try {
+      const tasks = await invoke<TaskItem[]>('get_tasks', { path: vaultPath, ...query });
+      await info(`Found ${tasks.length} tasks`);
+      return tasks;
+    } catch (err) {
+      await logError(`Failed to get tasks: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to get tasks', err);
+    }
+  }
+
+  /**
+   * Check or uncheck a task.
+   * Only the checkbox changes; the editor should reload the note if it is open.
+   * @param task - The task as listed; its text and status must still match the note
+   * @returns Promise resolving to the task after the change
+   * @throws CommandFailure with code `conflict` if the task changed since it was listed,
+   * or if there is no task on its line
+   */
+  async toggleTask(task: TaskItem): Promise<TaskItem> {
+    const { path, line, text, status } = task;
+    try {
+      const toggled = await invoke<TaskItem>('toggle_task', { path, line, text, status });
+      await info(`Task on line ${line} is now ${toggled.status}`);
+      return toggled;
+    } catch (err) {
+      await logError(`Failed to toggle task: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to toggle task', err);
+    }
+  }
+}
+
+export const taskService = new TaskService();
+export { TaskService };
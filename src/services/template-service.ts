@@ -1,75 +1,93 @@
-import { LazyStore } from '@tauri-apps/plugin-store';
+import { invoke } from '@tauri-apps/api/core';
 import { info, error as logError } from '@tauri-apps/plugin-log';
+import type { FileNode } from '@/types/file-tree';
+import type { NoteTemplate } from '@/types/templates';
+import { CommandFailure, getErrorMessage } from '@/lib/command-error';
 
 /**
- * Service layer for template CRUD operations.
- * Abstracts Tauri plugin interactions for template management.
+ * Service layer for note templates.
+ * Templates are markdown files in the vault's templates folder (the `templatesFolder`
+ * setting, `Templates` by default). Templates for a folder sit at the same relative
+ * path below it, so `Templates/Projects/Meeting.md` is offered in `Projects` and its
+ * subfolders. `Default.md` is the template new notes in the folder start from.
  */
 class TemplateService {
-  private store: LazyStore;
-  private templates: Record<string, string> = {};
-
-  constructor() {
-    this.store = new LazyStore('templates.json');
-  }
-
   /**
-   * Load all templates from storage.
+   * Get the default template of a folder, not counting ones inherited from parents.
+   * @param folderPath - Absolute path to the folder
+   * @returns Promise resolving to the template content, or null if the folder has none
    */
-  async loadTemplates(): Promise<Record<string, string>> {
+  async getTemplate(folderPath: string): Promise<string | null> {
     try {
-      const data = await this.store.get<{ templates: Record<string, string> }>('data');
-      if (data && data.templates) {
-        this.templates = data.templates;
-        await info(`Loaded ${Object.keys(this.templates).length} templates from store`);
-        return this.templates;
-      }
-      await info('No templates found in store, returning empty object');
-      this.templates = {};
-      return {};
+      return await invoke<string | null>('get_folder_template', { path: folderPath });
     } catch (err) {
-      await logError(`Failed to load templates: ${err}`);
-      this.templates = {};
-      return {}; // Return empty on error to allow app to function
+      await logError(`Failed to load template: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to load template', err);
     }
   }
 
   /**
-   * Save templates to storage.
+   * Set the default template of a folder.
+   * @param folderPath - Absolute path to the folder
+   * @param content - Template content
    */
-  async saveTemplates(): Promise<void> {
+  async setTemplate(folderPath: string, content: string): Promise<void> {
     try {
-      const data = { templates: this.templates };
-      await this.store.set('data', data);
-      await this.store.save();
-      await info(`Saved ${Object.keys(this.templates).length} templates to store`);
+      await invoke('set_folder_template', { path: folderPath, content });
+      await info(`Saved template for ${folderPath}`);
     } catch (err) {
-      await logError(`Failed to save templates: ${err}`);
-      throw new Error(`Failed to save templates: ${err}`);
+      await logError(`Failed to save template: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to save template', err);
     }
   }
 
   /**
-   * Get template for a specific folder path.
+   * Remove the default template of a folder.
+   * @param folderPath - Absolute path to the folder
    */
-  getTemplate(folderPath: string): string | undefined {
-    return this.templates[folderPath];
+  async removeTemplate(folderPath: string): Promise<void> {
+    await this.setTemplate(folderPath, '');
   }
 
   /**
-   * Set template for a specific folder path.
+   * List the templates of a vault.
+   * @param vaultPath - Absolute path to the vault root
+   * @param folderPath - Only templates offered in this folder, its own first
+   * @returns Promise resolving to the templates
    */
-  async setTemplate(folderPath: string, content: string): Promise<void> {
-    this.templates[folderPath] = content;
-    await this.saveTemplates();
+  async listTemplates(vaultPath: string, folderPath?: string): Promise<NoteTemplate[]> {
+    try {
+      const templates = await invoke<NoteTemplate[]>('list_templates', {
+        path: vaultPath,
+        folder: folderPath,
+      });
+      await info(`Found ${templates.length} templates`);
+      return templates;
+    } catch (err) {
+      await logError(`Failed to list templates: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to list templates', err);
+    }
   }
 
   /**
-   * Remove template for a specific folder path.
+   * Create a note in a folder from one of the templates offered there.
+   * @param folderPath - Absolute path to the folder the note goes in
+   * @param template - Template name
+   * @returns Promise resolving to the FileNode of the new note
+   * @throws CommandFailure if the template is not offered in the folder
    */
-  async removeTemplate(folderPath: string): Promise<void> {
-    delete this.templates[folderPath];
-    await this.saveTemplates();
+  async createNoteFromTemplate(folderPath: string, template: string): Promise<FileNode> {
+    try {
+      const node = await invoke<FileNode>('create_note_from_template', {
+        folder: folderPath,
+        template,
+      });
+      await info(`Created ${node.path} from template ${template}`);
+      return node;
+    } catch (err) {
+      await logError(`Failed to create note from template: ${getErrorMessage(err)}`);
+      throw new CommandFailure('Failed to create note from template', err);
+    }
   }
 }
 
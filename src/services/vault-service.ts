@@ -1,8 +1,10 @@
 import { v4 as uuidv4 } from 'uuid';
+import { invoke } from '@tauri-apps/api/core';
 import { LazyStore } from '@tauri-apps/plugin-store';
-import { open, message } from '@tauri-apps/plugin-dialog';
+import { message, ask } from '@tauri-apps/plugin-dialog';
 import { info, error as logError } from '@tauri-apps/plugin-log';
-import type { Vault, VaultStore } from '@/types/vault';
+import type { Vault, VaultStore, VaultLockStatus } from '@/types/vault';
+import { isCommandError, getErrorMessage } from '@/lib/command-error';
 
 /**
  * Service layer for vault CRUD operations and business logic.
@@ -46,12 +48,111 @@ class VaultService {
       await this.store.set('data', data);
       await this.store.save();
       await info(`Saved ${vaults.length} vaults to store`);
+      await this.syncVaultRoots();
     } catch (err) {
       await logError(`Failed to save vaults: ${err}`);
       throw new Error(`Failed to save vaults: ${err}`);
     }
   }
 
+  /**
+   * Tell the backend to reload the vault roots from the store.
+   * File commands are rejected for paths outside the registered vaults,
+   * so this must run whenever the vault list changes. Only folders picked
+   * through chooseAndAddVault or regrantVault are accepted; the list can only
+   * narrow them.
+   * @returns Promise resolving to the canonical vault roots now accessible
+   */
+  async syncVaultRoots(): Promise<string[]> {
+    const roots = await invoke<string[]>('sync_vault_roots');
+    await info(`Registered ${roots.length} vault roots with backend`);
+    return roots;
+  }
+
+  /**
+   * Get the lock status of a vault without changing it.
+   * @param path - Vault root path
+   * @returns Promise resolving to the VaultLockStatus
+   */
+  async getVaultLockStatus(path: string): Promise<VaultLockStatus> {
+    return invoke<VaultLockStatus>('get_vault_lock_status', { path });
+  }
+
+  /**
+   * Take the lock of a vault so no other Yana instance autosaves into it.
+   * If another instance holds it, asks the user whether to take it over.
+   * @param path - Vault root path
+   * @returns Promise resolving to the resulting VaultLockStatus; state is "held"
+   * if the user chose not to take the lock over
+   */
+  async acquireVaultLock(path: string): Promise<VaultLockStatus> {
+    try {
+      return await invoke<VaultLockStatus>('acquire_vault_lock', { path });
+    } catch (err) {
+      if (!isCommandError(err) || err.code !== 'conflict') {
+        await logError(`Failed to lock vault ${path}: ${getErrorMessage(err)}`);
+        throw err;
+      }
+
+      const takeOver = await ask(
+        `${err.message}.\n\nEditing the same vault from two places can corrupt notes. Take over the vault anyway?`,
+        { title: 'Vault Already Open', kind: 'warning', okLabel: 'Take Over', cancelLabel: 'Cancel' }
+      );
+      if (!takeOver) {
+        await info(`Vault ${path} is open elsewhere, not taking the lock`);
+        return this.getVaultLockStatus(path);
+      }
+
+      await info(`Taking over vault lock for ${path}`);
+      return invoke<VaultLockStatus>('acquire_vault_lock', { path, force: true });
+    }
+  }
+
+  /**
+   * Ask the user to pick the folder of a vault again, so the backend grants
+   * access to it. Vaults are only opened after their folder was picked in the
+   * backend's dialog; ones added before that, or whose grant was lost, need it.
+   * @param path - Path of the vault the backend refused with an outside_vault error
+   * @param name - Name of the vault, for the prompt
+   * @returns Promise resolving to true once the vault is accessible
+   */
+  async regrantVault(path: string, name: string): Promise<boolean> {
+    const choose = await ask(
+      `Yana needs permission to open the vault "${name}". Choose its folder (${path}) to continue.`,
+      { title: 'Open Vault', kind: 'info', okLabel: 'Choose Folder', cancelLabel: 'Cancel' }
+    );
+    if (!choose) return false;
+
+    const folder = await invoke<string | null>('choose_vault_folder');
+    if (!folder) return false;
+    await this.syncVaultRoots();
+
+    try {
+      await this.getVaultLockStatus(path);
+      await info(`Regranted vault ${path}`);
+      return true;
+    } catch (err) {
+      if (!isCommandError(err) || err.code !== 'outside_vault') throw err;
+      await message(`The chosen folder is not the folder of "${name}":\n\n${path}`, {
+        title: 'Open Vault',
+        kind: 'error',
+      });
+      return false;
+    }
+  }
+
+  /**
+   * Release the lock of a vault if this instance holds it.
+   * @param path - Vault root path
+   */
+  async releaseVaultLock(path: string): Promise<void> {
+    try {
+      await invoke('release_vault_lock', { path });
+    } catch (err) {
+      await logError(`Failed to release vault lock ${path}: ${getErrorMessage(err)}`);
+    }
+  }
+
   /**
    * Extract folder name from a full filesystem path.
    * Works with both Unix and Windows path separators.
@@ -80,18 +181,14 @@ class VaultService {
 
   /**
    * Open native folder dialog and create a new vault entry.
+   * The dialog runs in the backend, which grants access to the picked folder.
    * Sets isDefault to true if this is the first vault.
    * @param existingVaults - Current list of vaults to determine default status
    * @returns Promise resolving to the new Vault entry
    * @throws Error if user cancels the dialog
    */
   async chooseAndAddVault(existingVaults: Vault[]): Promise<Vault> {
-    const folder = await open({
-      multiple: false,
-      directory: true,
-      canCreateDirectories: true,
-      title: 'Choose Vault',
-    });
+    const folder = await invoke<string | null>('choose_vault_folder');
 
     await info(`Chosen vault folder: ${folder}`);
 
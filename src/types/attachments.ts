@@ -0,0 +1,27 @@
+/**
+ * Attachment cleanup type definitions
+ */
+
+import type { CommandError } from './errors';
+
+/**
+ * A file in an attachment folder that no note references
+ */
+export interface OrphanedAttachment {
+  /** Absolute path of the file */
+  path: string;
+  /** Size in bytes */
+  size: number;
+  /** Modification time in milliseconds since the epoch */
+  modifiedAt: number | null;
+}
+
+/**
+ * Result of moving attachments to the trash
+ */
+export interface TrashReport {
+  /** Paths that were moved to the trash */
+  trashed: string[];
+  /** Paths that were skipped, e.g. because a note references them again */
+  failed: CommandError[];
+}
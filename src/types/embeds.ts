@@ -0,0 +1,60 @@
+/**
+ * Embed (transclusion) type definitions
+ */
+
+/**
+ * Why an embed could not be expanded
+ */
+export type EmbedProblemKind = "missing_note" | "missing_section" | "cycle" | "too_deep";
+
+/**
+ * An embed that was left as written instead of being expanded
+ */
+export interface EmbedProblem {
+  /** What went wrong */
+  kind: EmbedProblemKind;
+  /** Absolute path of the note containing the embed */
+  source: string;
+  /** Embed target as written, with its fragment */
+  target: string;
+  /** 1-based line number in the source note, null for the requested embed itself */
+  line: number | null;
+}
+
+/**
+ * Markdown to show in place of an embed
+ */
+export interface ResolvedEmbed {
+  /** Absolute path of the embedded note, null if it doesn't exist */
+  path: string | null;
+  /** Heading path or `^block` that was addressed */
+  fragment: string | null;
+  /** Content with nested note embeds expanded, null if the target is missing */
+  markdown: string | null;
+  /** Embeds that could not be expanded, the requested one first */
+  problems: EmbedProblem[];
+}
+
+/**
+ * A `^block-id` marker in a note
+ */
+export interface BlockId {
+  /** Id without the `^` */
+  id: string;
+  /** 1-based line number of the marker */
+  line: number;
+  /** Text of the marked block without the marker, clipped */
+  text: string;
+}
+
+/**
+ * Result of an insert_block_id call
+ */
+export interface BlockIdOutcome {
+  /** Block id without the `^`, for `[[note#^id]]` references */
+  id: string;
+  /** 1-based line number of the marker */
+  line: number;
+  /** Whether the id was added, rather than already present */
+  created: boolean;
+}
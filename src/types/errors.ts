@@ -0,0 +1,33 @@
+/**
+ * Error type definitions shared with the Rust backend
+ */
+
+/**
+ * Stable, machine-readable error codes returned by backend commands
+ */
+export type ErrorCode =
+  | "not_found"
+  | "permission_denied"
+  | "already_exists"
+  | "conflict"
+  | "outside_vault"
+  | "invalid_path"
+  | "not_a_directory"
+  | "not_a_file"
+  | "invalid_input"
+  | "io"
+  | "internal";
+
+/**
+ * Error payload rejected by every backend command
+ */
+export interface CommandError {
+  /** Machine-readable error code to branch on */
+  code: ErrorCode;
+  /** Human-readable description, safe to show to the user */
+  message: string;
+  /** Path the error relates to, if any */
+  path: string | null;
+  /** Rust io::ErrorKind name (e.g. "NotFound") when the error came from the filesystem */
+  ioKind: string | null;
+}
@@ -2,6 +2,8 @@
  * File tree type definitions for the file tree feature
  */
 
+import type { HeadingRename, LinkUpdateReport } from './links';
+
 /**
  * Represents a file or folder node in the tree
  * A node can be either a file or a folder with optional children
@@ -30,6 +32,39 @@ export interface FileEvent {
   path: string;
 }
 
+/**
+ * Result of a write_file call
+ * Writes to the same path are serialized by the backend and ordered by sequence number
+ */
+export interface WriteOutcome {
+  /** Sequence number of the requested write */
+  seq: number;
+  /** Highest sequence number whose content is now on disk */
+  persistedSeq: number;
+  /** Whether this write was dropped in favour of a newer one */
+  superseded: boolean;
+}
+
+/**
+ * Result of saving a note through write_file
+ */
+export interface SaveOutcome extends WriteOutcome {
+  /** Renamed headings of the note that links in other notes still use */
+  headingRenames: HeadingRename[];
+  /** Links updated to follow the renamed headings, when requested */
+  links: LinkUpdateReport | null;
+}
+
+/**
+ * Result of a rename_path call
+ */
+export interface RenameOutcome {
+  /** The renamed file or folder */
+  node: FileNode;
+  /** Notes whose links were updated to follow the rename */
+  links: LinkUpdateReport;
+}
+
 export interface FileStats {
   wordCount: number;
   charCount: number;
@@ -55,6 +90,8 @@ export interface FileTreeContextValue {
   selectFile: (node: FileNode) => Promise<void>;
   /** Create a new markdown note in the specified directory */
   createNewNote: (parentPath: string) => Promise<void>;
+  /** Create a new note in the specified directory from a named template */
+  createNoteFromTemplate: (parentPath: string, template: string) => Promise<void>;
   /** Create a new folder in the specified directory */
   createNewFolder: (parentPath: string) => Promise<void>;
   /** Delete a file or directory at the specified path */
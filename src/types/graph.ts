@@ -0,0 +1,57 @@
+/**
+ * Graph view type definitions
+ */
+
+/** What a graph node stands for */
+export type GraphNodeKind = "note" | "tag" | "attachment";
+
+/**
+ * A node in the vault graph
+ */
+export interface GraphNode {
+  /** Absolute path for notes and attachments, `#name` for tags */
+  id: string;
+  kind: GraphNodeKind;
+  /** Display name: file stem for notes, file name for attachments, `#name` for tags */
+  label: string;
+  /** Sum of the weights of all edges touching the node, for sizing */
+  weight: number;
+}
+
+/**
+ * A weighted edge from a note to a note, attachment or tag
+ */
+export interface GraphEdge {
+  /** Id of the linking note */
+  source: string;
+  /** Id of the linked note, attachment or tag */
+  target: string;
+  /** Number of links (or tag occurrences) the edge stands for */
+  weight: number;
+}
+
+/**
+ * Nodes and edges returned by get_graph_data
+ */
+export interface GraphData {
+  nodes: GraphNode[];
+  edges: GraphEdge[];
+}
+
+/**
+ * Which part of the vault to draw
+ */
+export interface GraphOptions {
+  /** Absolute path of the note to center on; enables local mode */
+  focus?: string;
+  /** Hops from the focus note in local mode (defaults to 1) */
+  depth?: number;
+  /** Only notes inside this folder */
+  folder?: string;
+  /** Only notes carrying this tag or a tag nested below it */
+  tag?: string;
+  /** Add tag nodes linked to the notes carrying them */
+  includeTags?: boolean;
+  /** Add nodes for linked and embedded attachments */
+  includeAttachments?: boolean;
+}
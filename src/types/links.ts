@@ -0,0 +1,150 @@
+/**
+ * Link index type definitions
+ */
+
+import type { CommandError } from './errors';
+
+/**
+ * Syntax a link is written in
+ */
+export type LinkKind = "wiki" | "markdown";
+
+/**
+ * A link between notes, with the note it is written in and the file it points at
+ */
+export interface LinkReference {
+  /** Absolute path of the note containing the link */
+  source: string;
+  /** Absolute path the link resolves to, null if it points at nothing */
+  resolved: string | null;
+  /** Link syntax */
+  kind: LinkKind;
+  /** Whether the link is an embed (`![[...]]` or `![...](...)`) */
+  embed: boolean;
+  /** Target as written, without the fragment */
+  target: string;
+  /** Heading or `^block` after the `#` */
+  fragment: string | null;
+  /** Wiki alias or markdown label */
+  label: string | null;
+  /** 1-based line number in the source note */
+  line: number;
+  /** 1-based column of the link start */
+  column: number;
+  /** Text of the line the link is on, clipped around the link */
+  context: string;
+}
+
+/**
+ * What a broken link was meant to point at
+ */
+export type MissingKind = "note" | "attachment" | "file";
+
+/**
+ * A link that resolves to nothing, as reported by the link check
+ */
+export interface BrokenLink extends Omit<LinkReference, "resolved"> {
+  /** What the link was meant to point at */
+  missing: MissingKind;
+  /** Absolute path of the most similar existing file, if any */
+  suggestion: string | null;
+  /** Link target that would point at the suggestion, in the link's own style */
+  suggestedTarget: string | null;
+}
+
+/**
+ * A note whose links were rewritten
+ */
+export interface UpdatedFile {
+  /** Absolute path of the note */
+  path: string;
+  /** Number of links changed in the note */
+  links: number;
+}
+
+/**
+ * Notes changed by a link update
+ */
+export interface LinkUpdateReport {
+  /** Notes that were rewritten */
+  updated: UpdatedFile[];
+  /** Notes that needed changes but could not be written */
+  failed: CommandError[];
+}
+
+/**
+ * A heading renamed in a note while links in other notes still use the old text
+ */
+export interface HeadingRename {
+  /** Heading text the links use */
+  from: string;
+  /** Heading text now in the note */
+  to: string;
+  /** Number of links in other notes pointing at the old heading */
+  links: number;
+}
+
+/**
+ * Plain-text occurrence of a note's title or alias that isn't linked yet
+ */
+export interface Mention {
+  /** Absolute path of the note containing the mention */
+  source: string;
+  /** Matched text, as written */
+  text: string;
+  /** 1-based line number in the source note */
+  line: number;
+  /** 1-based column of the match */
+  column: number;
+  /** Text of the line the mention is on, clipped around the match */
+  context: string;
+}
+
+/**
+ * Why a link was left in its original syntax by a conversion
+ */
+export type SkipReason = "unresolved" | "unrepresentable";
+
+/**
+ * A link converted between wiki and markdown syntax
+ */
+export interface ConvertedLink {
+  /** Absolute path of the note containing the link */
+  source: string;
+  /** 1-based line number */
+  line: number;
+  /** 1-based column of the link start */
+  column: number;
+  /** Link as written before the conversion */
+  before: string;
+  /** Link as written after the conversion */
+  after: string;
+}
+
+/**
+ * A link a conversion left as written
+ */
+export interface SkippedLink {
+  /** Absolute path of the note containing the link */
+  source: string;
+  /** 1-based line number */
+  line: number;
+  /** 1-based column of the link start */
+  column: number;
+  /** Link as written */
+  text: string;
+  /** Why the link was not converted */
+  reason: SkipReason;
+}
+
+/**
+ * Outcome of converting links between wiki and markdown syntax
+ */
+export interface LinkConversionReport extends LinkUpdateReport {
+  /** Whether this was only a preview and nothing was written */
+  dryRun: boolean;
+  /** Links converted, or that would be converted in a dry run */
+  converted: ConvertedLink[];
+  /** Links left as written */
+  skipped: SkippedLink[];
+}
@@ -0,0 +1,35 @@
+/**
+ * Note outline and statistics type definitions
+ */
+
+/**
+ * A heading and the headings nested below it
+ */
+export interface OutlineHeading {
+  /** Heading text without markup */
+  text: string;
+  /** 1 to 6 */
+  level: number;
+  /** 1-based line of the heading */
+  line: number;
+  /** Last line of the section, before the next heading of the same or a higher level */
+  endLine: number;
+  children: OutlineHeading[];
+}
+
+/**
+ * Heading tree and statistics of a note.
+ * Counts leave out frontmatter, headings and code.
+ */
+export interface NoteOutline {
+  headings: OutlineHeading[];
+  words: number;
+  /** Characters of text, spaces included but markup left out */
+  characters: number;
+  /** Markdown, wiki and autolinks; embeds are not counted */
+  links: number;
+  tasks: number;
+  completedTasks: number;
+  /** Estimated reading time in whole minutes, rounded up */
+  readingMinutes: number;
+}
@@ -37,6 +37,8 @@ export interface FileOpenMetrics {
 export interface ReadFileResponse {
   content: string;
   duration_ms: number;
+  /** Sequence the next write of this file must exceed */
+  seq: number;
 }
 
 /**
@@ -0,0 +1,21 @@
+/**
+ * Periodic (daily, weekly, monthly) note type definitions
+ */
+
+/**
+ * Span of time a periodic note covers
+ */
+export type Period = "daily" | "weekly" | "monthly";
+
+/**
+ * Where the notes of a period go and how they are named
+ */
+export interface PeriodicNoteConfig {
+  /** Folder relative to the vault root, empty for the root itself */
+  folder: string;
+  /**
+   * Moment-style file name format such as `YYYY-MM-DD` or `gggg-[W]ww`.
+   * `/` starts a subfolder; text in `[brackets]` is kept as is.
+   */
+  format: string;
+}
@@ -0,0 +1,15 @@
+/**
+ * Note preview type definitions
+ */
+
+/**
+ * Title and plain-text excerpt of a note, for hover cards and search results
+ */
+export interface NotePreview {
+  /** Path as it was requested */
+  path: string;
+  /** The `title` property, else the first H1, else the file name */
+  title: string;
+  /** Text without markdown syntax, cut at a word boundary */
+  excerpt: string;
+}
@@ -0,0 +1,66 @@
+/**
+ * Frontmatter property type definitions
+ */
+
+/** Type of a property value */
+export type PropertyType =
+  | "text"
+  | "number"
+  | "checkbox"
+  | "date"
+  | "date_time"
+  | "list"
+  | "object"
+  | "empty";
+
+/** A property value as parsed from YAML or TOML frontmatter */
+export type PropertyValue =
+  | string
+  | number
+  | boolean
+  | null
+  | PropertyValue[]
+  | { [key: string]: PropertyValue };
+
+/**
+ * A property key used anywhere in the vault
+ */
+export interface PropertyKey {
+  /** Key as first written; keys differing only in case are merged */
+  key: string;
+  /** Value types seen for the key, most common first */
+  types: PropertyType[];
+  /** Number of notes with the key */
+  notes: number;
+}
+
+/**
+ * A property of a single note
+ */
+export interface NoteProperty {
+  key: string;
+  value: PropertyValue;
+  type: PropertyType;
+}
+
+/**
+ * What a property value has to match.
+ * With `value` the property must equal it (lists must contain it); with `min`
+ * and/or `max` it must lie in the inclusive range. Dates are `YYYY-MM-DD` strings.
+ */
+export interface PropertyQuery {
+  value?: PropertyValue;
+  min?: PropertyValue;
+  max?: PropertyValue;
+}
+
+/**
+ * A note whose property matched a query
+ */
+export interface PropertyMatch {
+  /** Absolute path of the note */
+  path: string;
+  /** Key as written in the note */
+  key: string;
+  value: PropertyValue;
+}
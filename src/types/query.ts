@@ -0,0 +1,24 @@
+/**
+ * Note query type definitions
+ */
+
+import type { PropertyValue } from './properties';
+
+/**
+ * A note matched by a query
+ */
+export interface QueryRow {
+  /** Absolute path of the note */
+  path: string;
+  /** Value per column, null where the note has none */
+  values: PropertyValue[];
+}
+
+/**
+ * Rows a query produced, ready to render as a table
+ */
+export interface QueryResult {
+  /** Column labels of a `TABLE` query, empty for `LIST` */
+  columns: string[];
+  rows: QueryRow[];
+}
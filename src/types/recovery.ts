@@ -0,0 +1,17 @@
+/**
+ * Crash recovery type definitions
+ */
+
+/**
+ * An editor buffer checkpoint that is newer than the note on disk
+ */
+export interface RecoverableBuffer {
+  /** Absolute path of the note */
+  path: string;
+  /** Buffer content at the time of the checkpoint */
+  content: string;
+  /** Checkpoint time in milliseconds since the epoch */
+  savedAt: number;
+  /** Modification time of the note on disk, null if the note no longer exists */
+  fileModifiedAt: number | null;
+}
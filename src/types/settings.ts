@@ -2,6 +2,8 @@
  * Settings type definitions for the application settings system
  */
 
+import type { Period, PeriodicNoteConfig } from './periodic';
+
 /**
  * Represents the application settings
  */
@@ -14,6 +16,12 @@ export interface Settings {
   autoCheckUpdates: boolean;
   /** Whether developer mode is enabled */
   devMode: boolean;
+  /** Whether to update `#heading` links in other notes without asking when a heading is renamed */
+  autoUpdateHeadingLinks: boolean;
+  /** Folder and file name format of daily, weekly and monthly notes */
+  periodicNotes: Record<Period, PeriodicNoteConfig>;
+  /** Vault folder holding note templates, relative to the vault root */
+  templatesFolder: string;
 }
 
 /**
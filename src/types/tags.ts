@@ -0,0 +1,51 @@
+/**
+ * Tag type definitions
+ */
+
+/**
+ * A `#tag` in the body of a note, or an entry of its `tags` property
+ */
+export interface TagRef {
+  /** Tag name without the `#`, as written */
+  name: string;
+  /** 1-based line number */
+  line: number;
+  /** 1-based column of the `#`, or of the name in frontmatter */
+  column: number;
+  /** Whether the tag comes from the frontmatter rather than the body */
+  frontmatter: boolean;
+}
+
+/**
+ * A level of the tag hierarchy
+ */
+export interface TagNode {
+  /** Last segment of the tag, e.g. `alpha` */
+  name: string;
+  /** Full tag without the `#`, e.g. `project/alpha` */
+  tag: string;
+  /** Notes carrying exactly this tag */
+  notes: number;
+  /** Notes carrying this tag or one nested below it */
+  total: number;
+  children: TagNode[];
+}
+
+/**
+ * A note carrying a tag, with where the tag occurs
+ */
+export interface TaggedNote {
+  /** Absolute path of the note */
+  path: string;
+  tags: TagRef[];
+}
+
+/**
+ * A note whose tags were renamed
+ */
+export interface TagUpdate {
+  /** Absolute path of the note */
+  path: string;
+  /** Number of tags changed in the note */
+  tags: number;
+}
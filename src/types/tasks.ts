@@ -0,0 +1,45 @@
+/**
+ * Task (checkbox list item) type definitions
+ */
+
+/**
+ * State of a checkbox: `[ ]`, `[/]`, `[-]` or `[x]`
+ */
+export type TaskStatus = "todo" | "in_progress" | "cancelled" | "done";
+
+/**
+ * Priority from the emoji markers or a `[priority:: ...]` field
+ */
+export type TaskPriority = "highest" | "high" | "medium" | "low" | "lowest";
+
+/**
+ * A checkbox list item and the note it is in
+ */
+export interface TaskItem {
+  /** Absolute path of the note */
+  path: string;
+  /** Text after the checkbox, as written */
+  text: string;
+  status: TaskStatus;
+  /** 1-based line number */
+  line: number;
+  /** Nearest heading above the task */
+  heading: string | null;
+  /** Due date as `YYYY-MM-DD` */
+  due: string | null;
+  priority: TaskPriority | null;
+}
+
+/**
+ * Which tasks to return; every field is optional
+ */
+export interface TaskQuery {
+  /** Only tasks in one of these states */
+  status?: TaskStatus[];
+  /** Only tasks in notes below this folder */
+  folder?: string;
+  /** Only tasks due on or after this date (`YYYY-MM-DD`) */
+  dueFrom?: string;
+  /** Only tasks due on or before this date (`YYYY-MM-DD`) */
+  dueTo?: string;
+}
@@ -0,0 +1,15 @@
+/**
+ * Note template type definitions
+ */
+
+/**
+ * A note template in the vault's templates folder
+ */
+export interface NoteTemplate {
+  /** File name without `.md` */
+  name: string;
+  /** Absolute path of the template file */
+  path: string;
+  /** Folder the template is for, relative to the vault root; empty for every folder */
+  folder: string;
+}
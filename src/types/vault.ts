@@ -24,6 +24,37 @@ export interface VaultStore {
   vaults: Vault[];
 }
 
+/**
+ * Identity of the Yana instance holding a vault lock
+ */
+export interface VaultLockOwner {
+  /** Process ID of the owning instance */
+  pid: number;
+  /** Host name the owning instance runs on */
+  host: string;
+  /** Unique ID of the owning instance */
+  instanceId: string;
+  /** When the lock was taken, in milliseconds since the epoch */
+  acquiredAt: number;
+}
+
+/**
+ * Lock state of a vault from this instance's point of view
+ * - free: nobody holds the lock
+ * - ours: this instance holds the lock
+ * - held: another live instance holds the lock
+ * - stale: the lock was left behind by a process that is no longer running
+ */
+export type VaultLockState = "free" | "ours" | "held" | "stale";
+
+/**
+ * Result of a vault lock query or acquisition
+ */
+export interface VaultLockStatus {
+  state: VaultLockState;
+  owner: VaultLockOwner | null;
+}
+
 /**
  * Context value providing vault state and operations to components
  */
@@ -32,6 +63,16 @@ export interface VaultContextValue {
   vaults: Vault[];
   /** The currently active vault (default vault) */
   currentVault: Vault | null;
+  /**
+   * Lock status of the current vault; "held" means it is open in another
+   * instance, and the backend refuses changes to it until the lock is ours
+   */
+  vaultLock: VaultLockStatus | null;
+  /**
+   * Increases each time the user grants access to the current vault again,
+   * so anything that failed to load it can retry
+   */
+  grantVersion: number;
   /** Whether vault operations are in progress */
   isLoading: boolean;
   /** Error state from vault operations */